@@ -2,15 +2,17 @@ use crate::views::commander::commander_ui::Commander;
 use crate::views::file_explorer::file_explorer_ui::{
     read_folder_as_items, ExplorerItem, FileExplorer, FolderState,
 };
+use crate::views::quick_open::quick_open_ui::QuickOpen;
 use crate::views::search::search_ui::Search;
 use crate::Args;
 use crate::{
     components::*,
     fs::{FSLocal, FSTransport},
+    recent_commands::RecentCommands,
     state::EditorCommands,
     views::panels::tabs::welcome::WelcomeTab,
 };
-use crate::{global_defaults::GlobalDefaults, state::KeyboardShortcuts};
+use crate::{global_defaults::GlobalDefaults, state::Keybindings};
 use crate::{hooks::*, settings::watch_settings};
 use crate::{utils::*, views::panels::tabs::editor::EditorTab};
 use dioxus_clipboard::prelude::use_clipboard;
@@ -89,41 +91,49 @@ pub fn App() -> Element {
         });
     });
 
-    use_hook(|| {
-        spawn(async move {
-            let res = watch_settings(radio_app_state).await;
-            if res.is_none() {
-                info!("Failed to watch the settings in background.");
-            }
-        })
-    });
-
     // Initialize the Commands
-    let mut editor_commands = use_hook(|| Signal::new(EditorCommands::default()));
+    let mut editor_commands =
+        use_hook(|| Signal::new(EditorCommands::new(radio_app_state, RecentCommands::load())));
+    // The editors run the commands requested by Vim's ex commands
+    use_context_provider(|| editor_commands);
 
-    // Initialize the Shorcuts
-    let mut keyboard_shorcuts = use_hook(|| Signal::new(KeyboardShortcuts::default()));
+    // Initialize the Keybindings
+    let mut keybindings = use_hook(|| Signal::new(Keybindings::default()));
+    // The editors leave out the bound keys, the Commander and the keybindings tab show them
+    use_context_provider(|| keybindings);
 
-    // Register Commands and Shortcuts
+    // Register Commands and Keybindings
     #[allow(clippy::explicit_auto_deref)]
     use_hook(|| {
         GlobalDefaults::init(
-            &mut *keyboard_shorcuts.write(),
+            &mut *keybindings.write(),
             &mut *editor_commands.write(),
             radio_app_state,
         );
         EditorTab::init(
-            &mut *keyboard_shorcuts.write(),
+            &mut *keybindings.write(),
             &mut *editor_commands.write(),
             radio_app_state,
         );
+        keybindings
+            .write()
+            .update(&radio_app_state.read(), &editor_commands.read());
+    });
+
+    use_hook(|| {
+        spawn(async move {
+            let res = watch_settings(radio_app_state, keybindings, editor_commands).await;
+            if res.is_none() {
+                info!("Failed to watch the settings in background.");
+            }
+        })
     });
 
-    // Trigger Shortcuts
+    // Trigger Keybindings
     let onglobalkeydown = move |e: KeyboardEvent| {
-        keyboard_shorcuts
+        keybindings
             .write()
-            .run(&e.data, &mut editor_commands.write(), radio_app_state);
+            .run(&e.data, &editor_commands.read(), radio_app_state);
     };
 
     let focused_view = radio_app_state.read().focused_view;
@@ -140,10 +150,13 @@ pub fn App() -> Element {
             onglobalkeydown,
             if focused_view == EditorView::Commander {
                 Commander {
-                    editor_commands
+                    editor_commands,
+                    keybindings
                 }
             } else if focused_view == EditorView::Search {
                 Search { }
+            } else if focused_view == EditorView::QuickOpen {
+                QuickOpen { }
             }
             rect {
                 height: "calc(100% - 35)",
@@ -1,4 +1,5 @@
 use std::ops::Range;
+use std::rc::Rc;
 
 use freya::prelude::*;
 use freya::prelude::{dioxus_elements, use_applied_theme};
@@ -50,6 +51,9 @@ pub struct EditorScrollViewProps<
 > {
     length: usize,
     item_size: f32,
+    /// Row where each item starts, followed by the total of rows, when items take multiple rows.
+    #[props(default)]
+    row_offsets: Option<Rc<Vec<usize>>>,
     #[props(default = "100%".to_string(), into)]
     pub height: String,
     #[props(default = "100%".to_string(), into)]
@@ -74,6 +78,7 @@ impl<BuilderArgs: Clone + PartialEq, Builder: Clone + Fn(usize, &BuilderArgs) ->
     fn eq(&self, other: &Self) -> bool {
         self.length == other.length
             && self.item_size == other.item_size
+            && self.row_offsets == other.row_offsets
             && self.width == other.width
             && self.height == other.height
             && self.padding == other.padding
@@ -106,6 +111,16 @@ fn get_render_range(
     render_index_start as usize..(render_index_end as usize)
 }
 
+/// Items that take part of the given rows.
+fn get_items_in_rows(row_offsets: &[usize], rows: Range<usize>) -> Range<usize> {
+    let items_len = row_offsets.len() - 1;
+    let start = row_offsets
+        .partition_point(|row| *row <= rows.start)
+        .saturating_sub(1);
+    let end = row_offsets.partition_point(|row| *row < rows.end);
+    start.min(items_len)..end.min(items_len)
+}
+
 /// A controlled ScrollView with virtual scrolling.
 #[allow(non_snake_case)]
 pub fn EditorScrollView<
@@ -115,6 +130,7 @@ pub fn EditorScrollView<
     EditorScrollViewProps {
         length,
         item_size,
+        row_offsets,
         height,
         width,
         padding,
@@ -140,7 +156,11 @@ pub fn EditorScrollView<
         }
     });
 
-    let inner_size = item_size + (item_size * length as f32);
+    let rows_len = row_offsets
+        .as_ref()
+        .map(|row_offsets| row_offsets[length])
+        .unwrap_or(length);
+    let inner_size = item_size + (item_size * rows_len as f32);
 
     let vertical_scrollbar_is_visible =
         is_scrollbar_visible(show_scrollbar, inner_size, size.area.height());
@@ -261,8 +281,16 @@ pub fn EditorScrollView<
         size.area.height(),
         corrected_scrolled_y,
         item_size,
-        length as f32,
+        rows_len as f32,
     );
+    let (render_range, first_row) = match &row_offsets {
+        Some(row_offsets) => {
+            let render_range = get_items_in_rows(row_offsets, render_range);
+            let first_row = row_offsets[render_range.start];
+            (render_range, first_row)
+        }
+        None => (render_range.clone(), render_range.start),
+    };
 
     let children = use_computed(
         &(render_range, builder_args),
@@ -284,8 +312,7 @@ pub fn EditorScrollView<
         .map(|f| f.0 == Axis::Y)
         .unwrap_or_default();
 
-    let offset_y_min = (-corrected_scrolled_y / item_size).floor() * item_size;
-    let offset_y = -corrected_scrolled_y - offset_y_min;
+    let offset_y = -corrected_scrolled_y - first_row as f32 * item_size;
 
     rsx!(
         rect {
@@ -3,7 +3,7 @@ use dioxus_radio::prelude::use_radio;
 use freya::prelude::*;
 
 #[component]
-pub fn Overlay(children: Element) -> Element {
+pub fn Overlay(children: Element, #[props(default = 500.0)] width: f32) -> Element {
     let mut radio_app_state = use_radio(Channel::Global);
 
     let onglobalmousedown = move |_| {
@@ -29,7 +29,7 @@ pub fn Overlay(children: Element) -> Element {
                     onmousedown: |e| {
                         e.stop_propagation();
                     },
-                    width: "500",
+                    width: "{width}",
                     padding: "5",
                     {children}
                 }
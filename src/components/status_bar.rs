@@ -36,7 +36,12 @@ pub fn StatusBar(props: StatusBarProps) -> Element {
             .map(|editor_tab| {
                 (
                     editor_tab.editor.cursor_row_and_col(),
-                    editor_tab.editor.editor_type(),
+                    editor_tab.editor.language_id(),
+                    editor_tab.editor.indentation,
+                    editor_tab.editor.encoding,
+                    editor_tab.editor.line_ending,
+                    editor_tab.editor.vim.as_ref().map(|vim| vim.to_string()),
+                    editor_tab.editor.message.clone(),
                 )
             })
     } else {
@@ -84,7 +89,22 @@ pub fn StatusBar(props: StatusBarProps) -> Element {
                 width: "50%",
                 direction: "horizontal",
                 main_align: "end",
-                if let Some(((row, col), editor_type)) = tab_data {
+                if let Some(((row, col), language_id, indentation, encoding, line_ending, vim, message)) = tab_data {
+                    if let Some(message) = message {
+                        StatusBarItem {
+                            label {
+                                color: "rgb(255, 120, 100)",
+                                "{message}"
+                            }
+                        }
+                    }
+                    if let Some(vim) = vim {
+                        StatusBarItem {
+                            label {
+                                "{vim}"
+                            }
+                        }
+                    }
                     StatusBarItem {
                         label {
                             "Ln {row + 1}, Col {col + 1}"
@@ -92,7 +112,22 @@ pub fn StatusBar(props: StatusBarProps) -> Element {
                     }
                     StatusBarItem {
                         label {
-                            "{editor_type.language_id()}"
+                            "{indentation}"
+                        }
+                    }
+                    StatusBarItem {
+                        label {
+                            "{encoding}"
+                        }
+                    }
+                    StatusBarItem {
+                        label {
+                            "{line_ending}"
+                        }
+                    }
+                    StatusBarItem {
+                        label {
+                            "{language_id}"
                         }
                     }
                 }
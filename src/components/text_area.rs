@@ -4,13 +4,16 @@ use freya::prelude::{keyboard::Key, *};
 #[derive(Props, Clone, PartialEq)]
 pub struct TextAreaProps {
     /// Placerholder text for when there is no text.
-    pub placeholder: &'static str,
+    pub placeholder: String,
     /// Current value of the TextArea
     pub value: String,
     /// Handler for the `onchange` event.
     pub onchange: EventHandler<String>,
     /// Handler for the `onsubmit` event.
     pub onsubmit: EventHandler<String>,
+    /// Focus the TextArea once it's shown.
+    #[props(default = true)]
+    pub auto_focus: bool,
 }
 
 #[allow(non_snake_case)]
@@ -25,7 +28,10 @@ pub fn TextArea(props: TextAreaProps) -> Element {
     let focus = use_focus();
 
     if &props.value != editable.editor().read().rope() {
-        editable.editor_mut().write().set(&props.value);
+        let mut editor = editable.editor_mut().write();
+        editor.set(&props.value);
+        // Keep typing after the new value
+        *editor.cursor_mut() = TextCursor::new(props.value.encode_utf16().count());
     }
 
     let onkeydown = move |e: Event<KeyboardData>| {
@@ -65,7 +71,7 @@ pub fn TextArea(props: TextAreaProps) -> Element {
     } = theme;
 
     let (color, text) = if props.value.is_empty() {
-        ("rgb(210, 210, 210)", props.placeholder)
+        ("rgb(210, 210, 210)", props.placeholder.as_str())
     } else {
         (color.as_ref(), props.value.as_str())
     };
@@ -82,7 +88,7 @@ pub fn TextArea(props: TextAreaProps) -> Element {
             margin: "0 0 2 0",
             a11y_id: focus.attribute(),
             a11y_role: "text-input",
-            a11y_auto_focus: "true",
+            a11y_auto_focus: "{props.auto_focus}",
             onkeydown,
             paragraph {
                 margin: "6 10",
@@ -0,0 +1,153 @@
+use std::{fs::read_to_string, path::Path};
+
+use crate::indentation::Indentation;
+
+/// Indentation properties of an `.editorconfig` file that apply to a file.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct EditorConfig {
+    use_tabs: Option<bool>,
+    indent_size: Option<u8>,
+    tab_width: Option<u8>,
+    /// `indent_size = tab`
+    indent_size_is_tab: bool,
+}
+
+impl EditorConfig {
+    /// Find the `.editorconfig` files from the folder of the given file up to the root,
+    /// or up to the one marked with `root = true`.
+    pub fn for_file(path: &Path) -> Self {
+        let mut config_files = Vec::new();
+
+        for folder in path.ancestors().skip(1) {
+            let config_path = folder.join(".editorconfig");
+            let Ok(content) = read_to_string(&config_path) else {
+                continue;
+            };
+            let is_root = is_root(&content);
+            config_files.push((folder.to_path_buf(), content));
+            if is_root {
+                break;
+            }
+        }
+
+        // Closer files override the further ones
+        let mut config = Self::default();
+        for (folder, content) in config_files.iter().rev() {
+            config.apply(folder, content, path);
+        }
+        config
+    }
+
+    /// Apply the properties from the sections of the file that match the given path.
+    fn apply(&mut self, folder: &Path, content: &str, path: &Path) {
+        let Ok(relative_path) = path.strip_prefix(folder) else {
+            return;
+        };
+        let relative_path = relative_path.to_string_lossy().replace('\\', "/");
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut section_matches = false;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                section_matches = if section.contains('/') {
+                    glob_matches(section.trim_start_matches('/'), &relative_path)
+                } else {
+                    glob_matches(section, &file_name)
+                };
+                continue;
+            }
+
+            if !section_matches {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().to_lowercase();
+
+            match key.trim().to_lowercase().as_str() {
+                "indent_style" => self.use_tabs = Some(value == "tab"),
+                "indent_size" if value == "tab" => self.indent_size_is_tab = true,
+                "indent_size" => {
+                    if let Ok(size) = value.parse() {
+                        self.indent_size = Some(size);
+                        self.indent_size_is_tab = false;
+                    }
+                }
+                "tab_width" => self.tab_width = value.parse().ok(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Override the given indentation with the configured properties.
+    pub fn apply_to(&self, indentation: &mut Indentation) {
+        if let Some(use_tabs) = self.use_tabs {
+            indentation.use_tabs = use_tabs;
+        }
+
+        let size = if indentation.use_tabs {
+            // The width of the tabs is what matters
+            self.tab_width.or(self.indent_size)
+        } else if self.indent_size_is_tab {
+            self.tab_width
+        } else {
+            self.indent_size
+        };
+        if let Some(size) = size.filter(|size| *size > 0) {
+            indentation.size = size;
+        }
+    }
+}
+
+fn is_root(content: &str) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .take_while(|line| !line.starts_with('['))
+        .filter_map(|line| line.split_once('='))
+        .any(|(key, value)| {
+            key.trim().eq_ignore_ascii_case("root") && value.trim().eq_ignore_ascii_case("true")
+        })
+}
+
+/// Match a path with the globs supported by EditorConfig: `*`, `**`, `?` and `{a,b}`.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    if let Some(start) = pattern.find('{') {
+        if let Some(len) = pattern[start..].find('}') {
+            let end = start + len;
+            let (prefix, suffix) = (&pattern[..start], &pattern[end + 1..]);
+            return pattern[start + 1..end]
+                .split(',')
+                .any(|option| glob_matches(&format!("{prefix}{option}{suffix}"), text));
+        }
+    }
+
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let text = text.chars().collect::<Vec<char>>();
+    wildcard_matches(&pattern, &text)
+}
+
+fn wildcard_matches(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| wildcard_matches(rest, &text[i..])),
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|i| *i == 0 || text[i - 1] != '/')
+            .any(|i| wildcard_matches(rest, &text[i..])),
+        ['?', rest @ ..] => {
+            text.first().is_some_and(|ch| *ch != '/') && wildcard_matches(rest, &text[1..])
+        }
+        [ch, rest @ ..] => text.first() == Some(ch) && wildcard_matches(rest, &text[1..]),
+    }
+}
@@ -0,0 +1,194 @@
+use std::{borrow::Cow, fmt::Display};
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF16_BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+/// Chars of the bytes from `0x80` to `0x9F` in Windows-1252, the rest are the same as Latin-1.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Encoding of the content of a file.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Windows1252,
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 with BOM",
+            Self::Utf16Le => "UTF-16 LE",
+            Self::Utf16Be => "UTF-16 BE",
+            Self::Latin1 => "ISO-8859-1",
+            Self::Windows1252 => "Windows-1252",
+        })
+    }
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 6] = [
+        Self::Utf8,
+        Self::Utf8Bom,
+        Self::Utf16Le,
+        Self::Utf16Be,
+        Self::Latin1,
+        Self::Windows1252,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf8Bom => "utf-8-bom",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Latin1 => "iso-8859-1",
+            Self::Windows1252 => "windows-1252",
+        }
+    }
+
+    /// Guess the encoding from the BOM, falling back to Windows-1252 for invalid UTF-8.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&UTF8_BOM) {
+            Self::Utf8Bom
+        } else if bytes.starts_with(&UTF16_LE_BOM) {
+            Self::Utf16Le
+        } else if bytes.starts_with(&UTF16_BE_BOM) {
+            Self::Utf16Be
+        } else if std::str::from_utf8(bytes).is_ok() {
+            Self::Utf8
+        } else {
+            Self::Windows1252
+        }
+    }
+
+    /// Decode the bytes, the BOM is not part of the text.
+    /// Invalid sequences are replaced with `U+FFFD`.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => {
+                let bytes = bytes.strip_prefix(&UTF8_BOM).unwrap_or(bytes);
+                String::from_utf8_lossy(bytes).into_owned()
+            }
+            Self::Utf16Le | Self::Utf16Be => {
+                let (bom, from_bytes): (_, fn([u8; 2]) -> u16) = if *self == Self::Utf16Le {
+                    (UTF16_LE_BOM, u16::from_le_bytes)
+                } else {
+                    (UTF16_BE_BOM, u16::from_be_bytes)
+                };
+                let bytes = bytes.strip_prefix(&bom).unwrap_or(bytes);
+                let units = bytes
+                    .chunks_exact(2)
+                    .map(|unit| from_bytes([unit[0], unit[1]]))
+                    .collect::<Vec<u16>>();
+                String::from_utf16_lossy(&units)
+            }
+            Self::Latin1 => bytes.iter().map(|byte| char::from(*byte)).collect(),
+            Self::Windows1252 => bytes
+                .iter()
+                .map(|byte| match byte {
+                    0x80..=0x9F => WINDOWS_1252[(byte - 0x80) as usize],
+                    _ => char::from(*byte),
+                })
+                .collect(),
+        }
+    }
+
+    /// Encode the text, chars that can't be represented are replaced with `?`.
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            Self::Utf8 => text.as_bytes().to_vec(),
+            Self::Utf8Bom => [&UTF8_BOM, text.as_bytes()].concat(),
+            Self::Utf16Le => UTF16_LE_BOM
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect(),
+            Self::Utf16Be => UTF16_BE_BOM
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect(),
+            Self::Latin1 => text
+                .chars()
+                .map(|ch| u8::try_from(ch).unwrap_or(b'?'))
+                .collect(),
+            Self::Windows1252 => text
+                .chars()
+                .map(|ch| {
+                    if let Some(idx) = WINDOWS_1252.iter().position(|c| *c == ch) {
+                        0x80 + idx as u8
+                    } else {
+                        u8::try_from(ch)
+                            .ok()
+                            .filter(|byte| !(0x80..=0x9F).contains(byte))
+                            .unwrap_or(b'?')
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Line breaks used in a text.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    /// Both are used, so they are kept as they are.
+    Mixed,
+}
+
+impl Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Lf => "LF",
+            Self::Crlf => "CRLF",
+            Self::Mixed => "Mixed",
+        })
+    }
+}
+
+impl LineEnding {
+    pub fn detect(text: &str) -> Self {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+
+        match (lf, crlf) {
+            (0, 0) => Self::default(),
+            (_, 0) => Self::Lf,
+            (0, _) => Self::Crlf,
+            _ => Self::Mixed,
+        }
+    }
+
+    /// The line break, if there is only one.
+    pub fn as_str(&self) -> Option<&'static str> {
+        match self {
+            Self::Lf => Some("\n"),
+            Self::Crlf => Some("\r\n"),
+            Self::Mixed => None,
+        }
+    }
+
+    /// Use this line ending in all the lines of the text.
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Lf if text.contains("\r\n") => Cow::Owned(text.replace("\r\n", "\n")),
+            Self::Crlf if LineEnding::detect(text) != Self::Crlf => {
+                Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+            }
+            _ => Cow::Borrowed(text),
+        }
+    }
+}
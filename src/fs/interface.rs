@@ -10,7 +10,7 @@ pub type FSTransport = Arc<Box<dyn FSTransportInterface>>;
 
 #[async_trait]
 pub trait FSReadTransportInterface {
-    async fn read_to_string(&self, path: &Path) -> tokio::io::Result<String>;
+    async fn read(&self, path: &Path) -> tokio::io::Result<Vec<u8>>;
 }
 
 #[async_trait]
@@ -7,8 +7,8 @@ pub struct FSLocal;
 
 #[async_trait]
 impl FSReadTransportInterface for FSLocal {
-    async fn read_to_string(&self, path: &std::path::Path) -> tokio::io::Result<String> {
-        tokio::fs::read_to_string(path).await
+    async fn read(&self, path: &std::path::Path) -> tokio::io::Result<Vec<u8>> {
+        tokio::fs::read(path).await
     }
 }
 
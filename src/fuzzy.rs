@@ -0,0 +1,68 @@
+use nucleo_matcher::{
+    pattern::{CaseMatching, Normalization, Pattern},
+    Config, Matcher, Utf32Str,
+};
+
+/// Fuzzy matcher of a query, like fzf. Matches at the start of words, path segments
+/// and camelCase humps score higher.
+pub struct FuzzyMatcher {
+    matcher: Matcher,
+    pattern: Pattern,
+    buf: Vec<char>,
+}
+
+impl FuzzyMatcher {
+    pub fn new(query: &str) -> Self {
+        Self::with_config(query, Config::DEFAULT)
+    }
+
+    /// Matcher with bonuses for the start of path segments.
+    pub fn for_paths(query: &str) -> Self {
+        Self::with_config(query, Config::DEFAULT.match_paths())
+    }
+
+    fn with_config(query: &str, config: Config) -> Self {
+        Self {
+            matcher: Matcher::new(config),
+            pattern: Pattern::parse(query, CaseMatching::Smart, Normalization::Smart),
+            buf: Vec::new(),
+        }
+    }
+
+    /// Whether the query matches everything.
+    pub fn is_empty(&self) -> bool {
+        self.pattern.atoms.is_empty()
+    }
+
+    /// Score of a text, `None` if it doesn't match. Cheaper than [Self::fuzzy_match].
+    pub fn fuzzy_score(&mut self, text: &str) -> Option<u32> {
+        self.pattern
+            .score(Utf32Str::new(text, &mut self.buf), &mut self.matcher)
+    }
+
+    /// Score of a text and the indexes of its chars that matched, `None` if it doesn't match.
+    pub fn fuzzy_match(&mut self, text: &str) -> Option<(u32, Vec<usize>)> {
+        let mut indices = Vec::new();
+        let score = self.pattern.indices(
+            Utf32Str::new(text, &mut self.buf),
+            &mut self.matcher,
+            &mut indices,
+        )?;
+        indices.sort_unstable();
+        indices.dedup();
+        Some((score, indices.into_iter().map(|idx| idx as usize).collect()))
+    }
+}
+
+/// Pieces of a text and whether their chars matched, given the sorted indexes of the matched chars.
+pub fn matched_pieces(text: &str, indices: &[usize]) -> Vec<(String, bool)> {
+    let mut pieces: Vec<(String, bool)> = Vec::new();
+    for (idx, ch) in text.chars().enumerate() {
+        let is_match = indices.binary_search(&idx).is_ok();
+        match pieces.last_mut() {
+            Some((piece, was_match)) if *was_match == is_match => piece.push(ch),
+            _ => pieces.push((ch.to_string(), is_match)),
+        }
+    }
+    pieces
+}
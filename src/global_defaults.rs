@@ -1,21 +1,33 @@
+use std::path::{Path, PathBuf};
+
 use crate::{
-    state::{Channel, CommandRunContext, EditorCommand, EditorView, Panel, RadioAppState},
-    views::panels::tabs::settings::Settings,
+    state::{
+        AppStateUtils, Channel, CommandArg, CommandChoice, CommandRunContext, EditorCommand,
+        EditorView, Panel, RadioAppState,
+    },
+    views::{
+        commander::path_completion::{base_folder, resolve_path},
+        panels::tabs::{
+            editor::{AppStateEditorUtils, EditorTab},
+            keybindings::KeybindingsTab,
+            settings::Settings,
+        },
+    },
 };
 
 #[allow(non_snake_case)]
 pub mod GlobalDefaults {
-    use freya::events::{Code, Key, KeyboardData, Modifiers};
-
-    use crate::state::{Channel, EditorCommands, EditorView, KeyboardShortcuts, RadioAppState};
+    use crate::state::{EditorCommands, KeybindingContext, Keybindings, RadioAppState};
 
     use super::{
-        ClosePanelCommand, CloseTabCommand, FocusNextPanelCommand, FocusPreviousPanelCommand,
-        OpenSearchCommand, OpenSettingsCommand, SplitPanelCommand, ToggleCommanderCommand,
+        CancelCommand, ClosePanelCommand, CloseTabCommand, FocusNextPanelCommand,
+        FocusPreviousPanelCommand, OpenFileCommand, OpenRecentCommand, OpenSearchCommand,
+        OpenSettingsCommand, QuickOpenCommand, ShowKeybindingsCommand, SplitPanelCommand,
+        ToggleCommanderCommand, ToggleFilesExplorerFocusCommand,
     };
 
     pub fn init(
-        keyboard_shorcuts: &mut KeyboardShortcuts,
+        keybindings: &mut Keybindings,
         commands: &mut EditorCommands,
         radio_app_state: RadioAppState,
     ) {
@@ -23,62 +35,132 @@ pub mod GlobalDefaults {
         commands.register(SplitPanelCommand(radio_app_state));
         commands.register(ClosePanelCommand(radio_app_state));
         commands.register(ToggleCommanderCommand(radio_app_state));
+        commands.register(CancelCommand(radio_app_state));
+        commands.register(ToggleFilesExplorerFocusCommand(radio_app_state));
         commands.register(OpenSettingsCommand(radio_app_state));
+        commands.register(ShowKeybindingsCommand(radio_app_state));
         commands.register(OpenSearchCommand(radio_app_state));
+        commands.register(QuickOpenCommand(radio_app_state));
+        commands.register(OpenRecentCommand(radio_app_state));
+        commands.register(OpenFileCommand(radio_app_state));
         commands.register(CloseTabCommand(radio_app_state));
         commands.register(FocusNextPanelCommand(radio_app_state));
         commands.register(FocusPreviousPanelCommand(radio_app_state));
 
-        // Register Shortcuts
-        keyboard_shorcuts.register(
-            |data: &KeyboardData,
-             commands: &mut EditorCommands,
-             mut radio_app_state: RadioAppState| {
-                let is_pressing_alt = data.modifiers == Modifiers::ALT;
-                let is_pressing_ctrl = data.modifiers == Modifiers::CONTROL;
-
-                match data.code {
-                    // Pressing `Esc`
-                    Code::Escape => {
-                        commands.trigger(ToggleCommanderCommand::id());
-                    }
-                    // Pressing `Alt E`
-                    Code::KeyE if is_pressing_alt => {
-                        let mut app_state = radio_app_state.write_channel(Channel::Global);
-                        if app_state.focused_view() == EditorView::FilesExplorer {
-                            app_state.focus_view(EditorView::Panels)
-                        } else {
-                            app_state.focus_view(EditorView::FilesExplorer)
-                        }
-                    }
-                    // Pressing `Ctrl W`
-                    Code::KeyW if is_pressing_ctrl => {
-                        commands.trigger(CloseTabCommand::id());
-                    }
-                    // Pressing `Alt +`
-                    _ if is_pressing_alt && data.key == Key::Character("+".to_string()) => {
-                        commands.trigger(SplitPanelCommand::id());
-                    }
-                    // Pressing `Alt -`
-                    _ if is_pressing_alt && data.key == Key::Character("-".to_string()) => {
-                        commands.trigger(ClosePanelCommand::id());
-                    }
-                    // Pressing `Alt ArrowRight`
-                    _ if is_pressing_alt && data.key == Key::ArrowRight => {
-                        commands.trigger(FocusNextPanelCommand::id());
-                    }
-                    // Pressing `Alt ArrowLeft`
-                    _ if is_pressing_alt && data.key == Key::ArrowLeft => {
-                        commands.trigger(FocusPreviousPanelCommand::id());
-                    }
-                    _ => return false,
-                }
-                true
-            },
-        );
+        // Register the default Keybindings
+        let any = KeybindingContext::Any;
+        keybindings.register("Escape", CancelCommand::id(), any);
+        keybindings.register("Alt+E", ToggleFilesExplorerFocusCommand::id(), any);
+        keybindings.register("Ctrl+P", QuickOpenCommand::id(), any);
+        keybindings.register("Ctrl+W", CloseTabCommand::id(), any);
+        keybindings.register("Alt++", SplitPanelCommand::id(), any);
+        keybindings.register("Alt+-", ClosePanelCommand::id(), any);
+        keybindings.register("Alt+ArrowRight", FocusNextPanelCommand::id(), any);
+        keybindings.register("Alt+ArrowLeft", FocusPreviousPanelCommand::id(), any);
     }
 }
 
+/// Open a file in the focused panel, as part of the open folder that contains it.
+fn open_file(mut radio_app_state: RadioAppState, path: PathBuf) {
+    let transport = radio_app_state.read().default_transport.clone();
+    let mut app_state = radio_app_state.write_channel(Channel::Global);
+    let root_path = app_state
+        .file_explorer
+        .folders
+        .iter()
+        .map(|folder| folder.path())
+        .find(|folder| path.starts_with(folder))
+        .cloned()
+        .or_else(|| path.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    EditorTab::open_with(
+        radio_app_state,
+        &mut app_state,
+        path,
+        root_path,
+        transport.as_read(),
+    );
+}
+
+/// Open the Commander, or close it if it's open.
+fn toggle_commander(mut radio_app_state: RadioAppState) {
+    let mut app_state = radio_app_state.write_channel(Channel::Global);
+    if app_state.focused_view == EditorView::Commander {
+        app_state.focus_previous_view();
+    } else {
+        app_state.commander_prompt = None;
+        app_state.focus_view(EditorView::Commander);
+    }
+}
+
+/// Close the find bar of the focused editor, if it's shown.
+fn close_find_bar(mut radio_app_state: RadioAppState) -> bool {
+    let Some(tab_id) = radio_app_state.get_active_tab() else {
+        return false;
+    };
+
+    let is_find_open = {
+        let app_state = radio_app_state.read();
+        app_state.focused_view() == EditorView::Panels
+            && app_state
+                .try_editor_tab(tab_id)
+                .is_some_and(|editor_tab| editor_tab.editor.find.is_some())
+    };
+
+    if is_find_open {
+        let mut app_state = radio_app_state.write_channel(Channel::follow_tab(tab_id));
+        app_state.editor_tab_mut(tab_id).editor.close_find();
+    }
+
+    is_find_open
+}
+
+/// Go back to a single cursor in the focused editor, if it has multiple cursors.
+fn clear_extra_cursors(mut radio_app_state: RadioAppState) -> bool {
+    let Some(tab_id) = radio_app_state.get_active_tab() else {
+        return false;
+    };
+
+    let has_extra_cursors = {
+        let app_state = radio_app_state.read();
+        app_state.focused_view() == EditorView::Panels
+            && app_state
+                .try_editor_tab(tab_id)
+                .is_some_and(|editor_tab| editor_tab.editor.has_extra_cursors())
+    };
+
+    if has_extra_cursors {
+        let mut app_state = radio_app_state.write_channel(Channel::follow_tab(tab_id));
+        app_state
+            .editor_tab_mut(tab_id)
+            .editor
+            .clear_extra_cursors();
+    }
+
+    has_extra_cursors
+}
+
+/// Go back to normal mode in the focused editor, if it uses Vim and is in another mode.
+fn vim_escape(mut radio_app_state: RadioAppState) -> bool {
+    let Some(tab_id) = radio_app_state.get_active_tab() else {
+        return false;
+    };
+
+    let uses_vim = {
+        let app_state = radio_app_state.read();
+        app_state.focused_view() == EditorView::Panels
+            && app_state
+                .try_editor_tab(tab_id)
+                .is_some_and(|editor_tab| editor_tab.editor.vim.is_some())
+    };
+    if !uses_vim {
+        return false;
+    }
+
+    let mut app_state = radio_app_state.write_channel(Channel::follow_tab(tab_id));
+    app_state.editor_tab_mut(tab_id).editor.vim_escape()
+}
+
 #[derive(Clone)]
 pub struct SplitPanelCommand(pub RadioAppState);
 
@@ -89,10 +171,6 @@ impl SplitPanelCommand {
 }
 
 impl EditorCommand for SplitPanelCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(input)
-    }
-
     fn id(&self) -> &str {
         Self::id()
     }
@@ -121,10 +199,6 @@ impl ClosePanelCommand {
 }
 
 impl EditorCommand for ClosePanelCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(input)
-    }
-
     fn id(&self) -> &str {
         Self::id()
     }
@@ -155,7 +229,31 @@ impl EditorCommand for ToggleCommanderCommand {
         false
     }
 
-    fn matches(&self, _input: &str) -> bool {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Toggle Commander"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        toggle_commander(self.0);
+    }
+}
+
+#[derive(Clone)]
+pub struct CancelCommand(pub RadioAppState);
+
+impl CancelCommand {
+    pub fn id() -> &'static str {
+        "cancel"
+    }
+}
+
+impl EditorCommand for CancelCommand {
+    fn is_visible(&self) -> bool {
+        // It only makes sense with its keybinding.
         false
     }
 
@@ -164,16 +262,49 @@ impl EditorCommand for ToggleCommanderCommand {
     }
 
     fn text(&self) -> &str {
-        "Toggle Commander"
+        "Cancel"
     }
 
     fn run(&self, _ctx: &mut CommandRunContext) {
+        let radio_app_state = self.0;
+        // Close the find bar, go back to a single cursor or to Vim's normal mode
+        // before toggling the Commander
+        if !close_find_bar(radio_app_state)
+            && !clear_extra_cursors(radio_app_state)
+            && !vim_escape(radio_app_state)
+        {
+            toggle_commander(radio_app_state);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ToggleFilesExplorerFocusCommand(pub RadioAppState);
+
+impl ToggleFilesExplorerFocusCommand {
+    pub fn id() -> &'static str {
+        "toggle-files-explorer-focus"
+    }
+}
+
+impl EditorCommand for ToggleFilesExplorerFocusCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Toggle Focus of the Files Explorer"
+    }
+
+    fn run(&self, ctx: &mut CommandRunContext) {
+        ctx.focus_previous_view = false;
+
         let mut radio_app_state = self.0;
         let mut app_state = radio_app_state.write_channel(Channel::Global);
-        if app_state.focused_view == EditorView::Commander {
-            app_state.focus_previous_view();
+        if app_state.focused_view() == EditorView::FilesExplorer {
+            app_state.focus_view(EditorView::Panels)
         } else {
-            app_state.focus_view(EditorView::Commander);
+            app_state.focus_view(EditorView::FilesExplorer)
         }
     }
 }
@@ -188,10 +319,6 @@ impl OpenSettingsCommand {
 }
 
 impl EditorCommand for OpenSettingsCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(&input.to_lowercase())
-    }
-
     fn id(&self) -> &str {
         Self::id()
     }
@@ -207,6 +334,31 @@ impl EditorCommand for OpenSettingsCommand {
     }
 }
 
+#[derive(Clone)]
+pub struct ShowKeybindingsCommand(pub RadioAppState);
+
+impl ShowKeybindingsCommand {
+    pub fn id() -> &'static str {
+        "show-keybindings"
+    }
+}
+
+impl EditorCommand for ShowKeybindingsCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Show Keybindings"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        let mut radio_app_state = self.0;
+        let mut app_state = radio_app_state.write_channel(Channel::Global);
+        KeybindingsTab::open_with(&mut app_state);
+    }
+}
+
 #[derive(Clone)]
 pub struct OpenSearchCommand(pub RadioAppState);
 
@@ -217,10 +369,6 @@ impl OpenSearchCommand {
 }
 
 impl EditorCommand for OpenSearchCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(&input.to_lowercase())
-    }
-
     fn id(&self) -> &str {
         Self::id()
     }
@@ -238,6 +386,102 @@ impl EditorCommand for OpenSearchCommand {
     }
 }
 
+#[derive(Clone)]
+pub struct QuickOpenCommand(pub RadioAppState);
+
+impl QuickOpenCommand {
+    pub fn id() -> &'static str {
+        "quick-open"
+    }
+}
+
+impl EditorCommand for QuickOpenCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Quick Open"
+    }
+
+    fn run(&self, ctx: &mut CommandRunContext) {
+        ctx.focus_previous_view = false;
+
+        let mut radio_app_state = self.0;
+        let mut app_state = radio_app_state.write_channel(Channel::Global);
+        app_state.focus_view(EditorView::QuickOpen);
+    }
+}
+
+#[derive(Clone)]
+pub struct OpenRecentCommand(pub RadioAppState);
+
+impl OpenRecentCommand {
+    pub fn id() -> &'static str {
+        "open-recent"
+    }
+}
+
+impl EditorCommand for OpenRecentCommand {
+    fn args(&self) -> Vec<CommandArg> {
+        let recent_files = self
+            .0
+            .read()
+            .recent_files
+            .iter()
+            .map(|path| CommandChoice::new(path.display(), path.display()))
+            .collect();
+        vec![CommandArg::choice("Recent file", recent_files)]
+    }
+
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Open Recent"
+    }
+
+    fn run(&self, ctx: &mut CommandRunContext) {
+        if let Some(path) = ctx.arg(0) {
+            open_file(self.0, PathBuf::from(path));
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct OpenFileCommand(pub RadioAppState);
+
+impl OpenFileCommand {
+    pub fn id() -> &'static str {
+        "open-file"
+    }
+}
+
+impl EditorCommand for OpenFileCommand {
+    fn args(&self) -> Vec<CommandArg> {
+        vec![CommandArg::file_path("Path of the file")]
+    }
+
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Open File"
+    }
+
+    fn run(&self, ctx: &mut CommandRunContext) {
+        let Some(path) = ctx.arg(0) else {
+            return;
+        };
+        let path = resolve_path(&base_folder(&self.0.read()), path);
+        if path.is_file() {
+            open_file(self.0, path);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct CloseTabCommand(pub RadioAppState);
 
@@ -248,10 +492,6 @@ impl CloseTabCommand {
 }
 
 impl EditorCommand for CloseTabCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(&input.to_lowercase())
-    }
-
     fn id(&self) -> &str {
         Self::id()
     }
@@ -277,10 +517,6 @@ impl FocusNextPanelCommand {
 }
 
 impl EditorCommand for FocusNextPanelCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(&input.to_lowercase())
-    }
-
     fn id(&self) -> &str {
         Self::id()
     }
@@ -306,10 +542,6 @@ impl FocusPreviousPanelCommand {
 }
 
 impl EditorCommand for FocusPreviousPanelCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(&input.to_lowercase())
-    }
-
     fn id(&self) -> &str {
         Self::id()
     }
@@ -0,0 +1,291 @@
+use std::{fmt::Write, io, path::Path};
+
+use clap::ValueEnum;
+use ropey::Rope;
+
+use crate::encoding::Encoding;
+use crate::languages::LanguageConfig;
+use crate::lsp::LanguageId;
+use crate::parser::{parse, SyntaxBlocks, SyntaxType, TextNode};
+use crate::state::EditorSettings;
+
+/// Same background as the editor.
+const BACKGROUND: Color = Color {
+    r: 29,
+    g: 32,
+    b: 33,
+    a: 1.0,
+};
+
+/// Approximated width of a monospace glyph relative to the font size.
+const GLYPH_WIDTH: f32 = 0.6;
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum HighlightFormat {
+    #[default]
+    Html,
+    Ansi,
+    Svg,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: f32,
+}
+
+impl Color {
+    /// Parse the colors used by [`SyntaxType`], e.g `rgb(223, 191, 142, 0.2)` or `gray`.
+    fn parse(color: &str) -> Option<Self> {
+        if color == "gray" {
+            return Some(Self {
+                r: 128,
+                g: 128,
+                b: 128,
+                a: 1.0,
+            });
+        }
+
+        let channels = color.strip_prefix("rgb(")?.strip_suffix(')')?;
+        let mut channels = channels.split(',').map(str::trim);
+        let r = channels.next()?.parse().ok()?;
+        let g = channels.next()?.parse().ok()?;
+        let b = channels.next()?.parse().ok()?;
+        let a = channels.next().map(str::parse).unwrap_or(Ok(1.0)).ok()?;
+
+        Some(Self { r, g, b, a })
+    }
+
+    /// Blend the color with the background, for outputs without transparency.
+    fn opaque(&self) -> Self {
+        let blend = |fg: u8, bg: u8| (fg as f32 * self.a + bg as f32 * (1.0 - self.a)) as u8;
+        Self {
+            r: blend(self.r, BACKGROUND.r),
+            g: blend(self.g, BACKGROUND.g),
+            b: blend(self.b, BACKGROUND.b),
+            a: 1.0,
+        }
+    }
+
+    fn rgb(&self) -> String {
+        format!("rgb({}, {}, {})", self.r, self.g, self.b)
+    }
+}
+
+/// A piece of highlighted text.
+struct Token {
+    text: String,
+    color: Color,
+}
+
+/// Highlight the given file without opening the editor.
+pub fn highlight_file(
+    path: &Path,
+    format: HighlightFormat,
+    settings: &EditorSettings,
+) -> io::Result<String> {
+    let content = std::fs::read(path)?;
+    let content = Encoding::detect(&content).decode(&content);
+    let rope = Rope::from_str(&content);
+
+    let language = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(LanguageId::parse)
+        .unwrap_or_default()
+        .config();
+
+    let lines = highlight_lines(&rope, &language, settings.rainbow_brackets);
+
+    Ok(match format {
+        HighlightFormat::Html => to_html(&lines),
+        HighlightFormat::Ansi => to_ansi(&lines),
+        HighlightFormat::Svg => to_svg(&lines, settings),
+    })
+}
+
+/// Run the editor parser and resolve the colors of every line.
+fn highlight_lines(
+    rope: &Rope,
+    language: &LanguageConfig,
+    rainbow_brackets: bool,
+) -> Vec<Vec<Token>> {
+    let mut syntax_blocks = SyntaxBlocks::default();
+    parse(rope, &mut syntax_blocks, language);
+
+    (0..syntax_blocks.len())
+        .map(|line| {
+            let mut char_idx = rope.line_to_char(line);
+
+            syntax_blocks
+                .get_line(line)
+                .iter()
+                .map(|(syntax_type, text_node)| {
+                    let text = match text_node {
+                        TextNode::Range(range) => {
+                            char_idx = range.end;
+                            rope.slice(range.clone()).to_string()
+                        }
+                        // Export the original whitespaces instead of the marks
+                        TextNode::LineOfChars { len, .. } => {
+                            let range = char_idx..char_idx + len;
+                            char_idx = range.end;
+                            rope.slice(range).to_string()
+                        }
+                    };
+                    let color = if rainbow_brackets {
+                        syntax_type.rainbow_color()
+                    } else {
+                        syntax_type.color()
+                    };
+
+                    Token {
+                        text: text.trim_end_matches(['\n', '\r']).to_string(),
+                        color: Color::parse(color)
+                            .unwrap_or_else(|| Color::parse(SyntaxType::Unknown.color()).unwrap()),
+                    }
+                })
+                .filter(|token| !token.text.is_empty())
+                .collect()
+        })
+        .collect()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn to_html(lines: &[Vec<Token>]) -> String {
+    let mut html = format!(
+        "<pre style=\"background-color: {}; font-family: 'Jetbrains Mono', monospace;\"><code>",
+        BACKGROUND.rgb()
+    );
+
+    for line in lines {
+        for token in line {
+            write!(
+                html,
+                "<span style=\"color: {};\">{}</span>",
+                token.color.opaque().rgb(),
+                escape_xml(&token.text)
+            )
+            .unwrap();
+        }
+        html.push('\n');
+    }
+
+    html.push_str("</code></pre>\n");
+    html
+}
+
+fn to_ansi(lines: &[Vec<Token>]) -> String {
+    let mut ansi = String::new();
+
+    for line in lines {
+        for token in line {
+            let Color { r, g, b, .. } = token.color.opaque();
+            write!(ansi, "\x1b[38;2;{r};{g};{b}m{}", token.text).unwrap();
+        }
+        ansi.push_str("\x1b[0m\n");
+    }
+
+    ansi
+}
+
+fn to_svg(lines: &[Vec<Token>], settings: &EditorSettings) -> String {
+    let font_size = settings.font_size;
+    let line_height = (font_size * settings.line_height).floor();
+
+    let longest_line = lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|token| token.text.chars().count())
+                .sum::<usize>()
+        })
+        .max()
+        .unwrap_or_default();
+    let width = (longest_line as f32 * font_size * GLYPH_WIDTH).ceil() + font_size * 2.0;
+    let height = lines.len() as f32 * line_height + font_size;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+    );
+    writeln!(
+        svg,
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        BACKGROUND.rgb()
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        "<g font-family=\"'Jetbrains Mono', monospace\" font-size=\"{font_size}\" xml:space=\"preserve\">"
+    )
+    .unwrap();
+
+    for (i, line) in lines.iter().enumerate() {
+        let y = (i as f32 + 1.0) * line_height;
+        write!(svg, "<text x=\"{font_size}\" y=\"{y}\">").unwrap();
+        for token in line {
+            write!(
+                svg,
+                "<tspan fill=\"{}\" fill-opacity=\"{}\">{}</tspan>",
+                token.color.rgb(),
+                token.color.a,
+                escape_xml(&token.text)
+            )
+            .unwrap();
+        }
+        svg.push_str("</text>\n");
+    }
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::RUST;
+
+    const SNIPPET: &str = "fn main() {\n    let less = 1 < 2;\n}\n";
+
+    fn snippet_lines() -> Vec<Vec<Token>> {
+        highlight_lines(&Rope::from_str(SNIPPET), &RUST, false)
+    }
+
+    #[test]
+    fn html_output() {
+        let html = to_html(&snippet_lines());
+
+        assert!(html.starts_with("<pre style=\"background-color: rgb(29, 32, 33);"));
+        assert!(html.ends_with("</code></pre>\n"));
+        assert!(html.contains("<span style=\"color: rgb(251, 60, 44);\">fn</span>"));
+        assert!(html.contains("&lt;"));
+    }
+
+    #[test]
+    fn ansi_output() {
+        let ansi = to_ansi(&snippet_lines());
+
+        assert_eq!(ansi.lines().count(), 3);
+        assert!(ansi.starts_with("\x1b[38;2;251;60;44mfn"));
+        assert!(ansi.lines().all(|line| line.ends_with("\x1b[0m")));
+    }
+
+    #[test]
+    fn svg_output() {
+        let svg = to_svg(&snippet_lines(), &EditorSettings::default());
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</g>\n</svg>\n"));
+        assert_eq!(svg.matches("<text ").count(), 3);
+        assert!(svg.contains("<tspan fill=\"rgb(251, 60, 44)\" fill-opacity=\"1\">fn</tspan>"));
+        assert!(svg.contains("&lt;"));
+    }
+}
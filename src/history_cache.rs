@@ -0,0 +1,182 @@
+use std::{
+    fs::{create_dir_all, read_to_string, write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Sender},
+        LazyLock,
+    },
+    thread,
+};
+
+use freya::hooks::HistoryChange;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Most changes kept for a file, the oldest ones are dropped first.
+const MAX_CHANGES: usize = 10_000;
+
+/// Most bytes of text kept in the changes of a file.
+const MAX_TEXT_SIZE: usize = 4_000_000;
+
+/// Same as [`HistoryChange`], but serializable.
+#[derive(Serialize, Deserialize, Debug)]
+enum CachedChange {
+    InsertChar {
+        idx: usize,
+        len: usize,
+        ch: char,
+    },
+    InsertText {
+        idx: usize,
+        len: usize,
+        text: String,
+    },
+    Remove {
+        idx: usize,
+        len: usize,
+        text: String,
+    },
+}
+
+impl CachedChange {
+    fn text_size(&self) -> usize {
+        match self {
+            Self::InsertChar { ch, .. } => ch.len_utf8(),
+            Self::InsertText { text, .. } | Self::Remove { text, .. } => text.len(),
+        }
+    }
+}
+
+impl From<&HistoryChange> for CachedChange {
+    fn from(change: &HistoryChange) -> Self {
+        match change.clone() {
+            HistoryChange::InsertChar { idx, len, ch } => Self::InsertChar { idx, len, ch },
+            HistoryChange::InsertText { idx, len, text } => Self::InsertText { idx, len, text },
+            HistoryChange::Remove { idx, len, text } => Self::Remove { idx, len, text },
+        }
+    }
+}
+
+impl From<CachedChange> for HistoryChange {
+    fn from(change: CachedChange) -> Self {
+        match change {
+            CachedChange::InsertChar { idx, len, ch } => Self::InsertChar { idx, len, ch },
+            CachedChange::InsertText { idx, len, text } => Self::InsertText { idx, len, text },
+            CachedChange::Remove { idx, len, text } => Self::Remove { idx, len, text },
+        }
+    }
+}
+
+/// Undo history of a file, kept between sessions.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CachedHistory {
+    /// Hash of the content the history belongs to, to discard it if the file changed since.
+    content_hash: String,
+    /// Change that leads to the content.
+    pub current_change: usize,
+    /// Ranges of changes that are undone and redone together.
+    pub groups: Vec<Range<usize>>,
+    changes: Vec<CachedChange>,
+}
+
+impl CachedHistory {
+    /// History of the given content, without the oldest changes past the limits.
+    pub fn new(
+        content: &str,
+        changes: &[HistoryChange],
+        current_change: usize,
+        groups: &[Range<usize>],
+    ) -> Self {
+        let mut changes = changes.iter().map(CachedChange::from).collect::<Vec<_>>();
+
+        // Only the changes before the content can be dropped
+        let mut text_size = changes.iter().map(CachedChange::text_size).sum::<usize>();
+        let mut dropped = 0;
+        while dropped < current_change
+            && (changes.len() - dropped > MAX_CHANGES || text_size > MAX_TEXT_SIZE)
+        {
+            text_size -= changes[dropped].text_size();
+            dropped += 1;
+        }
+        changes.drain(..dropped);
+
+        let groups = groups
+            .iter()
+            .filter(|group| group.end > dropped)
+            .map(|group| group.start.max(dropped) - dropped..group.end - dropped)
+            .filter(|group| group.len() > 1)
+            .collect();
+
+        Self {
+            content_hash: content_hash(content),
+            current_change: current_change - dropped,
+            groups,
+            changes,
+        }
+    }
+
+    pub fn changes(self) -> Vec<HistoryChange> {
+        self.changes.into_iter().map(HistoryChange::from).collect()
+    }
+
+    /// Load the history of a file, if it was saved with the same content.
+    pub fn load(file_path: &Path, content: &str) -> Option<Self> {
+        let history_path = history_path(file_path)?;
+        let history_content = read_to_string(history_path).ok()?;
+        let history: Self = toml::from_str(&history_content).ok()?;
+
+        if history.content_hash != content_hash(content)
+            || history.current_change > history.changes.len()
+        {
+            info!("Discarded the history of {file_path:?} as the file changed.");
+            return None;
+        }
+
+        Some(history)
+    }
+
+    /// Save the history in a thread, so writing it doesn't block the UI.
+    pub fn save_in_background(self, file_path: PathBuf) {
+        // A single writer keeps the saves of a file in order
+        static WRITER: LazyLock<Sender<(PathBuf, CachedHistory)>> = LazyLock::new(|| {
+            let (sender, receiver) = channel::<(PathBuf, CachedHistory)>();
+            thread::spawn(move || {
+                for (file_path, history) in receiver {
+                    if history.save(&file_path).is_none() {
+                        info!("Failed to save the history of {file_path:?}");
+                    }
+                }
+            });
+            sender
+        });
+
+        let _ = WRITER.send((file_path, self));
+    }
+
+    fn save(&self, file_path: &Path) -> Option<()> {
+        let history_path = history_path(file_path)?;
+        create_dir_all(history_path.parent()?).ok()?;
+        let history_content = toml::to_string(self).ok()?;
+        write(history_path, history_content).ok()
+    }
+}
+
+fn content_hash(content: &str) -> String {
+    format!("{:016x}", fxhash::hash64(content))
+}
+
+/// Where the history of a file is kept, e.g `~/.cache/valin/history/<hash of the path>.toml`.
+fn history_path(file_path: &Path) -> Option<PathBuf> {
+    let home_dir = home::home_dir()?;
+
+    let file_name = format!("{:016x}.toml", fxhash::hash64(file_path));
+
+    Some(
+        home_dir
+            .join(".cache")
+            .join("valin")
+            .join("history")
+            .join(file_name),
+    )
+}
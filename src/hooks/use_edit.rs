@@ -37,14 +37,10 @@ impl UseEdit {
             .unwrap_or_default()
     }
 
-    /// Create a highlights attribute.
+    /// Create a highlights attribute with the selections of all the cursors.
     pub fn highlights_attr(&self, editor_id: usize, editor_tab: &EditorTab) -> AttributeValue {
         AttributeValue::any_value(CustomAttributeValues::TextHighlights(
-            editor_tab
-                .editor
-                .get_visible_selection(editor_id)
-                .map(|v| vec![v])
-                .unwrap_or_default(),
+            editor_tab.editor.visible_selections(editor_id),
         ))
     }
 }
@@ -83,6 +79,7 @@ pub fn use_edit(mut radio: RadioAppState, tab_id: TabId, text_id: Uuid) -> UseEd
                                 } else {
                                     editor_tab.editor.clear_selection();
                                 }
+                                editor_tab.editor.merge_cursors();
                                 ChannelSelection::Current
                             } else {
                                 ChannelSelection::Silence
@@ -0,0 +1,140 @@
+use std::{fmt::Display, str::FromStr};
+
+use freya::prelude::Rope;
+use serde::{Deserialize, Serialize};
+
+/// How many lines are looked at when detecting the indentation of a file.
+const DETECTION_LINES: usize = 1000;
+
+/// Widest indentation that can be detected.
+const MAX_DETECTED_SIZE: usize = 8;
+
+/// How the lines get indented.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Indentation {
+    /// Number of spaces of every level, and the width of a tab.
+    pub(crate) size: u8,
+    pub(crate) use_tabs: bool,
+}
+
+impl Default for Indentation {
+    fn default() -> Self {
+        Self {
+            size: 4,
+            use_tabs: false,
+        }
+    }
+}
+
+impl Display for Indentation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.use_tabs {
+            write!(f, "Tab Size: {}", self.size)
+        } else {
+            write!(f, "Spaces: {}", self.size)
+        }
+    }
+}
+
+/// Parse an indentation like `spaces:4` or `tabs:8`.
+impl FromStr for Indentation {
+    type Err = ();
+
+    fn from_str(indentation: &str) -> Result<Self, Self::Err> {
+        let (kind, size) = indentation.split_once(':').ok_or(())?;
+        let use_tabs = match kind.trim() {
+            "spaces" => false,
+            "tabs" => true,
+            _ => return Err(()),
+        };
+        let size = size.trim().parse::<u8>().map_err(|_| ())?;
+        Ok(Self {
+            size: size.max(1),
+            use_tabs,
+        })
+    }
+}
+
+impl Indentation {
+    /// Width of a tab, never zero.
+    pub fn tab_width(&self) -> usize {
+        self.size.max(1) as usize
+    }
+
+    /// Whitespace for an indentation of the given width.
+    pub fn whitespace(&self, width: usize) -> String {
+        if self.use_tabs {
+            let tab_width = self.tab_width();
+            "\t".repeat(width / tab_width) + &" ".repeat(width % tab_width)
+        } else {
+            " ".repeat(width)
+        }
+    }
+}
+
+/// Number of chars of the leading whitespace of a line and how wide it is.
+pub fn leading_whitespace(line: &str, tab_width: usize) -> (usize, usize) {
+    let mut len = 0;
+    let mut width = 0;
+    for ch in line.chars() {
+        match ch {
+            ' ' => width += 1,
+            '\t' => width += tab_width,
+            _ => break,
+        }
+        len += 1;
+    }
+    (len, width)
+}
+
+/// Guess the indentation used in the text, if there is any indented line.
+/// Tabs keep the given size as they don't tell how wide they are.
+pub fn detect_indentation(rope: &Rope, tab_size: u8) -> Option<Indentation> {
+    let mut tab_lines = 0;
+    let mut space_lines = 0;
+    // How many times every increase of indentation happens
+    let mut increases = [0usize; MAX_DETECTED_SIZE + 1];
+    let mut previous_width = 0;
+
+    for line in rope.lines().take(DETECTION_LINES) {
+        let line = line.to_string();
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if line.starts_with('\t') {
+            tab_lines += 1;
+        } else if line.starts_with(' ') {
+            space_lines += 1;
+        }
+
+        let (_, width) = leading_whitespace(&line, tab_size.max(1) as usize);
+        if width > previous_width && width - previous_width <= MAX_DETECTED_SIZE {
+            increases[width - previous_width] += 1;
+        }
+        previous_width = width;
+    }
+
+    if tab_lines == 0 && space_lines == 0 {
+        return None;
+    }
+
+    if tab_lines > space_lines {
+        return Some(Indentation {
+            size: tab_size,
+            use_tabs: true,
+        });
+    }
+
+    // Prefer the smallest size on ties
+    let size = (1..=MAX_DETECTED_SIZE)
+        .rev()
+        .max_by_key(|size| increases[*size])
+        .filter(|size| increases[*size] > 0)?;
+
+    Some(Indentation {
+        size: size as u8,
+        use_tabs: false,
+    })
+}
@@ -0,0 +1,122 @@
+use std::sync::{Arc, LazyLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+/// How the syntax of a language is highlighted.
+/// Custom languages can be declared in the settings file under `[[languages]]`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LanguageConfig {
+    pub(crate) name: String,
+    pub(crate) extensions: Vec<String>,
+    pub(crate) keywords: Vec<String>,
+    pub(crate) special_keywords: Vec<String>,
+    pub(crate) line_comment: Option<String>,
+    pub(crate) block_comment: Option<(String, String)>,
+    pub(crate) string_delimiters: Vec<char>,
+    pub(crate) brackets: Vec<(char, char)>,
+    /// Pairs closed automatically when typing the opening char.
+    pub(crate) auto_closing_pairs: Vec<(char, char)>,
+    /// Chars that make the next line one level deeper when they end a line.
+    pub(crate) indent_after: Vec<char>,
+}
+
+impl Default for LanguageConfig {
+    fn default() -> Self {
+        Self {
+            name: String::default(),
+            extensions: Vec::default(),
+            keywords: Vec::default(),
+            special_keywords: Vec::default(),
+            line_comment: Some("//".to_string()),
+            block_comment: Some(("/*".to_string(), "*/".to_string())),
+            string_delimiters: vec!['"'],
+            brackets: vec![('{', '}'), ('(', ')'), ('[', ']')],
+            auto_closing_pairs: vec![('{', '}'), ('(', ')'), ('[', ']'), ('"', '"')],
+            indent_after: vec!['{', '(', '['],
+        }
+    }
+}
+
+impl LanguageConfig {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_keyword(&self, word: &str) -> bool {
+        self.keywords.iter().any(|keyword| keyword == word)
+    }
+
+    pub fn is_special_keyword(&self, word: &str) -> bool {
+        self.special_keywords.iter().any(|keyword| keyword == word)
+    }
+
+    pub fn is_string_delimiter(&self, ch: char) -> bool {
+        self.string_delimiters.contains(&ch)
+    }
+}
+
+fn words(words: &str) -> Vec<String> {
+    words.split_whitespace().map(str::to_string).collect()
+}
+
+/// Also used for the files of unknown languages.
+pub static RUST: LazyLock<Arc<LanguageConfig>> = LazyLock::new(|| {
+    Arc::new(LanguageConfig {
+        name: "Rust".to_string(),
+        keywords: words(
+            "mod use impl if let fn struct enum const pub crate else mut for i8 u8 i16 u16 i32 \
+             u32 f32 i64 u64 f64 i128 u128 usize isize move async in of dyn type match",
+        ),
+        special_keywords: words("self Self false true"),
+        ..LanguageConfig::default()
+    })
+});
+
+pub static PLAIN_TEXT: LazyLock<Arc<LanguageConfig>> = LazyLock::new(|| {
+    Arc::new(LanguageConfig {
+        name: "Plain Text".to_string(),
+        line_comment: None,
+        block_comment: None,
+        string_delimiters: Vec::new(),
+        auto_closing_pairs: Vec::new(),
+        ..LanguageConfig::default()
+    })
+});
+
+/// Languages declared in the settings.
+static CUSTOM_LANGUAGES: RwLock<Vec<Arc<LanguageConfig>>> = RwLock::new(Vec::new());
+
+/// Replace the custom languages with the ones from the settings.
+pub fn set_custom_languages(languages: &[LanguageConfig]) {
+    *CUSTOM_LANGUAGES.write().unwrap() = languages.iter().cloned().map(Arc::new).collect();
+}
+
+/// Name of the custom language that handles the given file extension.
+pub fn find_custom_language(extension: &str) -> Option<String> {
+    CUSTOM_LANGUAGES
+        .read()
+        .unwrap()
+        .iter()
+        .find(|language| language.extensions.iter().any(|ext| ext == extension))
+        .map(|language| language.name.clone())
+}
+
+/// Names of the languages declared in the settings.
+pub fn custom_language_names() -> Vec<String> {
+    CUSTOM_LANGUAGES
+        .read()
+        .unwrap()
+        .iter()
+        .map(|language| language.name.clone())
+        .collect()
+}
+
+pub fn custom_language(name: &str) -> Option<Arc<LanguageConfig>> {
+    CUSTOM_LANGUAGES
+        .read()
+        .unwrap()
+        .iter()
+        .find(|language| language.name == name)
+        .cloned()
+}
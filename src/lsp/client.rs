@@ -11,8 +11,9 @@ use async_lsp::{LanguageServer, ServerSocket};
 use freya::prelude::spawn_forever;
 use lsp_types::{
     notification::{Progress, PublishDiagnostics, ShowMessage},
-    DidCloseTextDocumentParams, DidOpenTextDocumentParams, HoverParams, TextDocumentIdentifier,
-    TextDocumentItem,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, FoldingRangeClientCapabilities,
+    FoldingRangeParams, HoverParams, PartialResultParams, TextDocumentClientCapabilities,
+    TextDocumentIdentifier, TextDocumentItem,
 };
 use lsp_types::{
     ClientCapabilities, HoverContents, InitializeParams, InitializedParams, MarkedString,
@@ -24,8 +25,11 @@ use tokio::sync::mpsc::{self, UnboundedSender};
 use tower::ServiceBuilder;
 use tracing::info;
 
+use crate::languages::{
+    custom_language, custom_language_names, find_custom_language, LanguageConfig, PLAIN_TEXT, RUST,
+};
 use crate::state::{AppState, Channel, RadioAppState, TabId};
-use crate::views::panels::tabs::editor::{Diagnostics, TabEditorUtils};
+use crate::views::panels::tabs::editor::{Diagnostics, FoldingRegion, TabEditorUtils};
 use crate::{views::panels::tabs::editor::EditorType, LspStatusSender};
 
 struct RouterState {
@@ -46,6 +50,7 @@ pub enum LspActionData {
     OpenFile,
     CloseFile { file_uri: Url },
     Hover { position: Position },
+    FoldingRange,
     Clear,
 }
 
@@ -92,6 +97,17 @@ impl LSPClient {
                                                     work_done_progress: Some(true),
                                                     ..WindowClientCapabilities::default()
                                                 }),
+                                                text_document: Some(
+                                                    TextDocumentClientCapabilities {
+                                                        folding_range: Some(
+                                                            FoldingRangeClientCapabilities {
+                                                                line_folding_only: Some(true),
+                                                                ..FoldingRangeClientCapabilities::default()
+                                                            },
+                                                        ),
+                                                        ..TextDocumentClientCapabilities::default()
+                                                    },
+                                                ),
                                                 ..ClientCapabilities::default()
                                             },
                                             ..InitializeParams::default()
@@ -187,6 +203,40 @@ impl LSPClient {
                                         }
                                     }
                                 }
+                                // Folding ranges are syntactic, so there is no need to wait for the indexing
+                                LspActionData::FoldingRange => {
+                                    let Some(file_uri) = ({
+                                        let app_state = radio_app_state.read();
+                                        let tab = app_state.tab(&action.tab_id);
+                                        let editor_tab = tab.as_text_editor().unwrap();
+                                        editor_tab.editor.uri()
+                                    }) else {
+                                        return;
+                                    };
+                                    let response = server
+                                        .folding_range(FoldingRangeParams {
+                                            text_document: TextDocumentIdentifier { uri: file_uri },
+                                            work_done_progress_params:
+                                                WorkDoneProgressParams::default(),
+                                            partial_result_params: PartialResultParams::default(),
+                                        })
+                                        .await;
+                                    if let Ok(Some(ranges)) = response {
+                                        let regions = ranges
+                                            .into_iter()
+                                            .filter(|range| range.end_line > range.start_line)
+                                            .map(|range| FoldingRegion {
+                                                start_line: range.start_line as usize,
+                                                end_line: range.end_line as usize,
+                                            })
+                                            .collect();
+                                        let mut app_state = radio_app_state
+                                            .write_channel(Channel::follow_tab(action.tab_id));
+                                        let tab = app_state.tab_mut(&action.tab_id);
+                                        let editor_tab = tab.as_text_editor_mut().unwrap();
+                                        editor_tab.editor.set_lsp_folding_regions(regions);
+                                    }
+                                }
                                 LspActionData::Clear => {
                                     let mut app_state = radio_app_state
                                         .write_channel(Channel::follow_tab(action.tab_id));
@@ -315,13 +365,17 @@ impl LspConfig {
     }
 }
 
-#[derive(Default, Clone, Debug, PartialEq, Copy)]
+#[derive(Default, Clone, Debug, PartialEq)]
 pub enum LanguageId {
     Rust,
     Python,
     JavaScript,
     TypeScript,
     Markdown,
+    /// Text without highlighting.
+    PlainText,
+    /// Name of a language declared in the settings.
+    Custom(String),
     #[default]
     Unknown,
 }
@@ -334,6 +388,8 @@ impl Display for LanguageId {
             Self::JavaScript => f.write_str("JavaScript"),
             Self::TypeScript => f.write_str("TypeScript"),
             Self::Markdown => f.write_str("Markdown"),
+            Self::PlainText => f.write_str("Plain Text"),
+            Self::Custom(name) => f.write_str(name),
             Self::Unknown => f.write_str("Unknown"),
         }
     }
@@ -341,6 +397,11 @@ impl Display for LanguageId {
 
 impl LanguageId {
     pub fn parse(id: &str) -> Self {
+        // Languages from the settings take precedence
+        if let Some(name) = find_custom_language(id) {
+            return LanguageId::Custom(name);
+        }
+
         match id {
             "rs" => LanguageId::Rust,
             "py" => LanguageId::Python,
@@ -351,10 +412,41 @@ impl LanguageId {
         }
     }
 
+    /// Every known language, the ones declared in the settings last.
+    pub fn all() -> Vec<Self> {
+        let mut languages = vec![
+            LanguageId::Rust,
+            LanguageId::Python,
+            LanguageId::JavaScript,
+            LanguageId::TypeScript,
+            LanguageId::Markdown,
+            LanguageId::PlainText,
+        ];
+        languages.extend(custom_language_names().into_iter().map(LanguageId::Custom));
+        languages
+    }
+
+    /// The known language with the given name, e.g `Python`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|language| language.to_string().eq_ignore_ascii_case(name))
+    }
+
     pub fn language_server(&self) -> Option<&str> {
         match self {
             LanguageId::Rust => Some("rust-analyzer"),
             _ => None,
         }
     }
+
+    /// How the language is highlighted.
+    pub fn config(&self) -> Arc<LanguageConfig> {
+        match self {
+            LanguageId::PlainText => PLAIN_TEXT.clone(),
+            // The language might be gone from the settings since
+            LanguageId::Custom(name) => custom_language(name).unwrap_or_else(|| PLAIN_TEXT.clone()),
+            _ => RUST.clone(),
+        }
+    }
 }
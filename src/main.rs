@@ -6,12 +6,20 @@
 mod app;
 mod components;
 mod constants;
+mod editorconfig;
+mod encoding;
 mod fs;
+mod fuzzy;
 mod global_defaults;
+mod highlight;
+mod history_cache;
 mod hooks;
+mod indentation;
+mod languages;
 mod lsp;
 mod metrics;
 mod parser;
+mod recent_commands;
 mod settings;
 mod state;
 mod utils;
@@ -20,10 +28,12 @@ mod views;
 use std::{path::PathBuf, sync::Arc};
 
 use crate::app::App;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use components::*;
 use freya::prelude::*;
+use highlight::{highlight_file, HighlightFormat};
 use hooks::*;
+use state::AppSettings;
 use tracing::info;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
@@ -42,8 +52,11 @@ const CUSTOM_THEME: Theme = Theme {
 };
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Enable Support for language servers.
     #[arg(short, long)]
     lsp: bool,
@@ -57,7 +70,34 @@ struct Args {
     performance_overlay: bool,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print a file with syntax highlighting, without opening the editor.
+    Highlight {
+        file: PathBuf,
+
+        /// Output format.
+        #[arg(short, long, value_enum, default_value_t)]
+        format: HighlightFormat,
+    },
+}
+
 fn main() {
+    let args = Args::parse();
+
+    // Run the subcommands before setting up the logger so their output stays clean
+    if let Some(Command::Highlight { file, format }) = &args.command {
+        let settings = AppSettings::load();
+        match highlight_file(file, *format, &settings.editor) {
+            Ok(output) => print!("{output}"),
+            Err(err) => {
+                eprintln!("Failed to highlight {file:?}: {err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     let subscriber = FmtSubscriber::builder()
         .with_env_filter(
             EnvFilter::builder()
@@ -70,8 +110,6 @@ fn main() {
 
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
-    let args = Args::parse();
-
     info!("Starting valin. \n{args:#?}");
 
     let mut config = LaunchConfig::<Arc<Args>>::default();
@@ -8,6 +8,7 @@ use skia_safe::textlayout::ParagraphBuilder;
 use skia_safe::textlayout::ParagraphStyle;
 use skia_safe::textlayout::TextStyle;
 
+use crate::languages::LanguageConfig;
 use crate::parser::*;
 
 pub struct EditorMetrics {
@@ -63,7 +64,7 @@ impl EditorMetrics {
         self.longest_width = paragraph.longest_line();
     }
 
-    pub fn run_parser(&mut self, rope: &Rope) {
-        parse(rope, &mut self.syntax_blocks);
+    pub fn run_parser(&mut self, rope: &Rope, language: &LanguageConfig) {
+        parse(rope, &mut self.syntax_blocks, language);
     }
 }
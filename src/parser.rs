@@ -4,6 +4,8 @@ use fxhash::FxHashMap;
 use ropey::Rope;
 use smallvec::SmallVec;
 
+use crate::languages::LanguageConfig;
+
 const LARGE_FILE: usize = 45_000_000;
 
 #[derive(Clone, Debug)]
@@ -18,15 +20,16 @@ pub enum SyntaxType {
     Module,
     Comment,
     SpaceMark,
+    Bracket { depth: usize },
 }
 
 impl SyntaxType {
-    pub fn color(&self) -> &str {
+    pub fn color(&self) -> &'static str {
         match self {
             SyntaxType::Keyword => "rgb(251, 60, 44)",
             SyntaxType::String => "rgb(151, 151, 26)",
             SyntaxType::Punctuation => "rgb(104, 157, 96)",
-            SyntaxType::Punctuation2 => "rgb(252, 188, 61)",
+            SyntaxType::Punctuation2 | SyntaxType::Bracket { .. } => "rgb(252, 188, 61)",
             SyntaxType::Unknown => "rgb(223, 191, 142)",
             SyntaxType::Property => "rgb(152, 192, 124)",
             SyntaxType::Module => "rgb(250, 189, 40)",
@@ -35,8 +38,22 @@ impl SyntaxType {
             SyntaxType::SpaceMark => "rgb(223, 191, 142, 0.2)",
         }
     }
+
+    /// Same as [`SyntaxType::color`] but brackets are colored by their depth.
+    pub fn rainbow_color(&self) -> &'static str {
+        match self {
+            SyntaxType::Bracket { depth } => RAINBOW_COLORS[depth % RAINBOW_COLORS.len()],
+            _ => self.color(),
+        }
+    }
 }
 
+const RAINBOW_COLORS: &[&str] = &[
+    "rgb(252, 188, 61)",
+    "rgb(211, 134, 155)",
+    "rgb(131, 165, 152)",
+];
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 enum SyntaxSemantic {
     Unknown,
@@ -62,6 +79,8 @@ pub type SyntaxLine = SmallVec<[(SyntaxType, TextNode); 4]>;
 #[derive(Default)]
 pub struct SyntaxBlocks {
     blocks: FxHashMap<usize, SyntaxLine>,
+    /// Pairs of brackets of the language that was parsed.
+    brackets: Vec<(char, char)>,
 }
 
 impl SyntaxBlocks {
@@ -80,15 +99,27 @@ impl SyntaxBlocks {
     pub fn clear(&mut self) {
         self.blocks.clear();
     }
-}
 
-const GENERIC_KEYWORDS: &[&str] = &[
-    "mod", "use", "impl", "if", "let", "fn", "struct", "enum", "const", "pub", "crate", "else",
-    "mut", "for", "i8", "u8", "i16", "u16", "i32", "u32", "f32", "i64", "u64", "f64", "i128",
-    "u128", "usize", "isize", "move", "async", "in", "of", "dyn", "type", "match",
-];
+    fn is_open_bracket(&self, ch: char) -> bool {
+        self.brackets.iter().any(|(open, _)| *open == ch)
+    }
 
-const SPECIAL_KEYWORDS: &[&str] = &["self", "Self", "false", "true"];
+    fn is_close_bracket(&self, ch: char) -> bool {
+        self.brackets.iter().any(|(_, close)| *close == ch)
+    }
+
+    /// Char indexes of the brackets found in the given line, in order.
+    fn line_brackets(&self, line: usize) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        self.blocks
+            .get(&line)
+            .into_iter()
+            .flatten()
+            .filter_map(|(syntax_type, text_node)| match (syntax_type, text_node) {
+                (SyntaxType::Bracket { .. }, TextNode::Range(range)) => Some(range.start),
+                _ => None,
+            })
+    }
+}
 
 const SPECIAL_CHARACTER: &[char] = &['.', '=', ';', ':', '\'', ',', '#', '&', '-', '+', '^', '\\'];
 
@@ -103,6 +134,7 @@ enum CommentTracking {
 
 fn flush_generic_stack(
     rope: &Rope,
+    language: &LanguageConfig,
     generic_stack: &mut Option<Range<usize>>,
     syntax_blocks: &mut SyntaxLine,
     last_semantic: &mut SyntaxSemantic,
@@ -124,11 +156,11 @@ fn flush_generic_stack(
             syntax_blocks.push((SyntaxType::Module, TextNode::Range(word_pos)));
         }
         // Match special keywords
-        else if GENERIC_KEYWORDS.contains(&trimmed) {
+        else if language.is_keyword(trimmed) {
             syntax_blocks.push((SyntaxType::Keyword, TextNode::Range(word_pos)));
         }
         // Match other special keyword, CONSTANTS and numbers
-        else if SPECIAL_KEYWORDS.contains(&trimmed) || word.to_uppercase() == word {
+        else if language.is_special_keyword(trimmed) || word.to_uppercase() == word {
             syntax_blocks.push((SyntaxType::SpecialKeyword, TextNode::Range(word_pos)));
         }
         // Match anything else
@@ -167,9 +199,18 @@ fn flush_spaces_stack(
     }
 }
 
-pub fn parse(rope: &Rope, syntax_blocks: &mut SyntaxBlocks) {
+/// Check if `pattern` is found at the given char index.
+fn starts_with_at(rope: &Rope, idx: usize, pattern: &str) -> bool {
+    pattern
+        .chars()
+        .enumerate()
+        .all(|(offset, ch)| rope.get_char(idx + offset) == Some(ch))
+}
+
+pub fn parse(rope: &Rope, syntax_blocks: &mut SyntaxBlocks, language: &LanguageConfig) {
     // Clear any blocks from before
     syntax_blocks.clear();
+    syntax_blocks.brackets.clone_from(&language.brackets);
 
     if rope.len_chars() >= LARGE_FILE {
         for (n, line) in rope.lines().enumerate() {
@@ -185,15 +226,20 @@ pub fn parse(rope: &Rope, syntax_blocks: &mut SyntaxBlocks) {
     // Track comments
     let mut tracking_comment = CommentTracking::None;
     let mut comment_stack: Option<Range<usize>> = None;
+    // Where the content of the current comment starts
+    let mut comment_content_start = 0;
 
-    // Track strings
-    let mut tracking_string = false;
+    // Track strings, by the delimiter that opened them
+    let mut tracking_string: Option<char> = None;
     let mut string_stack: Option<Range<usize>> = None;
 
     // Track anything else
     let mut generic_stack: Option<Range<usize>> = None;
     let mut last_semantic = SyntaxSemantic::Unknown;
 
+    // Track the nesting of brackets
+    let mut bracket_depth = 0;
+
     // Elements of the current line
     let mut line = SyntaxLine::new();
     let mut begining_of_line = true;
@@ -220,20 +266,30 @@ pub fn parse(rope: &Rope, syntax_blocks: &mut SyntaxBlocks) {
         }
 
         // Stop tracking a string
-        if tracking_string && ch == '"' {
-            flush_generic_stack(rope, &mut generic_stack, &mut line, &mut last_semantic, ch);
+        if tracking_string == Some(ch) {
+            flush_generic_stack(
+                rope,
+                language,
+                &mut generic_stack,
+                &mut line,
+                &mut last_semantic,
+                ch,
+            );
 
             let mut st = string_stack.take().unwrap_or_default();
             st.end += 1;
 
             // Strings
             line.push((SyntaxType::String, TextNode::Range(st)));
-            tracking_string = false;
+            tracking_string = None;
         }
         // Start tracking a string
-        else if tracking_comment == CommentTracking::None && ch == '"' {
+        else if tracking_comment == CommentTracking::None
+            && tracking_string.is_none()
+            && language.is_string_delimiter(ch)
+        {
             string_stack = Some(i..i + 1);
-            tracking_string = true;
+            tracking_string = Some(ch);
         }
         // While tracking a comment
         else if tracking_comment != CommentTracking::None {
@@ -243,7 +299,14 @@ pub fn parse(rope: &Rope, syntax_blocks: &mut SyntaxBlocks) {
                 let current_comment: Cow<str> = rope.slice(ct.clone()).into();
 
                 // Stop a multi line comment
-                if ch == '/' && current_comment.ends_with("*/") {
+                let block_comment_end = language
+                    .block_comment
+                    .as_ref()
+                    .map(|(_, end)| end.as_str())
+                    .filter(|end| i + 1 >= comment_content_start + end.chars().count());
+                if tracking_comment == CommentTracking::MultiLine
+                    && block_comment_end.is_some_and(|end| current_comment.ends_with(end))
+                {
                     generic_stack.take();
                     line.push((
                         SyntaxType::Comment,
@@ -256,12 +319,49 @@ pub fn parse(rope: &Rope, syntax_blocks: &mut SyntaxBlocks) {
             }
         }
         // While tracking a string
-        else if tracking_string {
+        else if tracking_string.is_some() {
             push_to_stack(&mut string_stack, i);
         }
+        // Start tracking a comment (both one line and multine)
+        else if let Some((tracking, start)) = [
+            (CommentTracking::OneLine, language.line_comment.as_deref()),
+            (
+                CommentTracking::MultiLine,
+                language
+                    .block_comment
+                    .as_ref()
+                    .map(|(start, _)| start.as_str()),
+            ),
+        ]
+        .into_iter()
+        .find_map(|(tracking, start)| {
+            start
+                .filter(|start| !start.is_empty() && starts_with_at(rope, i, start))
+                .map(|start| (tracking, start))
+        }) {
+            flush_generic_stack(
+                rope,
+                language,
+                &mut generic_stack,
+                &mut line,
+                &mut last_semantic,
+                ch,
+            );
+
+            comment_stack = Some(i..i + 1);
+            comment_content_start = i + start.chars().count();
+            tracking_comment = tracking;
+        }
         // If is a special character
         else if SPECIAL_CHARACTER.contains(&ch) {
-            flush_generic_stack(rope, &mut generic_stack, &mut line, &mut last_semantic, ch);
+            flush_generic_stack(
+                rope,
+                language,
+                &mut generic_stack,
+                &mut line,
+                &mut last_semantic,
+                ch,
+            );
 
             if ch == '.' {
                 last_semantic = SyntaxSemantic::PropertyAccess;
@@ -271,39 +371,54 @@ pub fn parse(rope: &Rope, syntax_blocks: &mut SyntaxBlocks) {
             line.push((SyntaxType::Punctuation, TextNode::Range(i..i + 1)));
         }
         // If is a special character 2
-        else if SPECIAL_CHARACTER_2.contains(&ch) {
-            flush_generic_stack(rope, &mut generic_stack, &mut line, &mut last_semantic, ch);
+        else if SPECIAL_CHARACTER_2.contains(&ch)
+            || syntax_blocks.is_open_bracket(ch)
+            || syntax_blocks.is_close_bracket(ch)
+        {
+            flush_generic_stack(
+                rope,
+                language,
+                &mut generic_stack,
+                &mut line,
+                &mut last_semantic,
+                ch,
+            );
 
-            if ch == '.' {
-                last_semantic = SyntaxSemantic::PropertyAccess;
+            // Brackets
+            if syntax_blocks.is_open_bracket(ch) {
+                line.push((
+                    SyntaxType::Bracket {
+                        depth: bracket_depth,
+                    },
+                    TextNode::Range(i..i + 1),
+                ));
+                bracket_depth += 1;
+            } else if syntax_blocks.is_close_bracket(ch) {
+                bracket_depth = bracket_depth.saturating_sub(1);
+                line.push((
+                    SyntaxType::Bracket {
+                        depth: bracket_depth,
+                    },
+                    TextNode::Range(i..i + 1),
+                ));
             }
-
             // Punctuation
-            line.push((SyntaxType::Punctuation2, TextNode::Range(i..i + 1)));
+            else {
+                line.push((SyntaxType::Punctuation2, TextNode::Range(i..i + 1)));
+            }
         }
         // Unknown (for now at least) characters
         else {
-            // Start tracking a comment (both one line and multine)
-            if tracking_comment == CommentTracking::None && (ch == '*' || ch == '/') {
-                if let Some(us) = generic_stack.as_mut() {
-                    let generic_stack_text: Cow<str> = rope.slice(us.clone()).into();
-                    if generic_stack_text == "/" {
-                        comment_stack = generic_stack.take();
-
-                        push_to_stack(&mut comment_stack, i);
-
-                        if ch == '*' {
-                            tracking_comment = CommentTracking::MultiLine
-                        } else if ch == '/' {
-                            tracking_comment = CommentTracking::OneLine
-                        }
-                    }
-                }
-            }
-
             // Flush the generic stack before adding the space
             if ch.is_whitespace() {
-                flush_generic_stack(rope, &mut generic_stack, &mut line, &mut last_semantic, ch);
+                flush_generic_stack(
+                    rope,
+                    language,
+                    &mut generic_stack,
+                    &mut line,
+                    &mut last_semantic,
+                    ch,
+                );
             }
 
             push_to_stack(&mut generic_stack, i);
@@ -322,7 +437,14 @@ pub fn parse(rope: &Rope, syntax_blocks: &mut SyntaxBlocks) {
                 }
             }
 
-            flush_generic_stack(rope, &mut generic_stack, &mut line, &mut last_semantic, ch);
+            flush_generic_stack(
+                rope,
+                language,
+                &mut generic_stack,
+                &mut line,
+                &mut last_semantic,
+                ch,
+            );
             flush_spaces_stack(rope, &mut generic_stack, &mut line, begining_of_line, true);
 
             if let Some(st) = string_stack.take() {
@@ -350,3 +472,125 @@ fn push_to_stack(stack: &mut Option<Range<usize>>, idx: usize) {
         stack.replace(idx..idx + 1);
     }
 }
+
+/// Find the bracket that matches the one at `bracket_idx`.
+/// Brackets inside strings or comments are ignored.
+pub fn find_matching_bracket(
+    rope: &Rope,
+    syntax_blocks: &SyntaxBlocks,
+    bracket_idx: usize,
+) -> Option<usize> {
+    let bracket = rope.get_char(bracket_idx)?;
+    let bracket_line = rope.char_to_line(bracket_idx);
+
+    // Only brackets recognized by the parser are valid
+    if !syntax_blocks
+        .line_brackets(bracket_line)
+        .any(|idx| idx == bracket_idx)
+    {
+        return None;
+    }
+
+    let mut depth = 0;
+
+    // Search forwards for the closing bracket
+    if let Some((open, close)) = syntax_blocks
+        .brackets
+        .iter()
+        .find(|(open, _)| *open == bracket)
+    {
+        for line in bracket_line..syntax_blocks.len() {
+            for idx in syntax_blocks
+                .line_brackets(line)
+                .filter(|idx| *idx > bracket_idx)
+            {
+                let ch = rope.char(idx);
+                if ch == *open {
+                    depth += 1;
+                } else if ch == *close {
+                    if depth == 0 {
+                        return Some(idx);
+                    }
+                    depth -= 1;
+                }
+            }
+        }
+    }
+    // Search backwards for the opening bracket
+    else if let Some((open, close)) = syntax_blocks
+        .brackets
+        .iter()
+        .find(|(_, close)| *close == bracket)
+    {
+        for line in (0..=bracket_line).rev() {
+            for idx in syntax_blocks
+                .line_brackets(line)
+                .rev()
+                .filter(|idx| *idx < bracket_idx)
+            {
+                let ch = rope.char(idx);
+                if ch == *close {
+                    depth += 1;
+                } else if ch == *open {
+                    if depth == 0 {
+                        return Some(idx);
+                    }
+                    depth -= 1;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Find the closest pair of brackets that surround `char_idx`.
+pub fn find_enclosing_brackets(
+    rope: &Rope,
+    syntax_blocks: &SyntaxBlocks,
+    char_idx: usize,
+) -> Option<(usize, usize)> {
+    let char_line = rope.char_to_line(char_idx.min(rope.len_chars()));
+    let mut depth = 0;
+
+    for line in (0..=char_line).rev() {
+        for idx in syntax_blocks
+            .line_brackets(line)
+            .rev()
+            .filter(|idx| *idx < char_idx)
+        {
+            let ch = rope.char(idx);
+            if syntax_blocks.is_close_bracket(ch) {
+                depth += 1;
+            } else if depth == 0 {
+                let matching = find_matching_bracket(rope, syntax_blocks, idx)?;
+                return Some((idx, matching));
+            } else {
+                depth -= 1;
+            }
+        }
+    }
+
+    None
+}
+
+/// Lines of every pair of brackets that spans multiple lines, as `(open_line, close_line)`.
+pub fn find_multiline_brackets(rope: &Rope, syntax_blocks: &SyntaxBlocks) -> Vec<(usize, usize)> {
+    let mut open_lines = Vec::new();
+    let mut ranges = Vec::new();
+
+    for line in 0..syntax_blocks.len() {
+        for idx in syntax_blocks.line_brackets(line) {
+            let ch = rope.char(idx);
+            if syntax_blocks.is_open_bracket(ch) {
+                open_lines.push(line);
+            } else if let Some(open_line) = open_lines.pop() {
+                if open_line < line {
+                    ranges.push((open_line, line));
+                }
+            }
+        }
+    }
+
+    ranges
+}
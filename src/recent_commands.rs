@@ -0,0 +1,80 @@
+use std::{
+    fs::{create_dir_all, read_to_string, write},
+    path::PathBuf,
+    sync::{
+        mpsc::{channel, Sender},
+        LazyLock,
+    },
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Most commands remembered as recently used.
+const MAX_RECENT_COMMANDS: usize = 30;
+
+/// Commands run lately from the Commander, the most recent first. Kept between sessions.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct RecentCommands {
+    commands: Vec<String>,
+}
+
+impl RecentCommands {
+    /// Load the commands used in the previous sessions, if any.
+    pub fn load() -> Self {
+        recent_commands_path()
+            .and_then(|path| read_to_string(path).ok())
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the commands in a thread, so writing them doesn't block the UI.
+    pub fn save(&self) {
+        // A single writer keeps the saves in order
+        static WRITER: LazyLock<Sender<String>> = LazyLock::new(|| {
+            let (sender, receiver) = channel::<String>();
+            thread::spawn(move || {
+                for content in receiver {
+                    if write_recent_commands(content).is_none() {
+                        info!("Failed to save the recent commands");
+                    }
+                }
+            });
+            sender
+        });
+
+        if let Ok(content) = toml::to_string(self) {
+            let _ = WRITER.send(content);
+        }
+    }
+
+    pub fn push(&mut self, command_id: &str) {
+        self.commands.retain(|id| id != command_id);
+        self.commands.insert(0, command_id.to_string());
+        self.commands.truncate(MAX_RECENT_COMMANDS);
+    }
+
+    /// How recently the command was used, `0` being the last one.
+    pub fn rank(&self, command_id: &str) -> Option<usize> {
+        self.commands.iter().position(|id| id == command_id)
+    }
+}
+
+fn write_recent_commands(content: String) -> Option<()> {
+    let path = recent_commands_path()?;
+    create_dir_all(path.parent()?).ok()?;
+    write(path, content).ok()
+}
+
+/// Where the recent commands are kept, `~/.cache/valin/recent_commands.toml`.
+fn recent_commands_path() -> Option<PathBuf> {
+    let home_dir = home::home_dir()?;
+
+    Some(
+        home_dir
+            .join(".cache")
+            .join("valin")
+            .join("recent_commands.toml"),
+    )
+}
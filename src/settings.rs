@@ -3,11 +3,15 @@ use std::{
     path::PathBuf,
 };
 
+use freya::prelude::{Readable, Signal, Writable};
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use tokio::sync::mpsc::channel;
 use tracing::info;
 
-use crate::state::{AppSettings, Channel, RadioAppState};
+use crate::{
+    languages::set_custom_languages,
+    state::{AppSettings, Channel, EditorCommands, Keybindings, RadioAppState},
+};
 
 pub fn settings_path() -> Option<PathBuf> {
     let home_dir = home::home_dir()?;
@@ -31,10 +35,16 @@ pub fn load_settings() -> Option<AppSettings> {
 
     let settings: AppSettings = toml::from_str(&settings_content).ok()?;
 
+    set_custom_languages(&settings.languages);
+
     Some(settings)
 }
 
-pub async fn watch_settings(mut radio_app_state: RadioAppState) -> Option<()> {
+pub async fn watch_settings(
+    mut radio_app_state: RadioAppState,
+    mut keybindings: Signal<Keybindings>,
+    editor_commands: Signal<EditorCommands>,
+) -> Option<()> {
     let (tx, mut rx) = channel::<()>(1);
 
     let settings_path = settings_path()?;
@@ -60,6 +70,9 @@ pub async fn watch_settings(mut radio_app_state: RadioAppState) -> Option<()> {
         if let Some(settings) = settings {
             let mut app_state = radio_app_state.write_channel(Channel::Settings);
             app_state.set_settings(settings);
+            keybindings
+                .write()
+                .update(&app_state, &editor_commands.read());
         } else {
             info!("Failed to update in-memory settings with the newest changes.")
         }
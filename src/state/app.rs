@@ -1,4 +1,4 @@
-use std::{collections::HashMap, vec};
+use std::{collections::HashMap, path::PathBuf, vec};
 
 use dioxus_clipboard::prelude::UseClipboard;
 use dioxus_radio::prelude::{Radio, RadioChannel};
@@ -9,11 +9,16 @@ use tracing::info;
 use crate::{
     fs::FSTransport,
     lsp::{LSPClient, LspConfig},
-    views::file_explorer::file_explorer_state::FileExplorerState,
+    views::{
+        file_explorer::file_explorer_state::FileExplorerState, panels::tabs::editor::KillRing,
+    },
     LspStatusSender,
 };
 
-use super::{AppSettings, EditorView, Panel, PanelTab, TabId};
+use super::{AppSettings, CommandPrompt, EditorView, Panel, PanelTab, TabId};
+
+/// Most files remembered as recently opened.
+const MAX_RECENT_FILES: usize = 50;
 
 pub type RadioAppState = Radio<AppState, Channel>;
 
@@ -116,6 +121,12 @@ pub struct AppState {
     pub default_transport: FSTransport,
     pub font_collection: FontCollection,
     pub clipboard: UseClipboard,
+    /// Texts killed with the Emacs keymap, shared by all the editors.
+    pub kill_ring: KillRing,
+    /// Files opened lately, the most recent first.
+    pub recent_files: Vec<PathBuf>,
+    /// Command whose arguments are asked once the Commander is shown.
+    pub commander_prompt: Option<CommandPrompt>,
 
     pub file_explorer: FileExplorerState,
 }
@@ -142,6 +153,9 @@ impl AppState {
             default_transport,
             font_collection,
             clipboard,
+            kill_ring: KillRing::default(),
+            recent_files: Vec::new(),
+            commander_prompt: None,
 
             file_explorer: FileExplorerState::new(),
         }
@@ -220,6 +234,18 @@ impl AppState {
         }
     }
 
+    pub fn push_recent_file(&mut self, path: PathBuf) {
+        self.recent_files.retain(|recent_file| *recent_file != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Show the Commander to ask for the missing arguments of a command.
+    pub fn prompt_command(&mut self, prompt: CommandPrompt) {
+        self.focus_view(EditorView::Commander);
+        self.commander_prompt = Some(prompt);
+    }
+
     pub fn font_size(&self) -> f32 {
         self.settings.editor.font_size
     }
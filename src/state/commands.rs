@@ -1,24 +1,115 @@
 use std::collections::HashMap;
 
+use crate::recent_commands::RecentCommands;
+
+use super::{Channel, RadioAppState};
+
 pub struct CommandRunContext {
     /// Only for Commander.
     pub focus_previous_view: bool,
+    /// Values of the arguments of the command, in the order they are declared.
+    pub args: Vec<String>,
 }
 
 impl Default for CommandRunContext {
     fn default() -> Self {
         Self {
             focus_previous_view: true,
+            args: Vec::new(),
+        }
+    }
+}
+
+impl CommandRunContext {
+    pub fn with_args(args: Vec<String>) -> Self {
+        Self {
+            args,
+            ..Self::default()
+        }
+    }
+
+    /// Value of an argument, if it was given.
+    pub fn arg(&self, idx: usize) -> Option<&str> {
+        self.args.get(idx).map(String::as_str)
+    }
+}
+
+/// An option of an argument that has to be chosen from a list.
+#[derive(Clone, PartialEq)]
+pub struct CommandChoice {
+    /// Passed to the command.
+    pub value: String,
+    /// Shown in the Commander.
+    pub text: String,
+}
+
+impl CommandChoice {
+    pub fn new(value: impl ToString, text: impl ToString) -> Self {
+        Self {
+            value: value.to_string(),
+            text: text.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum CommandArgKind {
+    /// Any text.
+    Text,
+    /// One of the given options.
+    Choice(Vec<CommandChoice>),
+    /// Path of a file, completed with the files of its folder.
+    /// Relative paths start from the first open folder.
+    FilePath,
+}
+
+/// An argument of a command, asked in the Commander when it's not given.
+#[derive(Clone, PartialEq)]
+pub struct CommandArg {
+    /// Shown while the argument is asked, e.g `Line number`.
+    pub prompt: String,
+    pub kind: CommandArgKind,
+}
+
+impl CommandArg {
+    pub fn text(prompt: &str) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            kind: CommandArgKind::Text,
+        }
+    }
+
+    pub fn choice(prompt: &str, choices: Vec<CommandChoice>) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            kind: CommandArgKind::Choice(choices),
+        }
+    }
+
+    pub fn file_path(prompt: &str) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            kind: CommandArgKind::FilePath,
         }
     }
 }
 
+/// A command waiting for the rest of its arguments to be given in the Commander.
+#[derive(Clone, PartialEq)]
+pub struct CommandPrompt {
+    pub command_id: String,
+    pub args: Vec<String>,
+}
+
 pub trait EditorCommand {
     fn is_visible(&self) -> bool {
         true
     }
 
-    fn matches(&self, input: &str) -> bool;
+    /// Arguments asked before running the command.
+    fn args(&self) -> Vec<CommandArg> {
+        Vec::new()
+    }
 
     fn id(&self) -> &str;
 
@@ -27,22 +118,51 @@ pub trait EditorCommand {
     fn run(&self, ctx: &mut CommandRunContext);
 }
 
-#[derive(Default)]
 pub struct EditorCommands {
     pub(crate) commands: HashMap<String, Box<dyn EditorCommand>>,
+    pub(crate) recent_commands: RecentCommands,
+    radio_app_state: RadioAppState,
 }
 
 impl EditorCommands {
+    pub fn new(radio_app_state: RadioAppState, recent_commands: RecentCommands) -> Self {
+        Self {
+            commands: HashMap::new(),
+            recent_commands,
+            radio_app_state,
+        }
+    }
+
     pub fn register(&mut self, editor: impl EditorCommand + 'static) {
         self.commands
             .insert(editor.id().to_string(), Box::new(editor));
     }
 
     pub fn trigger(&self, command_name: &str) {
+        self.trigger_with_args(command_name, Vec::new());
+    }
+
+    /// Run a command with the given arguments, the missing ones are asked in the Commander.
+    pub fn trigger_with_args(&self, command_name: &str, args: Vec<String>) {
         let command = self.commands.get(command_name);
 
         if let Some(command) = command {
-            command.run(&mut CommandRunContext::default());
+            if args.len() < command.args().len() {
+                let mut radio_app_state = self.radio_app_state;
+                let mut app_state = radio_app_state.write_channel(Channel::Global);
+                app_state.prompt_command(CommandPrompt {
+                    command_id: command_name.to_string(),
+                    args,
+                });
+            } else {
+                command.run(&mut CommandRunContext::with_args(args));
+            }
         }
     }
+
+    /// Remember a command run from the Commander so it's ranked first the next time.
+    pub fn push_recent_command(&mut self, command_id: &str) {
+        self.recent_commands.push(command_id);
+        self.recent_commands.save();
+    }
 }
@@ -2,7 +2,7 @@ use std::rc::Rc;
 
 use dioxus_radio::hooks::{ChannelSelection, DataReducer};
 use freya::{
-    events::{Code, Key, KeyboardData, Modifiers, MouseData},
+    events::{Key, KeyboardData, Modifiers, MouseData},
     prelude::{Readable, Signal, Writable},
 };
 use freya_hooks::EditableEvent;
@@ -26,15 +26,23 @@ pub enum EditorActionData {
         scroll_offsets: Signal<(i32, i32)>,
         line_height: f32,
         lines_len: usize,
+        /// Whether the key runs a command or continues a chord, so it's not typed.
+        is_keybinding: bool,
     },
     Click,
     MouseDown {
         data: Rc<MouseData>,
         line_index: usize,
+        add_cursor: bool,
     },
     MouseMove {
         data: Rc<MouseData>,
         line_index: usize,
+        /// Column under the pointer while pressing `Alt`, used for column selections.
+        column: Option<usize>,
+    },
+    ToggleFold {
+        line_index: usize,
     },
 }
 
@@ -57,20 +65,41 @@ impl DataReducer for AppState {
         let is_editor_focused = is_panel_focused && panel.active_tab() == Some(tab_id);
 
         match data {
-            EditorActionData::MouseMove { data, line_index }
-                if is_editor_focused && is_panel_focused =>
-            {
+            EditorActionData::MouseMove {
+                data,
+                line_index,
+                column,
+            } if is_editor_focused && is_panel_focused => {
                 let editor_tab = self.editor_tab_mut(tab_id);
-                editor_tab
-                    .editor
-                    .process_event(&EditableEvent::MouseMove(data, line_index));
 
-                ChannelSelection::Silence
+                // Dragging while pressing `Alt` selects columns
+                match column {
+                    Some(column) if editor_tab.editor.dragging.has_cursor_coords() => {
+                        editor_tab.editor.drag_column_selection(line_index, column);
+                        ChannelSelection::Select(Channel::follow_tab(tab_id))
+                    }
+                    _ => {
+                        editor_tab
+                            .editor
+                            .process_event(&EditableEvent::MouseMove(data, line_index));
+                        ChannelSelection::Silence
+                    }
+                }
             }
-            EditorActionData::MouseDown { data, line_index } => {
+            EditorActionData::MouseDown {
+                data,
+                line_index,
+                add_cursor,
+            } => {
                 let mut channel = ChannelSelection::Select(Channel::follow_tab(tab_id));
 
                 let editor_tab = self.editor_tab_mut(tab_id);
+                // The current cursor stays when adding a new one
+                if add_cursor {
+                    editor_tab.editor.keep_cursor();
+                } else {
+                    editor_tab.editor.clear_extra_cursors();
+                }
                 editor_tab
                     .editor
                     .process_event(&EditableEvent::MouseDown(data, line_index));
@@ -93,16 +122,46 @@ impl DataReducer for AppState {
                 editor_tab.editor.process_event(&EditableEvent::Click);
                 ChannelSelection::Silence
             }
+            EditorActionData::ToggleFold { line_index } => {
+                let editor_tab = self.editor_tab_mut(tab_id);
+                if editor_tab.editor.toggle_fold(line_index) {
+                    ChannelSelection::Select(Channel::follow_tab(tab_id))
+                } else {
+                    ChannelSelection::Silence
+                }
+            }
             EditorActionData::KeyUp { data } if is_editor_focused && is_panel_focused => {
                 let editor_tab = self.editor_tab_mut(tab_id);
                 editor_tab.editor.process_event(&EditableEvent::KeyUp(data));
                 ChannelSelection::Select(Channel::follow_tab(tab_id))
             }
+            // Vim takes the keys it handles, e.g all of them except what gets typed in insert mode
+            EditorActionData::KeyDown { data, .. }
+                if is_editor_focused
+                    && is_panel_focused
+                    && self.editor_tab_mut(tab_id).editor.process_vim_key(&data) =>
+            {
+                ChannelSelection::Select(Channel::follow_tab(tab_id))
+            }
+            // Pressing `Alt Shift Arrows` selects columns
+            EditorActionData::KeyDown { data, .. }
+                if is_editor_focused
+                    && is_panel_focused
+                    && data.modifiers == Modifiers::ALT | Modifiers::SHIFT =>
+            {
+                let editor_tab = self.editor_tab_mut(tab_id);
+                if editor_tab.editor.extend_column_selection(&data.key) {
+                    ChannelSelection::Select(Channel::follow_tab(tab_id))
+                } else {
+                    ChannelSelection::Silence
+                }
+            }
             EditorActionData::KeyDown {
                 data,
                 mut scroll_offsets,
                 line_height,
                 lines_len,
+                is_keybinding,
             } if is_editor_focused && is_panel_focused => {
                 const LINES_JUMP_ALT: usize = 5;
                 const LINES_JUMP_CONTROL: usize = 3;
@@ -113,6 +172,8 @@ impl DataReducer for AppState {
                 let current_scroll = scroll_offsets.read().1;
 
                 let events = match &data.key {
+                    // The keys bound to commands are handled by them
+                    _ if is_keybinding => Vec::new(),
                     Key::ArrowUp if data.modifiers.contains(Modifiers::ALT) => {
                         let jump = (current_scroll + lines_jump).clamp(min_height, max_height);
                         scroll_offsets.write().1 = jump;
@@ -134,13 +195,8 @@ impl DataReducer for AppState {
                             .map(|_| EditableEvent::KeyDown(data.clone()))
                             .collect::<Vec<EditableEvent>>()
                     }
-                    _ if data.code == Code::Escape
-                        || data.modifiers.contains(Modifiers::ALT)
-                        || (data.modifiers.contains(Modifiers::CONTROL)
-                            && data.code == Code::KeyS) =>
-                    {
-                        Vec::new()
-                    }
+                    // `Alt` doesn't type text
+                    _ if data.modifiers.contains(Modifiers::ALT) => Vec::new(),
                     _ => {
                         vec![EditableEvent::KeyDown(data.clone())]
                     }
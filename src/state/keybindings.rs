@@ -0,0 +1,595 @@
+use std::{fmt::Display, str::FromStr};
+
+use freya::events::{Code, Key, KeyboardData, Modifiers};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::views::panels::tabs::editor::AppStateEditorUtils;
+
+use super::{AppState, EditorCommands, EditorView, Keymap, RadioAppState};
+
+/// Names of the keys that can be bound, matched by their position in the keyboard.
+const KEY_CODES: &[(&str, Code)] = &[
+    ("A", Code::KeyA),
+    ("B", Code::KeyB),
+    ("C", Code::KeyC),
+    ("D", Code::KeyD),
+    ("E", Code::KeyE),
+    ("F", Code::KeyF),
+    ("G", Code::KeyG),
+    ("H", Code::KeyH),
+    ("I", Code::KeyI),
+    ("J", Code::KeyJ),
+    ("K", Code::KeyK),
+    ("L", Code::KeyL),
+    ("M", Code::KeyM),
+    ("N", Code::KeyN),
+    ("O", Code::KeyO),
+    ("P", Code::KeyP),
+    ("Q", Code::KeyQ),
+    ("R", Code::KeyR),
+    ("S", Code::KeyS),
+    ("T", Code::KeyT),
+    ("U", Code::KeyU),
+    ("V", Code::KeyV),
+    ("W", Code::KeyW),
+    ("X", Code::KeyX),
+    ("Y", Code::KeyY),
+    ("Z", Code::KeyZ),
+    ("0", Code::Digit0),
+    ("1", Code::Digit1),
+    ("2", Code::Digit2),
+    ("3", Code::Digit3),
+    ("4", Code::Digit4),
+    ("5", Code::Digit5),
+    ("6", Code::Digit6),
+    ("7", Code::Digit7),
+    ("8", Code::Digit8),
+    ("9", Code::Digit9),
+    (".", Code::Period),
+    (",", Code::Comma),
+    ("/", Code::Slash),
+    ("\\", Code::Backslash),
+    ("[", Code::BracketLeft),
+    ("]", Code::BracketRight),
+    (";", Code::Semicolon),
+    ("'", Code::Quote),
+    ("`", Code::Backquote),
+    ("=", Code::Equal),
+    ("Escape", Code::Escape),
+    ("Esc", Code::Escape),
+    ("Enter", Code::Enter),
+    ("Space", Code::Space),
+    ("Tab", Code::Tab),
+    ("Backspace", Code::Backspace),
+    ("Delete", Code::Delete),
+    ("Insert", Code::Insert),
+    ("Home", Code::Home),
+    ("End", Code::End),
+    ("PageUp", Code::PageUp),
+    ("PageDown", Code::PageDown),
+    ("ArrowUp", Code::ArrowUp),
+    ("ArrowDown", Code::ArrowDown),
+    ("ArrowLeft", Code::ArrowLeft),
+    ("ArrowRight", Code::ArrowRight),
+    ("F1", Code::F1),
+    ("F2", Code::F2),
+    ("F3", Code::F3),
+    ("F4", Code::F4),
+    ("F5", Code::F5),
+    ("F6", Code::F6),
+    ("F7", Code::F7),
+    ("F8", Code::F8),
+    ("F9", Code::F9),
+    ("F10", Code::F10),
+    ("F11", Code::F11),
+    ("F12", Code::F12),
+];
+
+/// Where a keybinding can be used, e.g `when = "editor"`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KeybindingContext {
+    /// Everywhere.
+    #[default]
+    Any,
+    /// While an editor is focused.
+    Editor,
+    /// While the files explorer is focused.
+    Explorer,
+    /// While the Commander is open.
+    Commander,
+}
+
+impl KeybindingContext {
+    /// Context of what is focused, `Any` if it's none of the others.
+    pub fn current(app_state: &AppState) -> Self {
+        match app_state.focused_view() {
+            EditorView::Panels => {
+                let is_editor = app_state
+                    .panel(app_state.focused_panel())
+                    .active_tab
+                    .is_some_and(|tab_id| app_state.try_editor_tab(tab_id).is_some());
+                if is_editor {
+                    Self::Editor
+                } else {
+                    Self::Any
+                }
+            }
+            EditorView::FilesExplorer => Self::Explorer,
+            EditorView::Commander => Self::Commander,
+            _ => Self::Any,
+        }
+    }
+
+    /// Whether the keybindings of this context can be used in the current one.
+    pub fn applies_to(self, current: Self) -> bool {
+        self == Self::Any || self == current
+    }
+
+    /// Whether both contexts can be active at the same time.
+    pub fn overlaps(self, other: Self) -> bool {
+        self.applies_to(other) || other.applies_to(self)
+    }
+}
+
+impl Display for KeybindingContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any => f.write_str("Anywhere"),
+            Self::Editor => f.write_str("Editor"),
+            Self::Explorer => f.write_str("Files Explorer"),
+            Self::Commander => f.write_str("Commander"),
+        }
+    }
+}
+
+/// A keybinding of the settings, under `[[keybindings]]`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct KeybindingConfig {
+    /// Keys pressed one after the other, e.g `Ctrl+K Ctrl+S`.
+    pub(crate) keys: String,
+    /// Id of the command, or empty to remove the default keybinding of the keys.
+    pub(crate) command: String,
+    /// Arguments of the command, the missing ones are asked in the Commander.
+    pub(crate) args: Vec<String>,
+    pub(crate) when: KeybindingContext,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum KeyName {
+    /// A key in a position of the keyboard.
+    Code(Code),
+    /// A key that types a char, whatever its position is.
+    Character(String),
+}
+
+/// A key pressed with some modifiers, e.g `Ctrl+Shift+K`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyStroke {
+    modifiers: Modifiers,
+    key: KeyName,
+}
+
+impl KeyStroke {
+    pub fn matches(&self, data: &KeyboardData) -> bool {
+        data.modifiers == self.modifiers
+            && match &self.key {
+                KeyName::Code(code) => data.code == *code,
+                KeyName::Character(ch) => data.key == Key::Character(ch.clone()),
+            }
+    }
+}
+
+impl FromStr for KeyStroke {
+    type Err = String;
+
+    fn from_str(keys: &str) -> Result<Self, Self::Err> {
+        // `+` can be the key itself, e.g `Alt++`
+        let (modifiers_names, key_name) = if keys == "+" {
+            ("", "+")
+        } else if let Some(modifiers_names) = keys.strip_suffix("++") {
+            (modifiers_names, "+")
+        } else {
+            keys.rsplit_once('+').unwrap_or(("", keys))
+        };
+
+        let mut modifiers = Modifiers::empty();
+        for modifier in modifiers_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CONTROL,
+                "alt" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                "meta" | "cmd" | "super" => Modifiers::META,
+                _ => return Err(format!("Unknown modifier `{modifier}`")),
+            };
+        }
+
+        let key = match KEY_CODES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key_name))
+        {
+            Some((_, code)) => KeyName::Code(*code),
+            None if key_name.chars().count() == 1 => KeyName::Character(key_name.to_string()),
+            None => return Err(format!("Unknown key `{key_name}`")),
+        };
+
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl Display for KeyStroke {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (Modifiers::CONTROL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::META, "Meta"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match &self.key {
+            KeyName::Code(code) => {
+                let name = KEY_CODES
+                    .iter()
+                    .find(|(_, key_code)| key_code == code)
+                    .map(|(name, _)| *name)
+                    .unwrap_or_default();
+                f.write_str(name)
+            }
+            KeyName::Character(ch) => f.write_str(ch),
+        }
+    }
+}
+
+/// Parse the keys of a chord, e.g `Ctrl+K Ctrl+S`.
+fn parse_keys(keys: &str) -> Result<Vec<KeyStroke>, String> {
+    let strokes = keys
+        .split_whitespace()
+        .map(KeyStroke::from_str)
+        .collect::<Result<Vec<KeyStroke>, String>>()?;
+    if strokes.is_empty() {
+        Err("No keys".to_string())
+    } else {
+        Ok(strokes)
+    }
+}
+
+/// A command bound to some keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keybinding {
+    /// Keys pressed one after the other.
+    pub keys: Vec<KeyStroke>,
+    pub command_id: String,
+    pub args: Vec<String>,
+    pub when: KeybindingContext,
+    /// Whether it comes from the settings.
+    pub is_custom: bool,
+}
+
+impl Keybinding {
+    /// The keys as they are written in the settings, e.g `Ctrl+K Ctrl+S`.
+    pub fn keys_text(&self) -> String {
+        self.keys
+            .iter()
+            .map(KeyStroke::to_string)
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Whether the first keys are the given ones.
+    fn starts_with(&self, pressed: &[KeyboardData]) -> bool {
+        self.keys.len() >= pressed.len()
+            && self
+                .keys
+                .iter()
+                .zip(pressed)
+                .all(|(stroke, data)| stroke.matches(data))
+    }
+
+    /// Whether pressing the keys of this keybinding first makes the other one unreachable.
+    fn shadows(&self, other: &Self) -> bool {
+        self.keys.len() < other.keys.len() && other.keys.starts_with(&self.keys)
+    }
+
+    /// Whether the default keybinding has the same keys and gets in the way of this one.
+    fn replaces(&self, default: &Self) -> bool {
+        self.keys == default.keys
+            && (self.when == default.when || self.when == KeybindingContext::Any)
+    }
+
+    /// Describe how the other keybinding clashes with this one, if it does.
+    fn conflict_with(&self, other: &Self) -> Option<String> {
+        if !self.when.overlaps(other.when) {
+            return None;
+        }
+        let (keys, other_keys) = (self.keys_text(), other.keys_text());
+        if self.keys == other.keys && self.when == other.when {
+            Some(format!(
+                "`{keys}` is bound to both `{}` and `{}`",
+                self.command_id, other.command_id
+            ))
+        } else if self.shadows(other) {
+            Some(format!(
+                "`{keys}` of `{}` hides `{other_keys}` of `{}`",
+                self.command_id, other.command_id
+            ))
+        } else if other.shadows(self) {
+            Some(format!(
+                "`{other_keys}` of `{}` hides `{keys}` of `{}`",
+                other.command_id, self.command_id
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// The keybindings in use and the problems found in them.
+#[derive(Default, Clone, PartialEq)]
+pub struct EffectiveKeybindings {
+    pub keybindings: Vec<Keybinding>,
+    /// Invalid keybindings and keybindings that clash, e.g two commands bound to the same keys.
+    pub conflicts: Vec<String>,
+}
+
+enum KeybindingMatch {
+    Command(String, Vec<String>),
+    /// The keys start a chord.
+    Pending,
+    None,
+}
+
+impl EffectiveKeybindings {
+    /// Keys of the first keybinding of a command.
+    pub fn keys_of(&self, command_id: &str) -> Option<String> {
+        self.keybindings
+            .iter()
+            .find(|keybinding| keybinding.command_id == command_id)
+            .map(Keybinding::keys_text)
+    }
+
+    fn find(&self, pressed: &[KeyboardData], context: KeybindingContext) -> KeybindingMatch {
+        let mut candidates = self.keybindings.iter().filter(|keybinding| {
+            keybinding.when.applies_to(context) && keybinding.starts_with(pressed)
+        });
+
+        // The keybindings of the context take precedence over the ones of anywhere
+        let command = candidates
+            .clone()
+            .filter(|keybinding| keybinding.keys.len() == pressed.len())
+            .min_by_key(|keybinding| keybinding.when == KeybindingContext::Any);
+
+        match command {
+            Some(keybinding) => {
+                KeybindingMatch::Command(keybinding.command_id.clone(), keybinding.args.clone())
+            }
+            None if candidates.next().is_some() => KeybindingMatch::Pending,
+            None => KeybindingMatch::None,
+        }
+    }
+}
+
+/// A keybinding used when the settings don't change it.
+struct DefaultKeybinding {
+    keys: &'static str,
+    command_id: &'static str,
+    when: KeybindingContext,
+    /// Keymap that binds the keys, every keymap if `None`.
+    keymap: Option<Keymap>,
+}
+
+/// The default keybindings, the ones of the settings and the chord being pressed.
+#[derive(Default)]
+pub struct Keybindings {
+    defaults: Vec<DefaultKeybinding>,
+    /// Settings and keymap the keybindings were last resolved from.
+    resolved_from: Option<(Vec<KeybindingConfig>, Keymap)>,
+    effective: EffectiveKeybindings,
+    /// Keys pressed so far of a chord.
+    pressed: Vec<KeyboardData>,
+}
+
+impl Keybindings {
+    /// Bind some keys to a command, e.g `Ctrl+S`.
+    pub fn register(
+        &mut self,
+        keys: &'static str,
+        command_id: &'static str,
+        when: KeybindingContext,
+    ) {
+        self.defaults.push(DefaultKeybinding {
+            keys,
+            command_id,
+            when,
+            keymap: None,
+        });
+    }
+
+    /// Bind some keys to a command only with the given keymap.
+    pub fn register_keymap(
+        &mut self,
+        keymap: Keymap,
+        keys: &'static str,
+        command_id: &'static str,
+        when: KeybindingContext,
+    ) {
+        self.defaults.push(DefaultKeybinding {
+            keys,
+            command_id,
+            when,
+            keymap: Some(keymap),
+        });
+    }
+
+    /// The keybindings of the settings, and the default ones they don't replace.
+    fn resolve(
+        &self,
+        configs: &[KeybindingConfig],
+        keymap: Keymap,
+        commands: &EditorCommands,
+    ) -> EffectiveKeybindings {
+        let mut conflicts = Vec::new();
+
+        let mut custom_keybindings = Vec::new();
+        for config in configs {
+            let keys = match parse_keys(&config.keys) {
+                Ok(keys) => keys,
+                Err(err) => {
+                    conflicts.push(format!("Invalid keys `{}`: {err}", config.keys));
+                    continue;
+                }
+            };
+            if !config.command.is_empty() && !commands.commands.contains_key(&config.command) {
+                conflicts.push(format!(
+                    "`{}` is bound to the unknown command `{}`",
+                    config.keys, config.command
+                ));
+                continue;
+            }
+            custom_keybindings.push(Keybinding {
+                keys,
+                command_id: config.command.clone(),
+                args: config.args.clone(),
+                when: config.when,
+                is_custom: true,
+            });
+        }
+
+        let default_keybindings = self
+            .defaults
+            .iter()
+            .filter(|default| {
+                default
+                    .keymap
+                    .is_none_or(|default_keymap| default_keymap == keymap)
+            })
+            .filter_map(|default| {
+                Some(Keybinding {
+                    keys: parse_keys(default.keys).ok()?,
+                    command_id: default.command_id.to_string(),
+                    args: Vec::new(),
+                    when: default.when,
+                    is_custom: false,
+                })
+            })
+            // The keybindings of the settings replace the default ones with the same keys
+            .filter(|default| {
+                !custom_keybindings
+                    .iter()
+                    .any(|custom| custom.replaces(default))
+            })
+            .collect::<Vec<Keybinding>>();
+
+        // Keybindings without a command only remove the default ones
+        custom_keybindings.retain(|keybinding| !keybinding.command_id.is_empty());
+
+        let mut keybindings = custom_keybindings;
+        keybindings.extend(default_keybindings);
+
+        // Every keybinding in use is compared with the rest, the default ones included
+        for (i, keybinding) in keybindings.iter().enumerate() {
+            conflicts.extend(
+                keybindings[i + 1..]
+                    .iter()
+                    .filter_map(|other| keybinding.conflict_with(other)),
+            );
+        }
+
+        EffectiveKeybindings {
+            keybindings,
+            conflicts,
+        }
+    }
+
+    /// Resolve the keybindings again if the settings changed since the last time.
+    /// Done once the defaults are registered and every time the settings are loaded.
+    pub fn update(&mut self, app_state: &AppState, editor_commands: &EditorCommands) {
+        let configs = &app_state.settings.keybindings;
+        let keymap = app_state.settings.editor.keymap;
+        let is_outdated =
+            self.resolved_from
+                .as_ref()
+                .is_none_or(|(resolved_configs, resolved_keymap)| {
+                    resolved_configs != configs || *resolved_keymap != keymap
+                });
+        if is_outdated {
+            self.effective = self.resolve(configs, keymap, editor_commands);
+            for conflict in &self.effective.conflicts {
+                info!("Keybindings: {conflict}");
+            }
+            self.resolved_from = Some((configs.clone(), keymap));
+        }
+    }
+
+    /// The keybindings in use, as last resolved.
+    pub fn effective(&self) -> &EffectiveKeybindings {
+        &self.effective
+    }
+
+    /// What the key does after the keys pressed so far, with the keys of the match.
+    /// A key that breaks a chord is looked up on its own.
+    fn lookup(
+        &self,
+        data: &KeyboardData,
+        context: KeybindingContext,
+    ) -> (Vec<KeyboardData>, KeybindingMatch) {
+        let keybindings = &self.effective;
+        let mut pressed = self.pressed.clone();
+        pressed.push(data.clone());
+        match keybindings.find(&pressed, context) {
+            KeybindingMatch::None if pressed.len() > 1 => {
+                let pressed = vec![data.clone()];
+                let keybinding_match = keybindings.find(&pressed, context);
+                (pressed, keybinding_match)
+            }
+            keybinding_match => (pressed, keybinding_match),
+        }
+    }
+
+    /// Whether the key runs a command or continues a chord, so it's not typed in the editor.
+    /// Asked by the editor before the key reaches [Keybindings::run].
+    pub fn is_bound(&self, data: &KeyboardData, app_state: &AppState) -> bool {
+        if is_modifier(data) {
+            return false;
+        }
+        let context = KeybindingContext::current(app_state);
+        !matches!(self.lookup(data, context).1, KeybindingMatch::None)
+    }
+
+    /// Run the command bound to the pressed keys, or wait for the rest of a chord.
+    pub fn run(
+        &mut self,
+        data: &KeyboardData,
+        editor_commands: &EditorCommands,
+        radio_app_state: RadioAppState,
+    ) {
+        // Modifiers alone don't make a chord move on
+        if is_modifier(data) {
+            return;
+        }
+
+        let context = KeybindingContext::current(&radio_app_state.read());
+
+        let (pressed, keybinding_match) = self.lookup(data, context);
+        match keybinding_match {
+            KeybindingMatch::Command(command_id, args) => {
+                self.pressed.clear();
+                editor_commands.trigger_with_args(&command_id, args);
+            }
+            KeybindingMatch::Pending => self.pressed = pressed,
+            KeybindingMatch::None => self.pressed.clear(),
+        }
+    }
+}
+
+/// Whether the key is only a modifier, e.g `Ctrl`.
+fn is_modifier(data: &KeyboardData) -> bool {
+    matches!(
+        data.key,
+        Key::Control | Key::Shift | Key::Alt | Key::AltGraph | Key::Meta
+    )
+}
@@ -1,7 +1,7 @@
 mod app;
 mod commands;
 mod editor;
-mod keyboard_shortcuts;
+mod keybindings;
 mod panels_tabs;
 mod settings;
 mod views;
@@ -9,7 +9,7 @@ mod views;
 pub use app::*;
 pub use commands::*;
 pub use editor::*;
-pub use keyboard_shortcuts::*;
+pub use keybindings::*;
 pub use panels_tabs::*;
 pub use settings::*;
 pub use views::*;
@@ -1,7 +1,11 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize, Serializer};
 use tracing::info;
 
-use crate::settings::load_settings;
+use crate::{indentation::Indentation, languages::LanguageConfig, settings::load_settings};
+
+use super::KeybindingConfig;
 
 fn human_number_serializer<S>(value: &f32, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -9,12 +13,55 @@ where
 {
     serializer.serialize_f64((*value as f64 * 100.0).trunc() / 100.0)
 }
-#[derive(Serialize, Deserialize, Debug)]
+/// Shortcuts used to edit the text, e.g `keymap = "emacs"`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Keymap {
+    #[default]
+    Default,
+    /// `Ctrl A/E` to move to the line start and end, `Ctrl K` and `Ctrl Y` to kill and yank...
+    Emacs,
+}
+
+/// Which whitespace is shown with marks, e.g `render_whitespace = "all"`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderWhitespace {
+    None,
+    /// Only the indentation.
+    #[default]
+    Leading,
+    /// Spaces as dots and tabs as arrows, with the trailing whitespace highlighted.
+    All,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct EditorSettings {
     #[serde(serialize_with = "human_number_serializer")]
     pub(crate) font_size: f32,
     #[serde(serialize_with = "human_number_serializer")]
     pub(crate) line_height: f32,
+    pub(crate) rainbow_brackets: bool,
+    /// Guess the indentation from the content of the files.
+    pub(crate) detect_indentation: bool,
+    pub(crate) indentation: Indentation,
+    /// Indentation by language name, e.g `[editor.languages_indentation.python]`.
+    pub(crate) languages_indentation: HashMap<String, Indentation>,
+    /// Edit with the modes and keys of Vim.
+    pub(crate) vim_mode: bool,
+    pub(crate) keymap: Keymap,
+    /// Wrap the long lines instead of scrolling horizontally.
+    pub(crate) soft_wrap: bool,
+    /// Column where the lines wrap, instead of the width of the editor.
+    pub(crate) wrap_column: Option<usize>,
+    pub(crate) render_whitespace: RenderWhitespace,
+    /// Show a mark where every line ends.
+    pub(crate) eol_markers: bool,
+    /// Show a vertical guide for every level of indentation.
+    pub(crate) indent_guides: bool,
+    /// Keep the undo history of the files for the next time they are opened.
+    pub(crate) persistent_history: bool,
 }
 
 impl Default for EditorSettings {
@@ -22,13 +69,29 @@ impl Default for EditorSettings {
         Self {
             font_size: 17.0,
             line_height: 1.6_f32,
+            rainbow_brackets: false,
+            detect_indentation: true,
+            indentation: Indentation::default(),
+            languages_indentation: HashMap::default(),
+            vim_mode: false,
+            keymap: Keymap::default(),
+            soft_wrap: false,
+            wrap_column: None,
+            render_whitespace: RenderWhitespace::default(),
+            eol_markers: false,
+            indent_guides: false,
+            persistent_history: true,
         }
     }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct AppSettings {
     pub(crate) editor: EditorSettings,
+    pub(crate) languages: Vec<LanguageConfig>,
+    /// Keys bound to commands, replacing the default ones with the same keys.
+    pub(crate) keybindings: Vec<KeybindingConfig>,
 }
 
 impl AppSettings {
@@ -7,11 +7,12 @@ pub enum EditorView {
     FilesExplorer,
     Commander,
     Search,
+    QuickOpen,
 }
 
 impl EditorView {
     pub fn is_popup(&self) -> bool {
-        matches!(self, Self::Search | Self::Commander)
+        matches!(self, Self::Search | Self::Commander | Self::QuickOpen)
     }
 }
 
@@ -22,6 +23,7 @@ impl Display for EditorView {
             Self::FilesExplorer => f.write_str("Files Explorer"),
             Self::Commander => f.write_str("Commander"),
             Self::Search => f.write_str("Search"),
+            Self::QuickOpen => f.write_str("Quick Open"),
         }
     }
 }
@@ -1,37 +1,199 @@
 use crate::{
-    state::{Channel, CommandRunContext, EditorCommands},
+    fuzzy::{matched_pieces, FuzzyMatcher},
+    state::{
+        Channel, CommandArgKind, CommandPrompt, CommandRunContext, EditorCommands,
+        EffectiveKeybindings, Keybindings, RadioAppState,
+    },
     Overlay, TextArea,
 };
 use dioxus_radio::prelude::use_radio;
 use freya::prelude::*;
 
+use super::path_completion::{base_folder, complete_path};
+
+/// Score added to the most recently used command, the next ones get a bit less.
+const RECENCY_BONUS: u32 = 60;
+
+/// An option of the Commander, a command or a value of the argument being asked.
+struct CommanderOption {
+    /// Id of the command, or the value of the argument.
+    value: String,
+    text: String,
+    /// Indexes of the chars of the text that matched the query.
+    indices: Vec<usize>,
+    shortcut: Option<String>,
+    /// Whether it's a folder that completes a path.
+    is_folder: bool,
+}
+
+impl CommanderOption {
+    fn new(value: String, text: String, indices: Vec<usize>) -> Self {
+        Self {
+            value,
+            text,
+            indices,
+            shortcut: None,
+            is_folder: false,
+        }
+    }
+}
+
+/// The visible commands that fuzzy match the query, the recently used ones first.
+fn command_options(
+    commands: &EditorCommands,
+    keybindings: &EffectiveKeybindings,
+    query: &str,
+) -> Vec<CommanderOption> {
+    let mut matcher = FuzzyMatcher::new(query);
+    let mut matches = commands
+        .commands
+        .iter()
+        .filter(|(_, command)| command.is_visible())
+        .filter_map(|(id, command)| {
+            let (score, indices) = matcher.fuzzy_match(command.text())?;
+            // Recently used commands go first
+            let recency = commands
+                .recent_commands
+                .rank(id)
+                .map(|rank| RECENCY_BONUS.saturating_sub(rank as u32))
+                .unwrap_or_default();
+            let option = CommanderOption {
+                shortcut: keybindings.keys_of(id),
+                ..CommanderOption::new(id.clone(), command.text().to_string(), indices)
+            };
+            Some((score + recency, option))
+        })
+        .collect::<Vec<(u32, CommanderOption)>>();
+    matches.sort_unstable_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then_with(|| a.text.cmp(&b.text))
+            .then_with(|| a.value.cmp(&b.value))
+    });
+    matches.into_iter().map(|(_, option)| option).collect()
+}
+
+/// The values of an argument that match the query.
+fn arg_options(
+    kind: &CommandArgKind,
+    query: &str,
+    radio_app_state: &RadioAppState,
+) -> Vec<CommanderOption> {
+    match kind {
+        CommandArgKind::Text => Vec::new(),
+        CommandArgKind::Choice(choices) => {
+            let mut matcher = FuzzyMatcher::new(query);
+            let mut matches = choices
+                .iter()
+                .filter_map(|choice| {
+                    let (score, indices) = matcher.fuzzy_match(&choice.text)?;
+                    let option =
+                        CommanderOption::new(choice.value.clone(), choice.text.clone(), indices);
+                    Some((score, option))
+                })
+                .collect::<Vec<(u32, CommanderOption)>>();
+            // The choices keep their order when they match as well
+            matches.sort_by(|(a_score, _), (b_score, _)| b_score.cmp(a_score));
+            matches.into_iter().map(|(_, option)| option).collect()
+        }
+        CommandArgKind::FilePath => {
+            let base_folder = base_folder(&radio_app_state.read());
+            complete_path(&base_folder, query)
+                .into_iter()
+                .map(|completion| CommanderOption {
+                    is_folder: completion.is_folder,
+                    ..CommanderOption::new(
+                        completion.path.clone(),
+                        completion.path,
+                        completion.indices,
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+/// Run the command of the prompt if it has all its arguments, or ask for the next one.
+fn submit_prompt(
+    prompt: CommandPrompt,
+    mut editor_commands: Signal<EditorCommands>,
+    mut radio_app_state: RadioAppState,
+    mut value: Signal<String>,
+    mut selected: Signal<usize>,
+) {
+    let args_len = {
+        let editor_commands = editor_commands.read();
+        let Some(command) = editor_commands.commands.get(&prompt.command_id) else {
+            return;
+        };
+        command.args().len()
+    };
+
+    if prompt.args.len() < args_len {
+        radio_app_state
+            .write_channel(Channel::Global)
+            .commander_prompt = Some(prompt);
+        value.set(String::new());
+        selected.set(0);
+        return;
+    }
+
+    radio_app_state
+        .write_channel(Channel::Global)
+        .commander_prompt = None;
+    let mut ctx = CommandRunContext::with_args(prompt.args);
+
+    // Run the command
+    if let Some(command) = editor_commands.read().commands.get(&prompt.command_id) {
+        command.run(&mut ctx);
+    }
+
+    editor_commands
+        .write()
+        .push_recent_command(&prompt.command_id);
+
+    if ctx.focus_previous_view {
+        let mut app_state = radio_app_state.write();
+        app_state.focus_previous_view();
+    }
+}
+
 #[derive(Props, Clone, PartialEq)]
 pub struct CommanderProps {
     editor_commands: Signal<EditorCommands>,
+    keybindings: Signal<Keybindings>,
 }
 
 #[allow(non_snake_case)]
-pub fn Commander(CommanderProps { editor_commands }: CommanderProps) -> Element {
-    let mut radio_app_state = use_radio(Channel::Global);
+pub fn Commander(
+    CommanderProps {
+        editor_commands,
+        keybindings,
+    }: CommanderProps,
+) -> Element {
+    let radio_app_state = use_radio(Channel::Global);
     let mut value = use_signal(String::new);
     let mut selected = use_signal(|| 0);
     let mut focus = use_focus();
 
     let commands = editor_commands.read();
-    let filtered_commands = commands
-        .commands
-        .iter()
-        .filter_map(|(id, command)| {
-            if value.read().is_empty() {
-                command.is_visible()
-            } else {
-                command.is_visible() && command.matches(value.read().as_str())
-            }
-            .then_some(id.clone())
-        })
-        .collect::<Vec<String>>();
-    let filtered_commands_len = filtered_commands.len();
-    let options_height = ((filtered_commands_len.max(1)) * 30).max(175);
+    let keybindings = keybindings.read();
+    let effective_keybindings = keybindings.effective();
+    let prompt = radio_app_state.read().commander_prompt.clone();
+
+    // The argument being asked, with the text of its command
+    let prompt_arg = prompt.as_ref().and_then(|prompt| {
+        let command = commands.commands.get(&prompt.command_id)?;
+        let arg = command.args().into_iter().nth(prompt.args.len())?;
+        Some((command.text().to_string(), arg))
+    });
+
+    let options = match &prompt_arg {
+        Some((_, arg)) => arg_options(&arg.kind, &value.read(), &radio_app_state),
+        None => command_options(&commands, effective_keybindings, &value.read()),
+    };
+    let options_len = options.len();
+    let options_height = ((options_len.max(1)) * 30).max(175);
 
     let onchange = move |v| {
         if *value.read() != v {
@@ -40,23 +202,42 @@ pub fn Commander(CommanderProps { editor_commands }: CommanderProps) -> Element
         }
     };
 
-    let command_id = filtered_commands.get(selected()).cloned();
+    let selected_option = options
+        .get(selected())
+        .map(|option| (option.value.clone(), option.is_folder));
+    let arg_kind = prompt_arg.as_ref().map(|(_, arg)| arg.kind.clone());
 
-    let onsubmit = move |_: String| {
-        let editor_commands = editor_commands.read();
-        let command = command_id
-            .as_ref()
-            .and_then(|command_i| editor_commands.commands.get(command_i));
-        if let Some(command) = command {
-            let mut ctx = CommandRunContext::default();
-
-            // Run the command
-            command.run(&mut ctx);
-
-            if ctx.focus_previous_view {
-                let mut app_state = radio_app_state.write();
-                app_state.focus_previous_view();
+    let onsubmit = move |typed: String| {
+        let (Some(prompt), Some(arg_kind)) = (&prompt, &arg_kind) else {
+            if let Some((command_id, _)) = &selected_option {
+                let prompt = CommandPrompt {
+                    command_id: command_id.clone(),
+                    args: Vec::new(),
+                };
+                submit_prompt(prompt, editor_commands, radio_app_state, value, selected);
             }
+            return;
+        };
+
+        let arg = match arg_kind {
+            CommandArgKind::Text => Some(typed),
+            CommandArgKind::Choice(_) => selected_option.as_ref().map(|(value, _)| value.clone()),
+            CommandArgKind::FilePath => match &selected_option {
+                // Keep completing inside of the folder
+                Some((path, true)) => {
+                    value.set(path.clone());
+                    selected.set(0);
+                    return;
+                }
+                Some((path, false)) => Some(path.clone()),
+                None => (!typed.is_empty()).then_some(typed),
+            },
+        };
+
+        if let Some(arg) = arg {
+            let mut prompt = prompt.clone();
+            prompt.args.push(arg);
+            submit_prompt(prompt, editor_commands, radio_app_state, value, selected);
         }
     };
 
@@ -65,8 +246,8 @@ pub fn Commander(CommanderProps { editor_commands }: CommanderProps) -> Element
         focus.prevent_navigation();
         match e.code {
             Code::ArrowDown => {
-                if filtered_commands_len > 0 {
-                    if *selected.read() < filtered_commands_len - 1 {
+                if options_len > 0 {
+                    if *selected.read() < options_len - 1 {
                         *selected.write() += 1;
                     } else {
                         selected.set(0);
@@ -74,36 +255,60 @@ pub fn Commander(CommanderProps { editor_commands }: CommanderProps) -> Element
                 }
             }
             Code::ArrowUp => {
-                if selected() > 0 && filtered_commands_len > 0 {
+                if selected() > 0 && options_len > 0 {
                     *selected.write() -= 1;
-                } else {
-                    selected.set(filtered_commands_len - 1);
+                } else if options_len > 0 {
+                    selected.set(options_len - 1);
                 }
             }
             _ => {}
         }
     };
 
+    let placeholder = match &prompt_arg {
+        Some((_, arg)) => arg.prompt.clone(),
+        None => "Run a command...".to_string(),
+    };
+    let not_found = match &arg_kind {
+        None => Some("Command Not Found"),
+        Some(CommandArgKind::Text) => None,
+        Some(_) => Some("Nothing Found"),
+    };
+
     rsx!(
         Overlay {
             rect {
                 onkeydown,
                 spacing: "5",
+                if let Some((command_text, _)) = &prompt_arg {
+                    label {
+                        margin: "0 6",
+                        color: "rgb(165, 165, 165)",
+                        "{command_text}"
+                    }
+                }
                 TextArea {
-                    placeholder: "Run a command...",
+                    placeholder,
                     value: "{value}",
                     onchange,
                     onsubmit,
                 }
                 ScrollView {
                     height: "{options_height}",
-                    if filtered_commands.is_empty() {
-                        {commander_option("not-found", "Command Not Found", true)}
+                    if options.is_empty() {
+                        if let Some(not_found) = not_found {
+                            {commander_option("not-found", not_found, &[], None, true)}
+                        }
                     }
-                    for (n, command_id) in filtered_commands.into_iter().enumerate() {
+                    for (n, option) in options.into_iter().enumerate() {
                         {
-                            let command = commands.commands.get(&command_id).unwrap();
-                            commander_option(&command_id, command.text(), n == selected())
+                            commander_option(
+                                &option.value,
+                                &option.text,
+                                &option.indices,
+                                option.shortcut.as_deref(),
+                                n == selected(),
+                            )
                         }
                     }
                 }
@@ -112,24 +317,47 @@ pub fn Commander(CommanderProps { editor_commands }: CommanderProps) -> Element
     )
 }
 
-fn commander_option(command_id: &str, command_text: &str, is_selected: bool) -> Element {
+fn commander_option(
+    key: &str,
+    text: &str,
+    indices: &[usize],
+    shortcut: Option<&str>,
+    is_selected: bool,
+) -> Element {
     let background = if is_selected {
         "rgb(29, 32, 33)"
     } else {
         "none"
     };
+    let pieces = matched_pieces(text, indices);
 
     rsx!(
         rect {
             background,
-            key: "{command_id}",
+            key: "{key}",
             padding: "8 6",
             width: "100%",
             height: "30",
             corner_radius: "10",
-            main_align: "center",
-            label {
-                "{command_text}"
+            direction: "horizontal",
+            main_align: "space-between",
+            cross_align: "center",
+            paragraph {
+                max_lines: "1",
+                for (i, (piece, is_match)) in pieces.into_iter().enumerate() {
+                    text {
+                        key: "{i}",
+                        color: if is_match { "rgb(255, 197, 61)" } else { "rgb(215, 215, 215)" },
+                        font_weight: if is_match { "bold" } else { "normal" },
+                        "{piece}"
+                    }
+                }
+            }
+            if let Some(shortcut) = shortcut {
+                label {
+                    color: "rgb(165, 165, 165)",
+                    "{shortcut}"
+                }
             }
         }
     )
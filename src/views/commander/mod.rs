@@ -1 +1,2 @@
 pub mod commander_ui;
+pub mod path_completion;
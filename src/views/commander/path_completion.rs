@@ -0,0 +1,87 @@
+use std::{
+    fs::read_dir,
+    path::{is_separator, Path, PathBuf},
+};
+
+use crate::{fuzzy::FuzzyMatcher, state::AppState};
+
+/// Most entries shown when completing a path.
+const MAX_COMPLETIONS: usize = 200;
+
+/// A file or folder that completes a typed path.
+#[derive(Clone, PartialEq)]
+pub struct PathCompletion {
+    /// The typed path completed with the entry, folders end with a separator.
+    pub path: String,
+    pub is_folder: bool,
+    /// Indexes of the chars of `path` that matched.
+    pub indices: Vec<usize>,
+}
+
+/// Folder where the relative paths start, the first open folder or the current one.
+pub fn base_folder(app_state: &AppState) -> PathBuf {
+    app_state
+        .file_explorer
+        .folders
+        .first()
+        .map(|folder| folder.path().clone())
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+}
+
+/// Path of a typed path, `~` being the home folder and relative ones starting from the base folder.
+pub fn resolve_path(base_folder: &Path, path: &str) -> PathBuf {
+    let home_dir = home::home_dir();
+    match (path.strip_prefix('~'), home_dir) {
+        (Some(rest), Some(home_dir)) if rest.is_empty() || rest.starts_with(is_separator) => {
+            home_dir.join(rest.trim_start_matches(is_separator))
+        }
+        _ => base_folder.join(path),
+    }
+}
+
+/// Entries of the folder of a typed path that fuzzy match its last part, the best ones first.
+pub fn complete_path(base_folder: &Path, path: &str) -> Vec<PathCompletion> {
+    let (folder, name) = match path.rfind(is_separator) {
+        Some(idx) => path.split_at(idx + 1),
+        None => ("", path),
+    };
+    let Ok(entries) = read_dir(resolve_path(base_folder, folder)) else {
+        return Vec::new();
+    };
+
+    let mut matcher = FuzzyMatcher::for_paths(name);
+    let folder_len = folder.chars().count();
+    let mut completions = entries
+        .flatten()
+        .filter_map(|entry| {
+            let entry_name = entry.file_name().to_string_lossy().to_string();
+            // Hidden files are only shown when asked for
+            if entry_name.starts_with('.') && !name.starts_with('.') {
+                return None;
+            }
+            let (score, indices) = matcher.fuzzy_match(&entry_name)?;
+            let is_folder = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            let separator = if is_folder { "/" } else { "" };
+            Some((
+                score,
+                PathCompletion {
+                    path: format!("{folder}{entry_name}{separator}"),
+                    is_folder,
+                    indices: indices.into_iter().map(|idx| idx + folder_len).collect(),
+                },
+            ))
+        })
+        .collect::<Vec<(u32, PathCompletion)>>();
+    completions.sort_unstable_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then(b.is_folder.cmp(&a.is_folder))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    completions
+        .into_iter()
+        .take(MAX_COMPLETIONS)
+        .map(|(_, completion)| completion)
+        .collect()
+}
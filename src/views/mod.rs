@@ -1,4 +1,5 @@
 pub mod commander;
 pub mod file_explorer;
 pub mod panels;
+pub mod quick_open;
 pub mod search;
@@ -0,0 +1,93 @@
+use freya::events::Key;
+use freya::hooks::TextEditor;
+
+use super::{EditorCursor, EditorData};
+
+/// A rectangular selection, made of one cursor per line.
+/// Points are `(line, column)`, with columns in UTF-16 code units.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ColumnSelection {
+    pub anchor: (usize, usize),
+    pub head: (usize, usize),
+}
+
+impl EditorData {
+    /// The column selection, or an empty one in the cursor.
+    fn current_column_selection(&self) -> ColumnSelection {
+        self.column_selection.unwrap_or_else(|| {
+            let point = (self.cursor_row(), self.cursor_col());
+            ColumnSelection {
+                anchor: point,
+                head: point,
+            }
+        })
+    }
+
+    /// Move the head of the column selection with the arrow keys.
+    pub fn extend_column_selection(&mut self, key: &Key) -> bool {
+        let ColumnSelection { anchor, head } = self.current_column_selection();
+        let (row, col) = head;
+
+        let head = match key {
+            Key::ArrowUp => (row.saturating_sub(1), col),
+            Key::ArrowDown => ((row + 1).min(self.len_lines() - 1), col),
+            Key::ArrowLeft => (row, col.saturating_sub(1)),
+            Key::ArrowRight => (row, col + 1),
+            _ => return false,
+        };
+
+        // Don't go further than the longest of the selected lines
+        let longest_line = (anchor.0.min(head.0)..=anchor.0.max(head.0))
+            .map(|line| self.line_position(line, usize::MAX) - self.line_position(line, 0))
+            .max()
+            .unwrap_or_default();
+        let head = (head.0, head.1.min(longest_line));
+
+        self.set_column_selection(ColumnSelection { anchor, head });
+        self.request_scroll_to_cursor();
+        true
+    }
+
+    /// Select from where the dragging started until the given point.
+    pub fn drag_column_selection(&mut self, line: usize, col: usize) {
+        let ColumnSelection { anchor, .. } = self.current_column_selection();
+        self.set_column_selection(ColumnSelection {
+            anchor,
+            head: (line, col),
+        });
+    }
+
+    /// Replace the cursors with one cursor for every line of the column selection.
+    /// The primary cursor is the one in the line of the head.
+    fn set_column_selection(&mut self, column_selection: ColumnSelection) {
+        let ColumnSelection {
+            anchor: (anchor_row, anchor_col),
+            head: (head_row, head_col),
+        } = column_selection;
+
+        let rows = if anchor_row <= head_row {
+            (anchor_row..=head_row).collect::<Vec<usize>>()
+        } else {
+            (head_row..=anchor_row).rev().collect()
+        };
+
+        let mut cursors = rows
+            .into_iter()
+            .filter(|row| *row == head_row || !self.folding.is_hidden(*row))
+            .map(|row| {
+                let from = self.line_position(row, anchor_col);
+                let to = self.line_position(row, head_col);
+                EditorCursor {
+                    pos: to,
+                    selection: (from != to).then_some((from, to)),
+                }
+            })
+            .collect::<Vec<EditorCursor>>();
+
+        if let Some(primary) = cursors.pop() {
+            self.load_cursor(primary);
+        }
+        self.extra_cursors = cursors;
+        self.column_selection = Some(column_selection);
+    }
+}
@@ -1,12 +1,53 @@
+use std::{io, mem, path::Path, time::Duration};
+
 use freya::prelude::spawn;
-use tokio::fs::OpenOptions;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
 
 use crate::{
     constants::{BASE_FONT_SIZE, MAX_FONT_SIZE},
-    state::{AppStateUtils, Channel, CommandRunContext, EditorCommand, RadioAppState},
+    encoding::{Encoding, LineEnding},
+    fs::FSTransport,
+    indentation::Indentation,
+    lsp::LanguageId,
+    state::{
+        AppStateUtils, Channel, CommandArg, CommandChoice, CommandRunContext, EditorCommand,
+        RadioAppState,
+    },
 };
 
 use crate::views::panels::tabs::editor::utils::AppStateEditorUtils;
+use crate::views::panels::tabs::history::HistoryTab;
+
+use super::{EditorData, KillRing, SortOrder};
+
+/// Run an action over the editor of the active tab, if any.
+fn with_active_editor(mut radio_app_state: RadioAppState, action: impl FnOnce(&mut EditorData)) {
+    let Some(active_tab) = radio_app_state.get_active_tab() else {
+        return;
+    };
+
+    let mut app_state = radio_app_state.write_channel(Channel::follow_tab(active_tab));
+    if let Some(editor_tab) = app_state.try_editor_tab_mut(active_tab) {
+        action(&mut editor_tab.editor);
+    }
+}
+
+/// Run an action over the editor of the active tab and the kill ring shared by all the editors.
+fn with_active_editor_and_kill_ring(
+    mut radio_app_state: RadioAppState,
+    action: impl FnOnce(&mut EditorData, &mut KillRing),
+) {
+    let Some(active_tab) = radio_app_state.get_active_tab() else {
+        return;
+    };
+
+    let mut app_state = radio_app_state.write_channel(Channel::follow_tab(active_tab));
+    let mut kill_ring = mem::take(&mut app_state.kill_ring);
+    if let Some(editor_tab) = app_state.try_editor_tab_mut(active_tab) {
+        action(&mut editor_tab.editor, &mut kill_ring);
+    }
+    app_state.kill_ring = kill_ring;
+}
 
 #[derive(Clone)]
 pub struct IncreaseFontSizeCommand(pub RadioAppState);
@@ -18,10 +59,6 @@ impl IncreaseFontSizeCommand {
 }
 
 impl EditorCommand for IncreaseFontSizeCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(&input.to_lowercase())
-    }
-
     fn id(&self) -> &str {
         Self::id()
     }
@@ -48,10 +85,6 @@ impl DecreaseFontSizeCommand {
 }
 
 impl EditorCommand for DecreaseFontSizeCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(&input.to_lowercase())
-    }
-
     fn id(&self) -> &str {
         Self::id()
     }
@@ -68,6 +101,56 @@ impl EditorCommand for DecreaseFontSizeCommand {
     }
 }
 
+/// Replace the content of a file.
+async fn write_file(transport: &FSTransport, path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut writer = transport
+        .open(path, OpenOptions::new().write(true).truncate(true))
+        .await?;
+    writer.write_all(content).await?;
+    writer.flush().await
+}
+
+/// Save the file of the active tab, and close the tab once it's written if asked to.
+fn save_active_file(mut radio_app_state: RadioAppState, close: bool) {
+    let Some(active_tab) = radio_app_state.get_active_tab() else {
+        return;
+    };
+    let editor_data = radio_app_state.read().editor_tab_data(active_tab);
+    let Some((Some(file_path), content, change, transport)) = editor_data else {
+        return;
+    };
+
+    spawn(async move {
+        let result = write_file(&transport, &file_path, &content).await;
+        let channel = if close {
+            Channel::Global
+        } else {
+            Channel::follow_tab(active_tab)
+        };
+        let mut app_state = radio_app_state.write_channel(channel);
+        let persistent_history = app_state.settings.editor.persistent_history;
+        // The tab might be closed already
+        let Some(editor_tab) = app_state.try_editor_tab_mut(active_tab) else {
+            return;
+        };
+        match result {
+            // Only what was written is saved, not the edits made meanwhile
+            Ok(()) => {
+                editor_tab.editor.mark_as_saved(change);
+                if persistent_history {
+                    editor_tab.editor.save_history();
+                }
+                if close && !editor_tab.editor.is_edited() {
+                    app_state.close_tab(active_tab);
+                }
+            }
+            Err(err) => {
+                editor_tab.editor.message = Some(format!("Couldn't save the file: {err}"));
+            }
+        }
+    });
+}
+
 #[derive(Clone)]
 pub struct SaveFileCommand(pub RadioAppState);
 
@@ -78,39 +161,1314 @@ impl SaveFileCommand {
 }
 
 impl EditorCommand for SaveFileCommand {
-    fn matches(&self, input: &str) -> bool {
-        self.text().to_lowercase().contains(&input.to_lowercase())
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Save File"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        save_active_file(self.0, false);
+    }
+}
+
+#[derive(Clone)]
+pub struct SaveAndCloseFileCommand(pub RadioAppState);
+
+impl SaveAndCloseFileCommand {
+    pub fn id() -> &'static str {
+        "save-and-close-file"
     }
+}
 
+impl EditorCommand for SaveAndCloseFileCommand {
     fn id(&self) -> &str {
         Self::id()
     }
 
     fn text(&self) -> &str {
-        "Save File"
+        "Save and Close File"
     }
 
     fn run(&self, _ctx: &mut CommandRunContext) {
-        let mut radio_app_state = self.0;
-        let active_tab = radio_app_state.get_active_tab();
-
-        if let Some(active_tab) = active_tab {
-            let editor_data = radio_app_state.read().editor_tab_data(active_tab);
-
-            if let Some((Some(file_path), rope, transport)) = editor_data {
-                spawn(async move {
-                    let writer = transport
-                        .open(&file_path, OpenOptions::new().write(true).truncate(true))
-                        .await
-                        .unwrap();
-                    let std_writer = writer.into_std().await;
-                    rope.borrow_mut().write_to(std_writer).unwrap();
-                    let mut app_state =
-                        radio_app_state.write_channel(Channel::follow_tab(active_tab));
-                    let editor_tab = app_state.editor_tab_mut(active_tab);
-                    editor_tab.editor.mark_as_saved()
-                });
-            }
-        }
+        save_active_file(self.0, true);
+    }
+}
+
+#[derive(Clone)]
+pub struct JumpToMatchingBracketCommand(pub RadioAppState);
+
+impl JumpToMatchingBracketCommand {
+    pub fn id() -> &'static str {
+        "jump-to-matching-bracket"
+    }
+}
+
+impl EditorCommand for JumpToMatchingBracketCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Jump to Matching Bracket"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.jump_to_matching_bracket();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct SelectToBracketCommand(pub RadioAppState);
+
+impl SelectToBracketCommand {
+    pub fn id() -> &'static str {
+        "select-to-bracket"
+    }
+}
+
+impl EditorCommand for SelectToBracketCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Select to Bracket"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.select_to_bracket();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct ToggleFoldCommand(pub RadioAppState);
+
+impl ToggleFoldCommand {
+    pub fn id() -> &'static str {
+        "toggle-fold"
+    }
+}
+
+impl EditorCommand for ToggleFoldCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Toggle Fold"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.toggle_fold_at_cursor();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct FoldAllCommand(pub RadioAppState);
+
+impl FoldAllCommand {
+    pub fn id() -> &'static str {
+        "fold-all"
+    }
+}
+
+impl EditorCommand for FoldAllCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Fold All"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| editor.fold_all());
+    }
+}
+
+#[derive(Clone)]
+pub struct UnfoldAllCommand(pub RadioAppState);
+
+impl UnfoldAllCommand {
+    pub fn id() -> &'static str {
+        "unfold-all"
+    }
+}
+
+impl EditorCommand for UnfoldAllCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Unfold All"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| editor.unfold_all());
+    }
+}
+
+#[derive(Clone)]
+pub struct AddCursorAboveCommand(pub RadioAppState);
+
+impl AddCursorAboveCommand {
+    pub fn id() -> &'static str {
+        "add-cursor-above"
+    }
+}
+
+impl EditorCommand for AddCursorAboveCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Add Cursor Above"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.add_cursor_vertically(false);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct AddCursorBelowCommand(pub RadioAppState);
+
+impl AddCursorBelowCommand {
+    pub fn id() -> &'static str {
+        "add-cursor-below"
+    }
+}
+
+impl EditorCommand for AddCursorBelowCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Add Cursor Below"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.add_cursor_vertically(true);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct AddNextOccurrenceCommand(pub RadioAppState);
+
+impl AddNextOccurrenceCommand {
+    pub fn id() -> &'static str {
+        "add-next-occurrence"
+    }
+}
+
+impl EditorCommand for AddNextOccurrenceCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Add Next Occurrence"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.add_next_occurrence();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct SelectAllOccurrencesCommand(pub RadioAppState);
+
+impl SelectAllOccurrencesCommand {
+    pub fn id() -> &'static str {
+        "select-all-occurrences"
+    }
+}
+
+impl EditorCommand for SelectAllOccurrencesCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Select All Occurrences"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.select_all_occurrences();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct ConvertIndentationToSpacesCommand(pub RadioAppState);
+
+impl ConvertIndentationToSpacesCommand {
+    pub fn id() -> &'static str {
+        "convert-indentation-to-spaces"
+    }
+}
+
+impl EditorCommand for ConvertIndentationToSpacesCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Convert Indentation to Spaces"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| editor.convert_indentation(false));
+    }
+}
+
+#[derive(Clone)]
+pub struct ConvertIndentationToTabsCommand(pub RadioAppState);
+
+impl ConvertIndentationToTabsCommand {
+    pub fn id() -> &'static str {
+        "convert-indentation-to-tabs"
+    }
+}
+
+impl EditorCommand for ConvertIndentationToTabsCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Convert Indentation to Tabs"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| editor.convert_indentation(true));
+    }
+}
+
+#[derive(Clone)]
+pub struct ToggleLineCommentCommand(pub RadioAppState);
+
+impl ToggleLineCommentCommand {
+    pub fn id() -> &'static str {
+        "toggle-line-comment"
+    }
+}
+
+impl EditorCommand for ToggleLineCommentCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Toggle Line Comment"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.toggle_line_comment();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct ToggleBlockCommentCommand(pub RadioAppState);
+
+impl ToggleBlockCommentCommand {
+    pub fn id() -> &'static str {
+        "toggle-block-comment"
+    }
+}
+
+impl EditorCommand for ToggleBlockCommentCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Toggle Block Comment"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.toggle_block_comment();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct ReopenWithEncodingCommand {
+    radio_app_state: RadioAppState,
+    encoding: Encoding,
+    id: String,
+    text: String,
+}
+
+impl ReopenWithEncodingCommand {
+    pub fn new(radio_app_state: RadioAppState, encoding: Encoding) -> Self {
+        Self {
+            radio_app_state,
+            encoding,
+            id: format!("reopen-with-encoding-{}", encoding.id()),
+            text: format!("Reopen with Encoding: {encoding}"),
+        }
+    }
+}
+
+impl EditorCommand for ReopenWithEncodingCommand {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        let mut radio_app_state = self.radio_app_state;
+        let encoding = self.encoding;
+        let Some(active_tab) = radio_app_state.get_active_tab() else {
+            return;
+        };
+
+        let editor_data =
+            radio_app_state
+                .read()
+                .try_editor_tab(active_tab)
+                .and_then(|editor_tab| {
+                    let path = editor_tab.editor.path()?.clone();
+                    Some((path, editor_tab.editor.transport.clone()))
+                });
+
+        if let Some((file_path, transport)) = editor_data {
+            spawn(async move {
+                let Ok(content) = transport.read(&file_path).await else {
+                    return;
+                };
+                let content = encoding.decode(&content);
+
+                let mut app_state = radio_app_state.write_channel(Channel::follow_tab(active_tab));
+                let font_size = app_state.font_size();
+                let font_collection = app_state.font_collection.clone();
+                if let Some(editor_tab) = app_state.try_editor_tab_mut(active_tab) {
+                    if editor_tab.editor.reload(&content, encoding) {
+                        editor_tab
+                            .editor
+                            .measure_longest_line(font_size, &font_collection);
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ChangeLineEndingCommand {
+    radio_app_state: RadioAppState,
+    line_ending: LineEnding,
+    id: String,
+    text: String,
+}
+
+impl ChangeLineEndingCommand {
+    pub fn new(radio_app_state: RadioAppState, line_ending: LineEnding) -> Self {
+        Self {
+            radio_app_state,
+            line_ending,
+            id: format!(
+                "change-line-ending-{}",
+                line_ending.to_string().to_lowercase()
+            ),
+            text: format!("Change Line Ending to {line_ending}"),
+        }
+    }
+}
+
+impl EditorCommand for ChangeLineEndingCommand {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        let line_ending = self.line_ending;
+        with_active_editor(self.radio_app_state, |editor| {
+            editor.change_line_ending(line_ending)
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct MoveLinesUpCommand(pub RadioAppState);
+
+impl MoveLinesUpCommand {
+    pub fn id() -> &'static str {
+        "move-lines-up"
+    }
+}
+
+impl EditorCommand for MoveLinesUpCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Move Lines Up"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.move_lines(true);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct MoveLinesDownCommand(pub RadioAppState);
+
+impl MoveLinesDownCommand {
+    pub fn id() -> &'static str {
+        "move-lines-down"
+    }
+}
+
+impl EditorCommand for MoveLinesDownCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Move Lines Down"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.move_lines(false);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct DuplicateSelectionCommand(pub RadioAppState);
+
+impl DuplicateSelectionCommand {
+    pub fn id() -> &'static str {
+        "duplicate-selection"
+    }
+}
+
+impl EditorCommand for DuplicateSelectionCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Duplicate Selection"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.duplicate_selection();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct DeleteLinesCommand(pub RadioAppState);
+
+impl DeleteLinesCommand {
+    pub fn id() -> &'static str {
+        "delete-lines"
+    }
+}
+
+impl EditorCommand for DeleteLinesCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Delete Lines"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.delete_lines();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct JoinLinesCommand(pub RadioAppState);
+
+impl JoinLinesCommand {
+    pub fn id() -> &'static str {
+        "join-lines"
+    }
+}
+
+impl EditorCommand for JoinLinesCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Join Lines"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.join_lines();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct InsertLineAboveCommand(pub RadioAppState);
+
+impl InsertLineAboveCommand {
+    pub fn id() -> &'static str {
+        "insert-line-above"
+    }
+}
+
+impl EditorCommand for InsertLineAboveCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Insert Line Above"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.insert_line(true);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct InsertLineBelowCommand(pub RadioAppState);
+
+impl InsertLineBelowCommand {
+    pub fn id() -> &'static str {
+        "insert-line-below"
+    }
+}
+
+impl EditorCommand for InsertLineBelowCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Insert Line Below"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.insert_line(false);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct ReverseLinesCommand(pub RadioAppState);
+
+impl ReverseLinesCommand {
+    pub fn id() -> &'static str {
+        "reverse-lines"
+    }
+}
+
+impl EditorCommand for ReverseLinesCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Reverse Lines"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.reverse_lines();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct TrimTrailingWhitespaceCommand(pub RadioAppState);
+
+impl TrimTrailingWhitespaceCommand {
+    pub fn id() -> &'static str {
+        "trim-trailing-whitespace"
+    }
+}
+
+impl EditorCommand for TrimTrailingWhitespaceCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Trim Trailing Whitespace"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.trim_trailing_whitespace();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct SortLinesCommand {
+    radio_app_state: RadioAppState,
+    order: SortOrder,
+    id: String,
+    text: String,
+}
+
+impl SortLinesCommand {
+    pub fn new(radio_app_state: RadioAppState, order: SortOrder) -> Self {
+        Self {
+            radio_app_state,
+            order,
+            id: format!("sort-lines-{}", order.to_string().to_lowercase()),
+            text: format!("Sort Lines: {order}"),
+        }
+    }
+}
+
+impl EditorCommand for SortLinesCommand {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        let order = self.order;
+        with_active_editor(self.radio_app_state, |editor| {
+            editor.sort_lines(order);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct MoveToLineStartCommand(pub RadioAppState);
+
+impl MoveToLineStartCommand {
+    pub fn id() -> &'static str {
+        "move-to-line-start"
+    }
+}
+
+impl EditorCommand for MoveToLineStartCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Move To Line Start"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.move_to_line_boundary(false);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct MoveToLineEndCommand(pub RadioAppState);
+
+impl MoveToLineEndCommand {
+    pub fn id() -> &'static str {
+        "move-to-line-end"
+    }
+}
+
+impl EditorCommand for MoveToLineEndCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Move To Line End"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.move_to_line_boundary(true);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct MoveWordForwardCommand(pub RadioAppState);
+
+impl MoveWordForwardCommand {
+    pub fn id() -> &'static str {
+        "move-word-forward"
+    }
+}
+
+impl EditorCommand for MoveWordForwardCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Move Word Forward"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.move_word(true);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct MoveWordBackwardCommand(pub RadioAppState);
+
+impl MoveWordBackwardCommand {
+    pub fn id() -> &'static str {
+        "move-word-backward"
+    }
+}
+
+impl EditorCommand for MoveWordBackwardCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Move Word Backward"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.move_word(false);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct SetMarkCommand(pub RadioAppState);
+
+impl SetMarkCommand {
+    pub fn id() -> &'static str {
+        "set-mark"
+    }
+}
+
+impl EditorCommand for SetMarkCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Set Mark"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.set_mark();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct CancelMarkCommand(pub RadioAppState);
+
+impl CancelMarkCommand {
+    pub fn id() -> &'static str {
+        "cancel-mark"
+    }
+}
+
+impl EditorCommand for CancelMarkCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Cancel Mark"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.cancel_mark();
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct KillLineCommand(pub RadioAppState);
+
+impl KillLineCommand {
+    pub fn id() -> &'static str {
+        "kill-line"
+    }
+}
+
+impl EditorCommand for KillLineCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Kill Line"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor_and_kill_ring(self.0, |editor, kill_ring| {
+            editor.kill_line(kill_ring);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct CopyRegionCommand(pub RadioAppState);
+
+impl CopyRegionCommand {
+    pub fn id() -> &'static str {
+        "copy-region"
+    }
+}
+
+impl EditorCommand for CopyRegionCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Copy Region"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor_and_kill_ring(self.0, |editor, kill_ring| {
+            editor.copy_region(kill_ring);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct YankCommand(pub RadioAppState);
+
+impl YankCommand {
+    pub fn id() -> &'static str {
+        "yank"
+    }
+}
+
+impl EditorCommand for YankCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Yank"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor_and_kill_ring(self.0, |editor, kill_ring| {
+            editor.yank(kill_ring);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct YankPopCommand(pub RadioAppState);
+
+impl YankPopCommand {
+    pub fn id() -> &'static str {
+        "yank-pop"
+    }
+}
+
+impl EditorCommand for YankPopCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Yank Pop"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor_and_kill_ring(self.0, |editor, kill_ring| {
+            editor.yank_pop(kill_ring);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct ShowUndoHistoryCommand(pub RadioAppState);
+
+impl ShowUndoHistoryCommand {
+    pub fn id() -> &'static str {
+        "show-undo-history"
+    }
+}
+
+impl EditorCommand for ShowUndoHistoryCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Show Undo History"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        let mut radio_app_state = self.0;
+        let Some(active_tab) = radio_app_state.get_active_tab() else {
+            return;
+        };
+        let mut app_state = radio_app_state.write_channel(Channel::Global);
+        HistoryTab::open_with(&mut app_state, active_tab);
+    }
+}
+
+#[derive(Clone)]
+pub struct NextUndoBranchCommand(pub RadioAppState);
+
+impl NextUndoBranchCommand {
+    pub fn id() -> &'static str {
+        "next-undo-branch"
+    }
+}
+
+impl EditorCommand for NextUndoBranchCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Next Undo Branch"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.switch_undo_branch(true);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct PreviousUndoBranchCommand(pub RadioAppState);
+
+impl PreviousUndoBranchCommand {
+    pub fn id() -> &'static str {
+        "previous-undo-branch"
+    }
+}
+
+impl EditorCommand for PreviousUndoBranchCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Previous Undo Branch"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.switch_undo_branch(false);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct GoBackOneMinuteCommand(pub RadioAppState);
+
+impl GoBackOneMinuteCommand {
+    pub fn id() -> &'static str {
+        "go-back-one-minute"
+    }
+}
+
+impl EditorCommand for GoBackOneMinuteCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Go Back One Minute in History"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.travel_in_time(Duration::from_secs(60), false);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct GoForwardOneMinuteCommand(pub RadioAppState);
+
+impl GoForwardOneMinuteCommand {
+    pub fn id() -> &'static str {
+        "go-forward-one-minute"
+    }
+}
+
+impl EditorCommand for GoForwardOneMinuteCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Go Forward One Minute in History"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.travel_in_time(Duration::from_secs(60), true);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct FindCommand(pub RadioAppState);
+
+impl FindCommand {
+    pub fn id() -> &'static str {
+        "find"
+    }
+}
+
+impl EditorCommand for FindCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Find"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.open_find(false);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct FindAndReplaceCommand(pub RadioAppState);
+
+impl FindAndReplaceCommand {
+    pub fn id() -> &'static str {
+        "find-and-replace"
+    }
+}
+
+impl EditorCommand for FindAndReplaceCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Find And Replace"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.open_find(true);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct FindNextCommand(pub RadioAppState);
+
+impl FindNextCommand {
+    pub fn id() -> &'static str {
+        "find-next"
+    }
+}
+
+impl EditorCommand for FindNextCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Find Next"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.find_next(true);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct FindPreviousCommand(pub RadioAppState);
+
+impl FindPreviousCommand {
+    pub fn id() -> &'static str {
+        "find-previous"
+    }
+}
+
+impl EditorCommand for FindPreviousCommand {
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Find Previous"
+    }
+
+    fn run(&self, _ctx: &mut CommandRunContext) {
+        with_active_editor(self.0, |editor| {
+            editor.find_next(false);
+        });
+    }
+}
+
+/// Parse a `line` or `line:column` position, both starting from 1.
+fn parse_line_position(position: &str) -> Option<(usize, usize)> {
+    let (line, col) = match position.trim().split_once(':') {
+        Some((line, col)) => (line, col.parse::<usize>().ok()?),
+        None => (position.trim(), 1),
+    };
+    let line = line.parse::<usize>().ok()?;
+    Some((line.saturating_sub(1), col.saturating_sub(1)))
+}
+
+#[derive(Clone)]
+pub struct GoToLineCommand(pub RadioAppState);
+
+impl GoToLineCommand {
+    pub fn id() -> &'static str {
+        "go-to-line"
+    }
+}
+
+impl EditorCommand for GoToLineCommand {
+    fn args(&self) -> Vec<CommandArg> {
+        vec![CommandArg::text("Line number, or line:column")]
+    }
+
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Go to Line"
+    }
+
+    fn run(&self, ctx: &mut CommandRunContext) {
+        let Some((line, col)) = ctx.arg(0).and_then(parse_line_position) else {
+            return;
+        };
+        with_active_editor(self.0, |editor| {
+            editor.select_line_range(line, col..col);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct ChangeLanguageModeCommand(pub RadioAppState);
+
+impl ChangeLanguageModeCommand {
+    pub fn id() -> &'static str {
+        "change-language-mode"
+    }
+}
+
+impl EditorCommand for ChangeLanguageModeCommand {
+    fn args(&self) -> Vec<CommandArg> {
+        let languages = LanguageId::all()
+            .into_iter()
+            .map(|language| CommandChoice::new(&language, &language))
+            .collect();
+        vec![CommandArg::choice("Language", languages)]
+    }
+
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Change Language Mode"
+    }
+
+    fn run(&self, ctx: &mut CommandRunContext) {
+        if let Some(language) = ctx.arg(0).and_then(LanguageId::from_name) {
+            with_active_editor(self.0, |editor| editor.set_language(language));
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SetIndentationCommand(pub RadioAppState);
+
+impl SetIndentationCommand {
+    pub fn id() -> &'static str {
+        "set-indentation"
+    }
+}
+
+impl EditorCommand for SetIndentationCommand {
+    fn args(&self) -> Vec<CommandArg> {
+        let indentations = [false, true]
+            .into_iter()
+            .flat_map(|use_tabs| {
+                [2, 4, 8].map(|size| {
+                    let kind = if use_tabs { "tabs" } else { "spaces" };
+                    let indentation = Indentation { size, use_tabs };
+                    CommandChoice::new(format!("{kind}:{size}"), indentation)
+                })
+            })
+            .collect();
+        vec![CommandArg::choice("Indentation", indentations)]
+    }
+
+    fn id(&self) -> &str {
+        Self::id()
+    }
+
+    fn text(&self) -> &str {
+        "Set Indentation"
+    }
+
+    fn run(&self, ctx: &mut CommandRunContext) {
+        let Some(indentation) = ctx.arg(0).and_then(|arg| arg.parse::<Indentation>().ok()) else {
+            return;
+        };
+        with_active_editor(self.0, |editor| editor.set_indentation(indentation));
     }
 }
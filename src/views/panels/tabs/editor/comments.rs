@@ -0,0 +1,299 @@
+use std::collections::{BTreeSet, HashMap};
+
+use freya::hooks::{TextCursor, TextEditor};
+use freya_hooks::TextEvent;
+
+use crate::indentation::leading_whitespace;
+
+use super::{EditorCursor, EditorData};
+
+/// Text replaced in a position, in UTF-16 code units.
+/// The position is relative to the start of a line in the line edits.
+struct TextEdit {
+    pos: usize,
+    removed: usize,
+    inserted: String,
+}
+
+impl TextEdit {
+    /// Where a position ends up after the edit.
+    fn move_pos(&self, pos: usize) -> usize {
+        if pos < self.pos {
+            return pos;
+        }
+        let pos = pos.saturating_sub(self.removed).max(self.pos);
+        pos + self.inserted.encode_utf16().count()
+    }
+}
+
+/// Number of chars of the leading whitespace of a line that fit in the given width.
+fn whitespace_within(line: &str, tab_width: usize, max_width: usize) -> usize {
+    let mut len = 0;
+    let mut width = 0;
+    for ch in line.chars() {
+        width += match ch {
+            ' ' => 1,
+            '\t' => tab_width,
+            _ => break,
+        };
+        if width > max_width {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+
+impl EditorData {
+    /// Comment the lines of all the cursors with the line comment of the language,
+    /// or uncomment them when all of them are commented already.
+    pub fn toggle_line_comment(&mut self) -> bool {
+        let language = self.language_id().config();
+        let Some(token) = language.line_comment.as_deref() else {
+            return false;
+        };
+        let tab_width = self.indentation.tab_width();
+
+        let lines = self
+            .cursors_lines()
+            .into_iter()
+            .filter_map(|line| {
+                let text = self.line(line)?.text;
+                let text = text.trim_end_matches(['\n', '\r']).to_string();
+                (!text.trim().is_empty()).then_some((line, text))
+            })
+            .collect::<Vec<(usize, String)>>();
+        if lines.is_empty() {
+            return false;
+        }
+
+        let uncomment = lines
+            .iter()
+            .all(|(_, text)| text.trim_start().starts_with(token));
+        // The comments are aligned with the least indented line, by how wide the indentation looks
+        let comment_width = lines
+            .iter()
+            .map(|(_, text)| leading_whitespace(text, tab_width).1)
+            .min()
+            .unwrap_or_default();
+
+        let edits = lines
+            .iter()
+            .map(|(line, text)| {
+                let edit = if uncomment {
+                    let (col, _) = leading_whitespace(text, tab_width);
+                    let after_token = &text[col + token.len()..];
+                    TextEdit {
+                        pos: col,
+                        removed: token.encode_utf16().count()
+                            + after_token.starts_with(' ') as usize,
+                        inserted: String::new(),
+                    }
+                } else {
+                    TextEdit {
+                        pos: whitespace_within(text, tab_width, comment_width),
+                        removed: 0,
+                        inserted: format!("{token} "),
+                    }
+                };
+                (*line, edit)
+            })
+            .collect::<HashMap<usize, TextEdit>>();
+
+        self.apply_line_edits(edits);
+        true
+    }
+
+    /// Wrap the selection of every cursor, or its line when there is no selection,
+    /// with the block comment of the language, or unwrap it when it is commented already.
+    pub fn toggle_block_comment(&mut self) -> bool {
+        let language = self.language_id().config();
+        let Some((open, close)) = language.block_comment.clone() else {
+            return false;
+        };
+
+        let first_change = self.history.current_change();
+        let event =
+            self.edit_each_cursor(|editor, _| editor.toggle_block_comment_at_cursor(&open, &close));
+
+        if event.contains(TextEvent::TEXT_CHANGED) {
+            self.group_history(first_change);
+            self.run_parser();
+        }
+        true
+    }
+
+    fn toggle_block_comment_at_cursor(&mut self, open: &str, close: &str) -> TextEvent {
+        let selection = self
+            .get_selection_range()
+            .filter(|(start, end)| start != end);
+        let has_selection = selection.is_some();
+        let (start, end) = selection.unwrap_or_else(|| {
+            let line = self.cursor_row();
+            let line_start = self.char_to_utf16_cu(self.line_to_char(line));
+            (line_start, self.line_position(line, usize::MAX))
+        });
+
+        // The whitespace around the text stays out of the comment
+        let text = self.text_between(start, end);
+        let trimmed_start = text.trim_start();
+        let start = start
+            + text[..text.len() - trimmed_start.len()]
+                .encode_utf16()
+                .count();
+        let text = trimmed_start.trim_end();
+        let end = start + text.encode_utf16().count();
+        if text.is_empty() {
+            return TextEvent::empty();
+        }
+
+        let pos = self.cursor_pos();
+        let (open_len, close_len) = (open.encode_utf16().count(), close.encode_utf16().count());
+
+        let is_commented = text.len() >= open.len() + close.len()
+            && text.starts_with(open)
+            && text.ends_with(close);
+
+        let (open_edit, close_edit) = if is_commented {
+            let inner = &text[open.len()..text.len() - close.len()];
+            let open_space = inner.starts_with(' ') as usize;
+            let close_space = (inner.len() > open_space && inner.ends_with(' ')) as usize;
+            (
+                TextEdit {
+                    pos: start,
+                    removed: open_len + open_space,
+                    inserted: String::new(),
+                },
+                TextEdit {
+                    pos: end - close_len - close_space,
+                    removed: close_len + close_space,
+                    inserted: String::new(),
+                },
+            )
+        } else {
+            (
+                TextEdit {
+                    pos: start,
+                    removed: 0,
+                    inserted: format!("{open} "),
+                },
+                TextEdit {
+                    pos: end,
+                    removed: 0,
+                    inserted: format!(" {close}"),
+                },
+            )
+        };
+
+        // From the end so the start doesn't move
+        for edit in [&close_edit, &open_edit] {
+            if edit.removed > 0 {
+                self.remove(edit.pos..edit.pos + edit.removed);
+            }
+            if !edit.inserted.is_empty() {
+                self.insert(&edit.inserted, edit.pos);
+            }
+        }
+
+        let move_pos = |pos: usize| {
+            let pos = if pos > close_edit.pos {
+                close_edit.move_pos(pos)
+            } else {
+                pos
+            };
+            open_edit.move_pos(pos)
+        };
+        if has_selection {
+            // The comment is part of the selection
+            let new_end = open_edit.move_pos(close_edit.move_pos(end));
+            self.selected = Some((start, new_end));
+            self.cursor = TextCursor::new(new_end);
+        } else {
+            self.cursor = TextCursor::new(move_pos(pos));
+        }
+
+        TextEvent::TEXT_CHANGED
+    }
+
+    /// Lines covered by the cursors and their selections, without counting
+    /// the last line of a selection that ends at its very start.
+    fn cursors_lines(&self) -> BTreeSet<usize> {
+        let mut lines = BTreeSet::new();
+        for cursor in self.all_cursors() {
+            let (start, end) = cursor
+                .selection_range()
+                .filter(|(start, end)| start != end)
+                .unwrap_or((cursor.pos, cursor.pos));
+            let start_line = self.char_to_line(self.utf16_cu_to_char(start));
+            let mut end_line = self.char_to_line(self.utf16_cu_to_char(end));
+            let end_line_start = self.char_to_utf16_cu(self.line_to_char(end_line));
+            if end_line > start_line && end == end_line_start {
+                end_line -= 1;
+            }
+            lines.extend(start_line..=end_line);
+        }
+        lines
+    }
+
+    /// Apply edits to some lines as a single history step, keeping the cursors in place.
+    fn apply_line_edits(&mut self, edits: HashMap<usize, TextEdit>) {
+        let first_change = self.history.current_change();
+
+        // The edits don't add or remove lines, so the cursors are kept as lines and columns
+        let to_point = |editor: &Self, pos: usize| {
+            let line = editor.char_to_line(editor.utf16_cu_to_char(pos));
+            (
+                line,
+                pos - editor.char_to_utf16_cu(editor.line_to_char(line)),
+            )
+        };
+        let cursors = self
+            .all_cursors()
+            .into_iter()
+            .map(|cursor| {
+                (
+                    to_point(self, cursor.pos),
+                    cursor
+                        .selection
+                        .map(|(from, to)| (to_point(self, from), to_point(self, to))),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut lines = edits.keys().copied().collect::<Vec<usize>>();
+        lines.sort_unstable();
+        for line in lines.into_iter().rev() {
+            let edit = &edits[&line];
+            let pos = self.char_to_utf16_cu(self.line_to_char(line)) + edit.pos;
+            if edit.removed > 0 {
+                self.remove(pos..pos + edit.removed);
+            }
+            if !edit.inserted.is_empty() {
+                self.insert(&edit.inserted, pos);
+            }
+        }
+
+        let to_pos = |editor: &Self, (line, col): (usize, usize)| {
+            let col = edits
+                .get(&line)
+                .map(|edit| edit.move_pos(col))
+                .unwrap_or(col);
+            editor.char_to_utf16_cu(editor.line_to_char(line)) + col
+        };
+        let mut cursors = cursors
+            .into_iter()
+            .map(|(pos, selection)| EditorCursor {
+                pos: to_pos(self, pos),
+                selection: selection.map(|(from, to)| (to_pos(self, from), to_pos(self, to))),
+            })
+            .collect::<Vec<EditorCursor>>();
+
+        self.load_cursor(cursors.remove(0));
+        self.extra_cursors = cursors;
+
+        if self.history.current_change() != first_change {
+            self.group_history(first_change);
+            self.run_parser();
+        }
+    }
+}
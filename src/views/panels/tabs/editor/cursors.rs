@@ -0,0 +1,427 @@
+use std::cmp::Reverse;
+
+use freya::events::{Code, Key, Modifiers};
+use freya::hooks::{TextCursor, TextEditor};
+use freya_hooks::TextEvent;
+
+use super::EditorData;
+
+/// A cursor added on top of the primary one, with its own selection.
+/// Positions are in UTF-16 code units, like the primary cursor.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EditorCursor {
+    pub pos: usize,
+    pub selection: Option<(usize, usize)>,
+}
+
+impl EditorCursor {
+    /// Left-to-right selection.
+    pub fn selection_range(&self) -> Option<(usize, usize)> {
+        self.selection
+            .map(|(from, to)| (from.min(to), from.max(to)))
+    }
+
+    /// Where an edit made by this cursor starts.
+    fn start(&self) -> usize {
+        self.selection_range()
+            .map(|(start, _)| start.min(self.pos))
+            .unwrap_or(self.pos)
+    }
+
+    fn shift(&mut self, delta: isize) {
+        let shift = |pos: usize| pos.saturating_add_signed(delta);
+        self.pos = shift(self.pos);
+        self.selection = self.selection.map(|(from, to)| (shift(from), shift(to)));
+    }
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// Shortcuts that work over the whole text, so only the primary cursor is kept.
+pub(super) fn is_whole_text_shortcut(code: &Code, modifiers: &Modifiers) -> bool {
+    modifiers.contains(Modifiers::CONTROL) && matches!(code, Code::KeyZ | Code::KeyY | Code::KeyA)
+}
+
+impl EditorData {
+    pub fn has_extra_cursors(&self) -> bool {
+        !self.extra_cursors.is_empty()
+    }
+
+    /// Go back to a single cursor.
+    pub fn clear_extra_cursors(&mut self) -> bool {
+        let had_extra_cursors = self.has_extra_cursors();
+        self.extra_cursors.clear();
+        self.column_selection = None;
+        had_extra_cursors
+    }
+
+    /// Keep the primary cursor as an extra one, so it stays after the primary cursor moves.
+    pub fn keep_cursor(&mut self) {
+        self.extra_cursors.push(self.current_cursor());
+    }
+
+    /// The primary cursor followed by the extra ones.
+    pub(super) fn all_cursors(&self) -> Vec<EditorCursor> {
+        let mut cursors = Vec::with_capacity(self.extra_cursors.len() + 1);
+        cursors.push(self.current_cursor());
+        cursors.extend_from_slice(&self.extra_cursors);
+        cursors
+    }
+
+    fn current_cursor(&self) -> EditorCursor {
+        EditorCursor {
+            pos: self.cursor_pos(),
+            selection: self.selected,
+        }
+    }
+
+    pub(super) fn load_cursor(&mut self, cursor: EditorCursor) {
+        self.cursor = TextCursor::new(cursor.pos);
+        self.selected = cursor.selection;
+    }
+
+    /// Remove the extra cursors that ended up in the same position as another cursor.
+    pub(crate) fn merge_cursors(&mut self) {
+        let len = self.len_utf16_cu();
+        let mut positions = vec![self.cursor_pos()];
+        self.extra_cursors.retain(|cursor| {
+            if cursor.pos > len || positions.contains(&cursor.pos) {
+                false
+            } else {
+                positions.push(cursor.pos);
+                true
+            }
+        });
+    }
+
+    /// Columns of the extra cursors placed in the given line.
+    pub fn extra_cursors_cols(&self, line: usize) -> Vec<usize> {
+        let len = self.len_utf16_cu();
+        let line_start = self.char_to_utf16_cu(self.line_to_char(line));
+        self.extra_cursors
+            .iter()
+            .filter(|cursor| {
+                cursor.pos <= len && self.char_to_line(self.utf16_cu_to_char(cursor.pos)) == line
+            })
+            .map(|cursor| cursor.pos - line_start)
+            .collect()
+    }
+
+    /// Highlighted ranges of every cursor in the given line.
+    pub fn visible_selections(&self, line: usize) -> Vec<(usize, usize)> {
+        let len = self.len_utf16_cu();
+        self.all_cursors()
+            .iter()
+            .filter_map(|cursor| cursor.selection)
+            .filter(|(from, to)| *from <= len && *to <= len)
+            .filter_map(|selection| self.visible_selection(selection, line))
+            .collect()
+    }
+
+    /// Add a cursor in the line above the topmost cursor, or below the bottommost one.
+    pub fn add_cursor_vertically(&mut self, below: bool) -> bool {
+        let rows = self
+            .all_cursors()
+            .iter()
+            .map(|cursor| self.char_to_line(self.utf16_cu_to_char(cursor.pos)))
+            .collect::<Vec<usize>>();
+
+        let target = if below {
+            rows.iter()
+                .max()
+                .map(|row| row + 1)
+                .filter(|row| *row < self.len_lines())
+        } else {
+            rows.iter().min().and_then(|row| row.checked_sub(1))
+        };
+        let Some(target) = target else {
+            return false;
+        };
+
+        let pos = self.line_position(target, self.cursor_col());
+        self.extra_cursors.push(EditorCursor {
+            pos,
+            selection: None,
+        });
+        self.merge_cursors();
+        true
+    }
+
+    /// Select the next occurrence of the selected text with a new cursor.
+    /// Without a selection, the word under the cursor gets selected instead.
+    pub fn add_next_occurrence(&mut self) -> bool {
+        let Some(text) = self.get_selected_text().filter(|text| !text.is_empty()) else {
+            let selected = self.select_word_at_cursor();
+            if selected {
+                self.request_scroll_to_cursor();
+            }
+            return selected;
+        };
+
+        let cursors = self.all_cursors();
+        let is_selected = |occurrence: &(usize, usize)| {
+            cursors
+                .iter()
+                .any(|cursor| cursor.selection_range() == Some(*occurrence))
+        };
+        let from = self
+            .current_cursor()
+            .selection_range()
+            .unwrap_or_default()
+            .1;
+
+        let occurrences = self.find_occurrences(&text);
+        // Continue from the start once the end is reached
+        let next = occurrences
+            .iter()
+            .filter(|occurrence| !is_selected(occurrence))
+            .find(|(start, _)| *start >= from)
+            .or_else(|| {
+                occurrences
+                    .iter()
+                    .find(|occurrence| !is_selected(occurrence))
+            })
+            .copied();
+        let Some((start, end)) = next else {
+            return false;
+        };
+
+        // The newest cursor becomes the primary one
+        self.keep_cursor();
+        self.load_cursor(EditorCursor {
+            pos: end,
+            selection: Some((start, end)),
+        });
+        self.request_scroll_to_cursor();
+        true
+    }
+
+    /// Add a cursor in every occurrence of the selected text, or of the word under the cursor.
+    pub fn select_all_occurrences(&mut self) -> bool {
+        let has_selection = self
+            .get_selected_text()
+            .is_some_and(|text| !text.is_empty());
+        if !has_selection && !self.select_word_at_cursor() {
+            return false;
+        }
+
+        let Some(text) = self.get_selected_text() else {
+            return false;
+        };
+        let primary = self.current_cursor().selection_range();
+
+        self.extra_cursors = self
+            .find_occurrences(&text)
+            .into_iter()
+            .filter(|occurrence| Some(*occurrence) != primary)
+            .map(|(start, end)| EditorCursor {
+                pos: end,
+                selection: Some((start, end)),
+            })
+            .collect();
+        true
+    }
+
+    fn select_word_at_cursor(&mut self) -> bool {
+        let (start, end) = {
+            let rope = self.rope.borrow();
+            let cursor = rope.utf16_cu_to_char(self.cursor_pos());
+
+            let mut start = cursor;
+            while start > 0 && is_word_char(rope.char(start - 1)) {
+                start -= 1;
+            }
+            let mut end = cursor;
+            while end < rope.len_chars() && is_word_char(rope.char(end)) {
+                end += 1;
+            }
+
+            (rope.char_to_utf16_cu(start), rope.char_to_utf16_cu(end))
+        };
+
+        if start == end {
+            return false;
+        }
+
+        self.set_selection((start, end));
+        self.cursor = TextCursor::new(end);
+        true
+    }
+
+    /// Ranges of every occurrence of the given text.
+    fn find_occurrences(&self, text: &str) -> Vec<(usize, usize)> {
+        let rope = self.rope.borrow();
+        let content = rope.to_string();
+        let text_len = text.chars().count();
+
+        content
+            .match_indices(text)
+            .map(|(byte_idx, _)| {
+                let start = rope.byte_to_char(byte_idx);
+                (
+                    rope.char_to_utf16_cu(start),
+                    rope.char_to_utf16_cu(start + text_len),
+                )
+            })
+            .collect()
+    }
+
+    /// Process a key in every cursor.
+    pub(super) fn process_key_multi(
+        &mut self,
+        key: &Key,
+        code: &Code,
+        modifiers: &Modifiers,
+    ) -> TextEvent {
+        self.merge_cursors();
+
+        let is_pressing_ctrl = modifiers.contains(Modifiers::CONTROL);
+
+        match code {
+            Code::KeyC if is_pressing_ctrl => {
+                self.copy_selections();
+                TextEvent::empty()
+            }
+            Code::KeyX if is_pressing_ctrl => {
+                self.copy_selections();
+                self.edit_each_cursor(|editor, _| editor.remove_selection())
+            }
+            Code::KeyV if is_pressing_ctrl => self.paste_in_each_cursor(),
+            _ => self.edit_each_cursor(|editor, _| editor.process_editor_key(key, code, modifiers)),
+        }
+    }
+
+    /// Run an edit in every cursor, from the last one to the first one,
+    /// so the edits don't move the cursors that are still pending.
+    /// The edit also gets the position of the cursor in the text, starting from the top.
+    pub(super) fn edit_each_cursor(
+        &mut self,
+        mut edit: impl FnMut(&mut Self, usize) -> TextEvent,
+    ) -> TextEvent {
+        let mut cursors = self.all_cursors();
+        let mut order = (0..cursors.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|idx| Reverse(cursors[*idx].start()));
+
+        let mut event = TextEvent::empty();
+
+        for (processed, idx) in order.iter().enumerate() {
+            let cursor = cursors[*idx];
+            let len_before = self.len_utf16_cu();
+            let lines_before = self.len_lines();
+            let edited_line = self.char_to_line(self.utf16_cu_to_char(cursor.start()));
+
+            self.load_cursor(cursor);
+            event |= edit(self, order.len() - 1 - processed);
+            cursors[*idx] = self.current_cursor();
+
+            // The cursors after this one move with the text
+            let delta = self.len_utf16_cu() as isize - len_before as isize;
+            for processed_idx in &order[..processed] {
+                cursors[*processed_idx].shift(delta);
+            }
+            self.folding.shift_lines(
+                edited_line,
+                self.len_lines() as isize - lines_before as isize,
+            );
+        }
+
+        self.load_cursor(cursors[0]);
+        self.extra_cursors = cursors.split_off(1);
+        self.merge_cursors();
+
+        event
+    }
+
+    fn remove_selection(&mut self) -> TextEvent {
+        let Some((start, end)) = self.get_selection_range() else {
+            return TextEvent::empty();
+        };
+
+        self.clear_selection();
+        self.cursor = TextCursor::new(start);
+        if start == end {
+            return TextEvent::SELECTION_CHANGED;
+        }
+
+        self.remove(start..end);
+        TextEvent::TEXT_CHANGED
+    }
+
+    /// Copy the selections of all the cursors, one per line.
+    fn copy_selections(&mut self) {
+        let text = {
+            let rope = self.rope.borrow();
+            let mut cursors = self.all_cursors();
+            cursors.sort_by_key(|cursor| cursor.start());
+
+            cursors
+                .iter()
+                .filter_map(|cursor| cursor.selection_range())
+                .filter(|(start, end)| start != end)
+                .map(|(start, end)| {
+                    rope.slice(rope.utf16_cu_to_char(start)..rope.utf16_cu_to_char(end))
+                        .to_string()
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+
+        if !text.is_empty() {
+            let _ = self.clipboard.set(text);
+        }
+    }
+
+    /// Paste the clipboard in every cursor.
+    /// When there are as many copied lines as cursors, each cursor gets its own line.
+    fn paste_in_each_cursor(&mut self) -> TextEvent {
+        let Ok(text) = self.clipboard.get() else {
+            return TextEvent::empty();
+        };
+        let lines = text.lines().collect::<Vec<&str>>();
+        let cursors_len = self.extra_cursors.len() + 1;
+
+        self.edit_each_cursor(|editor, idx| {
+            let text = if lines.len() == cursors_len {
+                lines[idx]
+            } else {
+                text.as_str()
+            };
+
+            editor.replace_selection(text)
+        })
+    }
+
+    /// Make the changes done since `first_change` be undone and redone together.
+    pub(super) fn group_history(&mut self, first_change: usize) {
+        let last_change = self.history.current_change();
+
+        // The changes that could be redone are gone
+        self.history_groups
+            .retain(|group| group.end <= first_change);
+
+        if last_change > first_change + 1 {
+            self.history_groups.push(first_change..last_change);
+            self.undo_tree.group(first_change..last_change);
+        }
+    }
+
+    pub(super) fn undo_steps(&self) -> usize {
+        let current_change = self.history.current_change();
+        self.history_groups
+            .iter()
+            .find(|group| group.end == current_change)
+            .map(|group| group.len())
+            .unwrap_or(1)
+    }
+
+    pub(super) fn redo_steps(&self) -> usize {
+        let current_change = self.history.current_change();
+        self.history_groups
+            .iter()
+            .find(|group| group.start == current_change)
+            .map(|group| group.len())
+            .unwrap_or(1)
+    }
+}
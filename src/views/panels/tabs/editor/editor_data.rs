@@ -5,15 +5,32 @@ use std::{cmp::Ordering, fmt::Display, ops::Range, path::PathBuf};
 
 use dioxus_clipboard::prelude::UseClipboard;
 use freya::core::event_loop_messages::{EventLoopMessage, TextGroupMeasurement};
-use freya::events::Code;
+use freya::events::{Code, Key, Modifiers};
 use freya::hooks::{EditorHistory, HistoryChange, Line, LinesIterator, TextCursor, TextEditor};
 use freya::prelude::Rope;
 use freya_hooks::{EditableEvent, TextDragging, TextEvent, UsePlatform};
 use lsp_types::Url;
 use skia_safe::textlayout::FontCollection;
+use tracing::info;
 use uuid::Uuid;
 
-use crate::{fs::FSTransport, lsp::LanguageId, metrics::EditorMetrics};
+use crate::{
+    editorconfig::EditorConfig,
+    encoding::{Encoding, LineEnding},
+    fs::FSTransport,
+    history_cache::CachedHistory,
+    indentation::{detect_indentation, leading_whitespace, Indentation},
+    lsp::LanguageId,
+    metrics::EditorMetrics,
+    parser::{find_enclosing_brackets, find_matching_bracket},
+    state::EditorSettings,
+};
+
+use super::cursors::is_whole_text_shortcut;
+use super::{
+    ColumnSelection, EditorCursor, EditorFolding, EmacsState, FindState, FoldingRegion, SoftWrap,
+    UndoTree, VimState,
+};
 
 pub type SharedRope = Rc<RefCell<Rope>>;
 
@@ -68,10 +85,20 @@ pub struct Diagnostics {
     pub content: String,
 }
 
+/// A request for the editor view to scroll a line into the viewport.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ScrollRequest {
+    /// Index of the line among the visible lines, or of the visual row when they are wrapped.
+    pub line: usize,
+    id: usize,
+}
+
 pub struct EditorData {
     pub(crate) editor_type: EditorType,
     pub(crate) cursor: TextCursor,
     pub(crate) history: EditorHistory,
+    /// Every change made, including the ones undone before making others.
+    pub(crate) undo_tree: UndoTree,
     pub(crate) rope: SharedRope,
     pub(crate) selected: Option<(usize, usize)>,
     pub(crate) clipboard: UseClipboard,
@@ -81,6 +108,27 @@ pub struct EditorData {
     pub(crate) dragging: TextDragging,
     pub(crate) diagnostics: Option<Diagnostics>,
     pub(crate) text_id: Uuid,
+    pub(crate) scroll_request: Option<ScrollRequest>,
+    pub(crate) folding: EditorFolding,
+    pub(crate) extra_cursors: Vec<EditorCursor>,
+    pub(crate) column_selection: Option<ColumnSelection>,
+    /// Ranges of history changes that are undone and redone together.
+    pub(crate) history_groups: Vec<Range<usize>>,
+    pub(crate) indentation: Indentation,
+    pub(crate) encoding: Encoding,
+    pub(crate) line_ending: LineEnding,
+    /// State of the Vim emulation, when it's enabled in the settings.
+    pub(crate) vim: Option<VimState>,
+    /// Mark and kills of the Emacs keymap.
+    pub(crate) emacs: EmacsState,
+    /// Visual rows of the lines, when they are wrapped.
+    pub(crate) soft_wrap: Option<SoftWrap>,
+    /// Query and matches of the find bar, when it's shown.
+    pub(crate) find: Option<FindState>,
+    /// Language chosen for the file instead of the one of its extension.
+    pub(crate) language_override: Option<String>,
+    /// Message shown in the status bar, e.g why the file couldn't be saved.
+    pub(crate) message: Option<String>,
 }
 
 impl EditorData {
@@ -97,6 +145,7 @@ impl EditorData {
             cursor: TextCursor::new(pos),
             selected: None,
             history: EditorHistory::new(),
+            undo_tree: UndoTree::default(),
             last_saved_history_change: 0,
             clipboard,
             transport,
@@ -104,6 +153,20 @@ impl EditorData {
             dragging: TextDragging::None,
             text_id: Uuid::new_v4(),
             diagnostics: None,
+            scroll_request: None,
+            folding: EditorFolding::default(),
+            extra_cursors: Vec::new(),
+            column_selection: None,
+            history_groups: Vec::new(),
+            indentation: Indentation::default(),
+            encoding: Encoding::default(),
+            line_ending: LineEnding::default(),
+            vim: None,
+            emacs: EmacsState::default(),
+            soft_wrap: None,
+            find: None,
+            language_override: None,
+            message: None,
         }
     }
 
@@ -117,12 +180,167 @@ impl EditorData {
         self.rope.borrow().to_string()
     }
 
+    /// Content as it gets saved, with its encoding and line endings.
+    pub fn encoded_content(&self) -> Vec<u8> {
+        let content = self.content();
+        self.encoding.encode(&self.line_ending.normalize(&content))
+    }
+
+    /// Replace the content with the file content decoded with another encoding.
+    /// Refused while there are unsaved changes, as they would be lost.
+    pub fn reload(&mut self, content: &str, encoding: Encoding) -> bool {
+        if self.is_edited() {
+            self.message = Some("Save or undo the changes before reopening the file".to_string());
+            return false;
+        }
+
+        let (row, col) = (self.cursor_row(), self.cursor_col());
+
+        self.set(content);
+        self.encoding = encoding;
+        self.line_ending = LineEnding::detect(content);
+        self.history = EditorHistory::new();
+        self.undo_tree = UndoTree::default();
+        self.history_groups.clear();
+        self.last_saved_history_change = 0;
+        self.clear_extra_cursors();
+        self.cursor = TextCursor::new(self.line_position(row.min(self.len_lines() - 1), col));
+        self.clear_selection();
+        self.run_parser();
+        self.message = None;
+        true
+    }
+
+    /// Line break used for new lines.
+    pub(super) fn line_break(&self) -> &'static str {
+        self.line_ending.as_str().unwrap_or("\n")
+    }
+
+    /// Use the given line ending in every line.
+    pub fn change_line_ending(&mut self, line_ending: LineEnding) {
+        let (row, col) = (self.cursor_row(), self.cursor_col());
+        let first_change = self.history.current_change();
+
+        // From the bottom so the positions of the pending lines don't change
+        for line in (0..self.len_lines()).rev() {
+            let Some(text) = self.line(line).map(|line| line.text.to_string()) else {
+                continue;
+            };
+            let line_end =
+                self.char_to_utf16_cu(self.line_to_char(line)) + text.encode_utf16().count();
+
+            match line_ending {
+                LineEnding::Lf if text.ends_with("\r\n") => {
+                    self.remove(line_end - 2..line_end - 1);
+                }
+                LineEnding::Crlf if text.ends_with('\n') && !text.ends_with("\r\n") => {
+                    self.insert("\r", line_end - 1);
+                }
+                _ => {}
+            }
+        }
+
+        self.line_ending = line_ending;
+
+        if self.history.current_change() != first_change {
+            self.group_history(first_change);
+            self.clear_extra_cursors();
+            self.cursor = TextCursor::new(self.line_position(row, col));
+            self.clear_selection();
+        }
+    }
+
     pub fn is_edited(&self) -> bool {
         self.history.current_change() != self.last_saved_history_change
     }
 
-    pub fn mark_as_saved(&mut self) {
-        self.last_saved_history_change = self.history.current_change();
+    /// Mark the content of a history change as the one in the file.
+    pub fn mark_as_saved(&mut self, change: usize) {
+        self.last_saved_history_change = change;
+        self.message = None;
+    }
+
+    /// Record a change in the history, the ones that could be redone stay in the undo tree.
+    fn push_history_change(&mut self, change: HistoryChange) {
+        self.undo_tree
+            .push(self.history.current_change(), change.clone());
+        self.history.push_change(change);
+        self.message = None;
+    }
+
+    /// Keep the history of the file for the next time it's opened.
+    pub fn save_history(&self) {
+        let Some(path) = self.path() else {
+            return;
+        };
+
+        let history = CachedHistory::new(
+            &self.content(),
+            &self.undo_tree.branch_changes(),
+            self.history.current_change(),
+            &self.history_groups,
+        );
+        history.save_in_background(path.clone());
+    }
+
+    /// Bring back the history of the file, if it didn't change since it was kept.
+    pub fn restore_history(&mut self) {
+        let Some(history) = self
+            .path()
+            .and_then(|path| CachedHistory::load(path, &self.content()))
+        else {
+            return;
+        };
+
+        let current_change = history.current_change;
+        let groups = history.groups.clone();
+        let changes = history.changes();
+
+        // The indexes of the changes must fit the content before they are made or undone
+        let mut rope = self.rope.borrow().clone();
+        let mut previous_rope = rope.clone();
+        let fits = changes[..current_change].iter().rev().all(|change| {
+            let fits = revert_fits(&previous_rope, change);
+            if fits {
+                revert_change(&mut previous_rope, change);
+            }
+            fits
+        }) && changes[current_change..].iter().all(|change| {
+            let fits = apply_fits(&rope, change);
+            if fits {
+                apply_change(&mut rope, change);
+            }
+            fits
+        });
+        if !fits {
+            info!(
+                "Discarded the history of {:?} as it doesn't fit",
+                self.path()
+            );
+            return;
+        }
+
+        // Undo the redone changes, so the history can redo them back
+        let mut editor_history = EditorHistory::new();
+        for change in &changes {
+            editor_history.push_change(change.clone());
+        }
+        for _ in current_change..changes.len() {
+            editor_history.undo(&mut rope);
+        }
+
+        if rope != *self.rope.borrow() {
+            info!(
+                "Discarded the history of {:?} as it doesn't apply",
+                self.path()
+            );
+            return;
+        }
+
+        self.history = editor_history;
+        self.undo_tree = UndoTree::from_changes(changes, &groups);
+        self.history_groups = groups;
+        self.last_saved_history_change = current_change;
     }
 
     pub fn path(&self) -> Option<&PathBuf> {
@@ -138,7 +356,104 @@ impl EditorData {
     }
 
     pub fn run_parser(&mut self) {
-        self.metrics.run_parser(&self.rope.borrow());
+        let language = self.language_id().config();
+        self.metrics.run_parser(&self.rope.borrow(), &language);
+        self.update_folding_regions();
+        self.update_wrapped_rows();
+        self.update_find_matches();
+    }
+
+    /// Use the folding regions reported by the Language Server.
+    pub fn set_lsp_folding_regions(&mut self, regions: Vec<FoldingRegion>) {
+        self.folding.set_lsp_regions(regions);
+        self.update_folding_regions();
+    }
+
+    fn update_folding_regions(&mut self) {
+        // The Language Server only knows about the text as it was when opened
+        let lsp_in_sync = self.history.current_change() == 0;
+        self.folding.update_regions(
+            &self.rope.borrow(),
+            &self.metrics.syntax_blocks,
+            lsp_in_sync,
+            self.indentation.tab_width(),
+        );
+    }
+
+    /// Decide the indentation from, in order of preference, the `.editorconfig` files,
+    /// the content of the file, the language settings and the editor settings.
+    pub fn update_indentation(&mut self, settings: &EditorSettings) {
+        let language = self.language_id().to_string();
+        let mut indentation = settings
+            .languages_indentation
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&language))
+            .map(|(_, indentation)| *indentation)
+            .unwrap_or(settings.indentation);
+
+        if settings.detect_indentation {
+            if let Some(detected) = detect_indentation(&self.rope.borrow(), indentation.size) {
+                indentation = detected;
+            }
+        }
+
+        if let Some(path) = self.path() {
+            EditorConfig::for_file(path).apply_to(&mut indentation);
+        }
+
+        self.set_indentation(indentation);
+    }
+
+    /// Use another indentation, the folding regions depend on the width of its tabs.
+    pub fn set_indentation(&mut self, indentation: Indentation) {
+        self.indentation = indentation;
+        self.update_folding_regions();
+    }
+
+    /// Rewrite the indentation of every line with tabs or spaces.
+    pub fn convert_indentation(&mut self, use_tabs: bool) {
+        let (row, col) = (self.cursor_row(), self.cursor_col());
+        let first_change = self.history.current_change();
+        let tab_width = self.indentation.tab_width();
+        let target = Indentation {
+            use_tabs,
+            ..self.indentation
+        };
+
+        // From the bottom so the positions of the pending lines don't change
+        for line in (0..self.len_lines()).rev() {
+            let Some(text) = self.line(line).map(|line| line.text.to_string()) else {
+                continue;
+            };
+            let (len, width) = leading_whitespace(&text, tab_width);
+            let whitespace = target.whitespace(width);
+            if text[..len] == whitespace {
+                continue;
+            }
+
+            let line_start = self.char_to_utf16_cu(self.line_to_char(line));
+            if len > 0 {
+                self.remove(line_start..line_start + len);
+            }
+            if !whitespace.is_empty() {
+                self.insert(&whitespace, line_start);
+            }
+        }
+
+        self.indentation = target;
+
+        if self.history.current_change() != first_change {
+            self.group_history(first_change);
+            self.clear_extra_cursors();
+            self.cursor = TextCursor::new(self.line_position(row, col));
+            self.clear_selection();
+            self.run_parser();
+        }
+    }
+
+    /// Number of lines, including the ones hidden by folds.
+    pub fn lines_len(&self) -> usize {
+        self.metrics.syntax_blocks.len()
     }
 
     pub fn measure_longest_line(&mut self, font_size: f32, font_collection: &FontCollection) {
@@ -150,6 +465,193 @@ impl EditorData {
         &self.editor_type
     }
 
+    /// Language of the file, given by its extension unless another one was chosen.
+    pub fn language_id(&self) -> LanguageId {
+        self.language_override
+            .as_deref()
+            .and_then(LanguageId::from_name)
+            .unwrap_or_else(|| self.editor_type.language_id())
+    }
+
+    /// Highlight the file as another language.
+    pub fn set_language(&mut self, language_id: LanguageId) {
+        self.language_override = Some(language_id.to_string());
+        self.run_parser();
+    }
+
+    /// Ask the editor view to scroll until the cursor is visible.
+    pub fn request_scroll_to_cursor(&mut self) {
+        let row = self.cursor_row();
+        self.folding.reveal_line(row, self.lines_len());
+
+        let id = self
+            .scroll_request
+            .map(|request| request.id + 1)
+            .unwrap_or_default();
+        self.scroll_request = Some(ScrollRequest {
+            line: self.cursor_visual_row(),
+            id,
+        });
+    }
+
+    /// Fold or unfold the region that starts in the given line.
+    pub fn toggle_fold(&mut self, line: usize) -> bool {
+        let toggled = self.folding.toggle(line, self.lines_len());
+        if toggled {
+            self.move_cursor_out_of_folds();
+        }
+        toggled
+    }
+
+    /// Fold or unfold the region under the cursor.
+    pub fn toggle_fold_at_cursor(&mut self) -> bool {
+        let row = self.cursor_row();
+        let start_line = if self.folding.is_folded(row) {
+            Some(row)
+        } else {
+            self.folding
+                .innermost_region(row)
+                .map(|region| region.start_line)
+        };
+
+        start_line.is_some_and(|start_line| self.toggle_fold(start_line))
+    }
+
+    pub fn fold_all(&mut self) {
+        self.folding.fold_all(self.lines_len());
+        self.move_cursor_out_of_folds();
+    }
+
+    pub fn unfold_all(&mut self) {
+        self.folding.unfold_all(self.lines_len());
+    }
+
+    /// Move the cursor to the start of the fold hiding it, if any.
+    fn move_cursor_out_of_folds(&mut self) {
+        if let Some(start_line) = self.folding.outermost_fold_start(self.cursor_row()) {
+            self.move_cursor_to_line(start_line);
+        }
+    }
+
+    /// Move the cursor to another line, keeping its column when possible.
+    fn move_cursor_to_line(&mut self, line: usize) {
+        self.cursor = TextCursor::new(self.line_position(line, self.cursor_col()));
+        self.clear_selection();
+    }
+
+    /// Position of a column in the given line, limited to the length of the line.
+    pub(super) fn line_position(&self, line: usize, col: usize) -> usize {
+        let line_start = self.char_to_utf16_cu(self.line_to_char(line));
+        let line_len = self
+            .line(line)
+            .map(|line| {
+                line.text
+                    .trim_end_matches(['\n', '\r'])
+                    .encode_utf16()
+                    .count()
+            })
+            .unwrap_or_default();
+
+        line_start + col.min(line_len)
+    }
+
+    /// Keep the cursor out of folded lines after it moved from `previous_row`.
+    /// Vertical movements skip the folded lines, anything else unfolds them.
+    pub(super) fn skip_folded_lines(&mut self, previous_row: usize, text_changed: bool) {
+        let row = self.cursor_row();
+        if !self.folding.is_hidden(row) {
+            return;
+        }
+
+        if text_changed || row == previous_row {
+            self.folding.reveal_line(row, self.lines_len());
+            return;
+        }
+
+        let visible_lines = self.folding.visible_lines();
+        let target = if row > previous_row {
+            visible_lines.iter().find(|line| **line > row).copied()
+        } else {
+            None
+        };
+        let target = target.or_else(|| self.folding.outermost_fold_start(row));
+
+        if let Some(target) = target {
+            let selection = self.selected;
+            self.move_cursor_to_line(target);
+            // Keep extending the selection, if any
+            if let Some((from, _)) = selection {
+                self.set_selection((from, self.cursor_pos()));
+            }
+        }
+    }
+
+    /// The bracket next to the cursor and the one matching it, as char indexes.
+    pub fn matching_brackets(&self) -> Option<(usize, usize)> {
+        let rope = self.rope.borrow();
+        let cursor = rope.utf16_cu_to_char(self.cursor_pos());
+
+        // Prefer the bracket after the cursor
+        [Some(cursor), cursor.checked_sub(1)]
+            .into_iter()
+            .flatten()
+            .find_map(|idx| {
+                find_matching_bracket(&rope, &self.metrics.syntax_blocks, idx)
+                    .map(|matching| (idx, matching))
+            })
+    }
+
+    /// Move the cursor to the bracket matching the one next to it.
+    pub fn jump_to_matching_bracket(&mut self) -> bool {
+        let Some((bracket, matching)) = self.matching_brackets() else {
+            return false;
+        };
+        let cursor = self.utf16_cu_to_char(self.cursor_pos());
+
+        // Keep the cursor on the same side of the bracket
+        let new_cursor = if bracket == cursor {
+            matching
+        } else {
+            matching + 1
+        };
+
+        self.cursor = TextCursor::new(self.char_to_utf16_cu(new_cursor));
+        self.clear_selection();
+        self.request_scroll_to_cursor();
+        true
+    }
+
+    /// Select some columns of a line, e.g to show a search result.
+    pub fn select_line_range(&mut self, line: usize, cols: Range<usize>) {
+        let line = line.min(self.len_lines() - 1);
+        let (start, end) = (
+            self.line_position(line, cols.start),
+            self.line_position(line, cols.end),
+        );
+        self.clear_extra_cursors();
+        self.set_selection((start, end));
+        self.cursor = TextCursor::new(end);
+        self.request_scroll_to_cursor();
+    }
+
+    /// Select the content between the closest pair of brackets, brackets included.
+    pub fn select_to_bracket(&mut self) -> bool {
+        let brackets = self.matching_brackets().or_else(|| {
+            let rope = self.rope.borrow();
+            let cursor = rope.utf16_cu_to_char(self.cursor_pos());
+            find_enclosing_brackets(&rope, &self.metrics.syntax_blocks, cursor)
+        });
+        let Some((start, end)) = brackets.map(|(a, b)| (a.min(b), a.max(b) + 1)) else {
+            return false;
+        };
+
+        let (start, end) = (self.char_to_utf16_cu(start), self.char_to_utf16_cu(end));
+        self.set_selection((start, end));
+        self.cursor = TextCursor::new(end);
+        self.request_scroll_to_cursor();
+        true
+    }
+
     pub fn process_event(&mut self, edit_event: &EditableEvent) -> bool {
         let mut processed = false;
         let res = match edit_event {
@@ -157,7 +659,9 @@ impl EditorData {
                 let coords = e.get_element_coordinates();
 
                 self.dragging.set_cursor_coords(coords);
+                self.cancel_mark();
                 self.clear_selection();
+                self.column_selection = None;
                 processed = true;
 
                 Some((*id, Some(coords), None))
@@ -206,11 +710,53 @@ impl EditorData {
                     }
                 }
 
-                let event = self.process_key(&e.key, &e.code, &e.modifiers, true, true, true);
-                if event.contains(TextEvent::TEXT_CHANGED) {
+                // Editing turns the column selection into regular cursors
+                self.column_selection = None;
+
+                // Undo, redo and select all only use the primary cursor
+                let is_whole_text_shortcut = is_whole_text_shortcut(&e.code, &e.modifiers);
+                if is_whole_text_shortcut {
+                    self.clear_extra_cursors();
+                }
+
+                let previous_row = self.cursor_row();
+                let first_change = self.history.current_change();
+                let extends_mark = self.extends_mark(&e.key, &e.modifiers);
+
+                let event = if self.has_extra_cursors() {
+                    // The folds are shifted by every cursor
+                    self.process_key_multi(&e.key, &e.code, &e.modifiers)
+                } else {
+                    let previous_lines_len = self.len_lines();
+                    let edited_line = self
+                        .selected
+                        .map(|(from, to)| self.char_to_line(self.utf16_cu_to_char(from.min(to))))
+                        .unwrap_or(previous_row)
+                        .min(previous_row);
+
+                    let event = self.process_editor_key(&e.key, &e.code, &e.modifiers);
+                    if event.contains(TextEvent::TEXT_CHANGED) {
+                        let delta = self.len_lines() as isize - previous_lines_len as isize;
+                        self.folding.shift_lines(edited_line, delta);
+                    }
+                    event
+                };
+
+                // Moving from the mark keeps selecting from it
+                if extends_mark {
+                    self.select_from_mark();
+                }
+
+                let text_changed = event.contains(TextEvent::TEXT_CHANGED);
+                if text_changed {
+                    // Undoing and redoing moves through the existing groups
+                    if !is_whole_text_shortcut {
+                        self.group_history(first_change);
+                    }
                     self.run_parser();
                     self.dragging = TextDragging::None;
                 }
+                self.skip_folded_lines(previous_row, text_changed);
 
                 if !event.is_empty() {
                     processed = true;
@@ -246,6 +792,150 @@ impl EditorData {
 
         processed
     }
+
+    /// Process a key, following the typing rules of the language
+    /// and inserting tabs instead of spaces when the indentation uses them.
+    pub(super) fn process_editor_key(
+        &mut self,
+        key: &Key,
+        code: &Code,
+        modifiers: &Modifiers,
+    ) -> TextEvent {
+        if let Some(event) = self.process_typing_key(key, modifiers) {
+            return event;
+        }
+
+        let no_selection = self
+            .get_selection_range()
+            .is_none_or(|(start, end)| start == end);
+        let pos = self.cursor_pos();
+
+        let event = match key {
+            Key::Tab if self.indentation.use_tabs && modifiers.is_empty() => {
+                self.replace_selection("\t")
+            }
+            // Line breaks made of two chars are removed at once
+            Key::Backspace if no_selection && pos >= 2 && self.is_crlf_at(pos - 2) => {
+                self.remove(pos - 2..pos);
+                self.cursor = TextCursor::new(pos - 2);
+                self.clear_selection();
+                TextEvent::TEXT_CHANGED
+            }
+            Key::Delete if no_selection && self.is_crlf_at(pos) => {
+                self.remove(pos..pos + 2);
+                self.clear_selection();
+                TextEvent::TEXT_CHANGED
+            }
+            // Wrapped lines are crossed one visual row at a time
+            Key::ArrowUp | Key::ArrowDown if self.soft_wrap.is_some() => {
+                self.process_wrapped_vertical_key(key, modifiers.contains(Modifiers::SHIFT))
+            }
+            _ => self.process_key(key, code, modifiers, true, true, true),
+        };
+
+        // Never leave the cursor between the chars of a `\r\n`
+        let pos = self.cursor_pos();
+        if pos > 0 && self.is_crlf_at(pos - 1) {
+            let pos = if *key == Key::ArrowLeft {
+                pos - 1
+            } else {
+                pos + 1
+            };
+            self.cursor = TextCursor::new(pos);
+            if let Some(selected) = self.selected.as_mut() {
+                selected.1 = pos;
+            }
+        }
+
+        event
+    }
+
+    /// Whether there is a `\r\n` starting at the given position.
+    fn is_crlf_at(&self, pos: usize) -> bool {
+        let rope = self.rope.borrow();
+        if pos + 2 > rope.len_utf16_cu() {
+            return false;
+        }
+        let idx = rope.utf16_cu_to_char(pos);
+        rope.get_char(idx) == Some('\r') && rope.get_char(idx + 1) == Some('\n')
+    }
+
+    /// Replace the selected text, if any, and move the cursor after the new text.
+    pub(super) fn replace_selection(&mut self, text: &str) -> TextEvent {
+        if let Some((start, end)) = self.get_selection_range() {
+            if start != end {
+                self.remove(start..end);
+            }
+            self.cursor = TextCursor::new(start);
+        }
+        self.clear_selection();
+
+        let pos = self.cursor_pos();
+        let len = self.insert(text, pos);
+        self.cursor = TextCursor::new(pos + len);
+        TextEvent::TEXT_CHANGED
+    }
+
+    /// Part of a selection that is visible in the given line.
+    pub(super) fn visible_selection(
+        &self,
+        (selected_from, selected_to): (usize, usize),
+        editor_id: usize,
+    ) -> Option<(usize, usize)> {
+        let selected_from_row = self.char_to_line(self.utf16_cu_to_char(selected_from));
+        let selected_to_row = self.char_to_line(self.utf16_cu_to_char(selected_to));
+
+        let editor_row_idx = self.char_to_utf16_cu(self.line_to_char(editor_id));
+        let selected_from_row_idx = self.char_to_utf16_cu(self.line_to_char(selected_from_row));
+        let selected_to_row_idx = self.char_to_utf16_cu(self.line_to_char(selected_to_row));
+
+        let selected_from_col_idx = selected_from - selected_from_row_idx;
+        let selected_to_col_idx = selected_to - selected_to_row_idx;
+
+        // Between starting line and endling line
+        if (editor_id > selected_from_row && editor_id < selected_to_row)
+            || (editor_id < selected_from_row && editor_id > selected_to_row)
+        {
+            let len = self.line(editor_id).unwrap().utf16_len();
+            return Some((0, len));
+        }
+
+        let highlights = match selected_from_row.cmp(&selected_to_row) {
+            // Selection direction is from bottom -> top
+            Ordering::Greater => {
+                if selected_from_row == editor_id {
+                    // Starting line
+                    Some((0, selected_from_col_idx))
+                } else if selected_to_row == editor_id {
+                    // Ending line
+                    let len = self.line(selected_to_row).unwrap().utf16_len();
+                    Some((selected_to_col_idx, len))
+                } else {
+                    None
+                }
+            }
+            // Selection direction is from top -> bottom
+            Ordering::Less => {
+                if selected_from_row == editor_id {
+                    // Starting line
+                    let len = self.line(selected_from_row).unwrap().utf16_len();
+                    Some((selected_from_col_idx, len))
+                } else if selected_to_row == editor_id {
+                    // Ending line
+                    Some((0, selected_to_col_idx))
+                } else {
+                    None
+                }
+            }
+            Ordering::Equal if selected_from_row == editor_id => {
+                // Starting and endline line are the same
+                Some((selected_from - editor_row_idx, selected_to - editor_row_idx))
+            }
+            _ => None,
+        };
+
+        highlights
+    }
 }
 
 impl Display for EditorData {
@@ -254,6 +944,62 @@ impl Display for EditorData {
     }
 }
 
+/// Whether the indexes of a history change are within the given text.
+fn apply_fits(rope: &Rope, change: &HistoryChange) -> bool {
+    let text_len = rope.len_utf16_cu();
+    match change {
+        HistoryChange::InsertChar { idx, .. } | HistoryChange::InsertText { idx, .. } => {
+            *idx <= text_len
+        }
+        HistoryChange::Remove { idx, len, .. } => {
+            idx.checked_add(*len).is_some_and(|end| end <= text_len)
+        }
+    }
+}
+
+/// Whether the indexes of a history change are within the given text once it's made.
+fn revert_fits(rope: &Rope, change: &HistoryChange) -> bool {
+    let text_len = rope.len_utf16_cu();
+    match change {
+        HistoryChange::InsertChar { idx, len, .. } | HistoryChange::InsertText { idx, len, .. } => {
+            idx.checked_add(*len).is_some_and(|end| end <= text_len)
+        }
+        HistoryChange::Remove { idx, .. } => *idx <= text_len,
+    }
+}
+
+/// Undo a history change in the given text.
+fn revert_change(rope: &mut Rope, change: &HistoryChange) {
+    match change {
+        HistoryChange::InsertChar { idx, len, .. } | HistoryChange::InsertText { idx, len, .. } => {
+            let range = rope.utf16_cu_to_char(*idx)..rope.utf16_cu_to_char(idx + len);
+            rope.remove(range);
+        }
+        HistoryChange::Remove { idx, text, .. } => {
+            let idx = rope.utf16_cu_to_char(*idx);
+            rope.insert(idx, text);
+        }
+    }
+}
+
+/// Make a history change again in the given text.
+pub(super) fn apply_change(rope: &mut Rope, change: &HistoryChange) {
+    match change {
+        HistoryChange::InsertChar { idx, ch, .. } => {
+            let idx = rope.utf16_cu_to_char(*idx);
+            rope.insert_char(idx, *ch);
+        }
+        HistoryChange::InsertText { idx, text, .. } => {
+            let idx = rope.utf16_cu_to_char(*idx);
+            rope.insert(idx, text);
+        }
+        HistoryChange::Remove { idx, len, .. } => {
+            let range = rope.utf16_cu_to_char(*idx)..rope.utf16_cu_to_char(idx + len);
+            rope.remove(range);
+        }
+    }
+}
+
 impl TextEditor for EditorData {
     type LinesIterator<'a>
         = LinesIterator<'a>
@@ -273,7 +1019,7 @@ impl TextEditor for EditorData {
 
         let inserted_text_len = len_after_insert - len_before_insert;
 
-        self.history.push_change(HistoryChange::InsertChar {
+        self.push_history_change(HistoryChange::InsertChar {
             idx,
             ch,
             len: inserted_text_len,
@@ -291,7 +1037,7 @@ impl TextEditor for EditorData {
 
         let inserted_text_len = len_after_insert - len_before_insert;
 
-        self.history.push_change(HistoryChange::InsertText {
+        self.push_history_change(HistoryChange::InsertText {
             idx,
             text: text.to_owned(),
             len: inserted_text_len,
@@ -311,7 +1057,7 @@ impl TextEditor for EditorData {
 
         let removed_text_len = len_before_remove - len_after_remove;
 
-        self.history.push_change(HistoryChange::Remove {
+        self.push_history_change(HistoryChange::Remove {
             idx: range_utf16.end - removed_text_len,
             text,
             len: removed_text_len,
@@ -384,60 +1130,7 @@ impl TextEditor for EditorData {
     }
 
     fn get_visible_selection(&self, editor_id: usize) -> Option<(usize, usize)> {
-        let (selected_from, selected_to) = self.selected?;
-        let selected_from_row = self.char_to_line(self.utf16_cu_to_char(selected_from));
-        let selected_to_row = self.char_to_line(self.utf16_cu_to_char(selected_to));
-
-        let editor_row_idx = self.char_to_utf16_cu(self.line_to_char(editor_id));
-        let selected_from_row_idx = self.char_to_utf16_cu(self.line_to_char(selected_from_row));
-        let selected_to_row_idx = self.char_to_utf16_cu(self.line_to_char(selected_to_row));
-
-        let selected_from_col_idx = selected_from - selected_from_row_idx;
-        let selected_to_col_idx = selected_to - selected_to_row_idx;
-
-        // Between starting line and endling line
-        if (editor_id > selected_from_row && editor_id < selected_to_row)
-            || (editor_id < selected_from_row && editor_id > selected_to_row)
-        {
-            let len = self.line(editor_id).unwrap().utf16_len();
-            return Some((0, len));
-        }
-
-        let highlights = match selected_from_row.cmp(&selected_to_row) {
-            // Selection direction is from bottom -> top
-            Ordering::Greater => {
-                if selected_from_row == editor_id {
-                    // Starting line
-                    Some((0, selected_from_col_idx))
-                } else if selected_to_row == editor_id {
-                    // Ending line
-                    let len = self.line(selected_to_row).unwrap().utf16_len();
-                    Some((selected_to_col_idx, len))
-                } else {
-                    None
-                }
-            }
-            // Selection direction is from top -> bottom
-            Ordering::Less => {
-                if selected_from_row == editor_id {
-                    // Starting line
-                    let len = self.line(selected_from_row).unwrap().utf16_len();
-                    Some((selected_from_col_idx, len))
-                } else if selected_to_row == editor_id {
-                    // Ending line
-                    Some((0, selected_to_col_idx))
-                } else {
-                    None
-                }
-            }
-            Ordering::Equal if selected_from_row == editor_id => {
-                // Starting and endline line are the same
-                Some((selected_from - editor_row_idx, selected_to - editor_row_idx))
-            }
-            _ => None,
-        };
-
-        highlights
+        self.visible_selection(self.selected?, editor_id)
     }
 
     fn set(&mut self, text: &str) {
@@ -493,19 +1186,27 @@ impl TextEditor for EditorData {
     }
 
     fn redo(&mut self) -> Option<usize> {
-        if self.history.can_redo() {
-            self.history.redo(&mut self.rope.borrow_mut())
-        } else {
-            None
+        let mut idx = None;
+        // Changes made with multiple cursors are redone together
+        for _ in 0..self.redo_steps() {
+            if !self.history.can_redo() {
+                break;
+            }
+            idx = self.history.redo(&mut self.rope.borrow_mut()).or(idx);
         }
+        idx
     }
 
     fn undo(&mut self) -> Option<usize> {
-        if self.history.can_undo() {
-            self.history.undo(&mut self.rope.borrow_mut())
-        } else {
-            None
+        let mut idx = None;
+        // Changes made with multiple cursors are undone together
+        for _ in 0..self.undo_steps() {
+            if !self.history.can_undo() {
+                break;
+            }
+            idx = self.history.undo(&mut self.rope.borrow_mut()).or(idx);
         }
+        idx
     }
 
     fn editor_history(&mut self) -> &mut EditorHistory {
@@ -513,6 +1214,6 @@ impl TextEditor for EditorData {
     }
 
     fn get_identation(&self) -> u8 {
-        4
+        self.indentation.size
     }
 }
@@ -1,24 +1,68 @@
+use std::{ops::Range, rc::Rc};
+
 use dioxus_radio::hooks::RadioReducer;
 use dioxus_radio::prelude::use_radio;
 use freya::prelude::*;
-use skia_safe::textlayout::{Paragraph, RectHeightStyle, RectWidthStyle};
+use skia_safe::textlayout::{
+    Paragraph, ParagraphBuilder, ParagraphStyle, RectHeightStyle, RectWidthStyle, TextStyle,
+};
 
 use crate::hooks::{use_computed, UseDebounce};
 use crate::lsp::LspActionData;
-use crate::parser::TextNode;
-use crate::state::{EditorAction, EditorActionData, TabId};
+use crate::parser::{SyntaxType, TextNode};
+use crate::state::{AppState, EditorAction, EditorActionData, RenderWhitespace, TabId};
 use crate::views::panels::tabs::editor::hover_box::HoverBox;
 use crate::views::panels::tabs::editor::AppStateEditorUtils;
 use crate::{hooks::UseEdit, utils::create_paragraph};
 use crate::{lsp::LspAction, state::Channel};
 
-use super::SharedRope;
+use super::{whitespace_pieces, SharedRope};
 
 #[derive(Props, Clone)]
 pub struct BuilderArgs {
     pub(crate) tab_id: TabId,
     pub(crate) font_size: f32,
     pub(crate) line_height: f32,
+    pub(crate) matching_brackets: Option<(usize, usize)>,
+    pub(crate) visible_lines: Rc<Vec<usize>>,
+    /// Width of the text when the lines are wrapped.
+    pub(crate) wrap_width: Option<f32>,
+    /// Width of a char, to place the indentation guides.
+    pub(crate) char_width: f32,
+    /// Guide of the scope with the cursor and the lines it spans.
+    pub(crate) active_indent_guide: Option<(usize, Range<usize>)>,
+}
+
+/// Width of the line numbers and the folding icons.
+pub fn gutter_width(font_size: f32) -> f32 {
+    font_size * 5.0
+}
+
+/// Paragraph of a line, wrapped like in the editor.
+fn line_paragraph(
+    line_str: &str,
+    font_size: f32,
+    line_height: f32,
+    wrap_width: Option<f32>,
+    app_state: &AppState,
+) -> Paragraph {
+    let Some(wrap_width) = wrap_width else {
+        return create_paragraph(line_str, font_size, app_state);
+    };
+
+    // Every visual row takes the height of a line
+    let mut style = ParagraphStyle::default();
+    let mut text_style = TextStyle::default();
+    text_style.set_font_size(font_size);
+    text_style.set_height_override(true);
+    text_style.set_height(line_height / font_size);
+    style.set_text_style(&text_style);
+
+    let mut paragraph_builder = ParagraphBuilder::new(&style, &app_state.font_collection);
+    paragraph_builder.add_text(line_str);
+    let mut paragraph = paragraph_builder.build();
+    paragraph.layout(wrap_width);
+    paragraph
 }
 
 impl PartialEq for BuilderArgs {
@@ -26,6 +70,11 @@ impl PartialEq for BuilderArgs {
         self.tab_id == other.tab_id
             && self.font_size == other.font_size
             && self.line_height == other.line_height
+            && self.matching_brackets == other.matching_brackets
+            && Rc::ptr_eq(&self.visible_lines, &other.visible_lines)
+            && self.wrap_width == other.wrap_width
+            && self.char_width == other.char_width
+            && self.active_indent_guide == other.active_indent_guide
     }
 }
 
@@ -36,6 +85,7 @@ pub struct EditorLineProps {
     editable: UseEdit,
     debouncer: UseDebounce<(CursorPoint, u32, Paragraph)>,
     rope: SharedRope,
+    pressing_alt: Signal<bool>,
 }
 
 #[allow(non_snake_case)]
@@ -46,11 +96,17 @@ pub fn EditorLine(
                 tab_id,
                 font_size,
                 line_height,
+                matching_brackets,
+                wrap_width,
+                char_width,
+                active_indent_guide,
+                ..
             },
         line_index,
         editable,
         mut debouncer,
         rope,
+        pressing_alt,
     }: EditorLineProps,
 ) -> Element {
     let mut radio_app_state = use_radio(Channel::follow_tab(tab_id));
@@ -61,9 +117,106 @@ pub fn EditorLine(
     let longest_width = editor.metrics.longest_width;
     let line = editor.metrics.syntax_blocks.get_line(line_index);
     let highlights = editable.highlights_attr(line_index, editor_tab);
-    let gutter_width = font_size * 5.0;
+    let gutter_width = gutter_width(font_size);
+    let rows = editor.line_rows(line_index);
     let cursor_reference = editable.cursor_attr();
     let is_line_selected = editor.cursor_row() == line_index;
+    let rainbow_brackets = app_state.settings.editor.rainbow_brackets;
+    let render_whitespace = app_state.settings.editor.render_whitespace;
+    let eol_markers = app_state.settings.editor.eol_markers;
+    let fold_state = editor
+        .folding
+        .region(line_index)
+        .map(|region| editor.folding.is_folded(region.start_line));
+
+    // Visual row and horizontal offset of the extra cursors in this line
+    let extra_cursors = {
+        let cols = editor.extra_cursors_cols(line_index);
+        if cols.is_empty() {
+            Vec::new()
+        } else {
+            let line_str = rope.borrow().line(line_index).to_string();
+            let paragraph =
+                line_paragraph(&line_str, font_size, line_height, wrap_width, &app_state);
+            cols.into_iter()
+                .map(|col| {
+                    paragraph
+                        .get_rects_for_range(
+                            0..col,
+                            RectHeightStyle::default(),
+                            RectWidthStyle::default(),
+                        )
+                        .last()
+                        .map(|text_box| {
+                            let row = (text_box.rect.top / line_height).floor();
+                            (row, text_box.rect.right)
+                        })
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<(f32, f32)>>()
+        }
+    };
+
+    // Highlight the whitespace at the end of the line, in every visual row it takes
+    let trailing_whitespace = match editor.trailing_whitespace(line_index) {
+        Some(range) if render_whitespace == RenderWhitespace::All => {
+            let line_str = rope.borrow().line(line_index).to_string();
+            let paragraph =
+                line_paragraph(&line_str, font_size, line_height, wrap_width, &app_state);
+            paragraph
+                .get_rects_for_range(range, RectHeightStyle::default(), RectWidthStyle::default())
+                .into_iter()
+                .map(|text_box| {
+                    let row = (text_box.rect.top / line_height).floor();
+                    (row, text_box.rect.left, text_box.rect.width())
+                })
+                .collect::<Vec<(f32, f32, f32)>>()
+        }
+        _ => Vec::new(),
+    };
+
+    // Highlight the matches of the find bar, in every visual row they take
+    let find_matches = {
+        let matches = editor.find_matches_in_line(line_index);
+        if matches.is_empty() {
+            Vec::new()
+        } else {
+            let line_str = rope.borrow().line(line_index).to_string();
+            let paragraph =
+                line_paragraph(&line_str, font_size, line_height, wrap_width, &app_state);
+            matches
+                .into_iter()
+                .flat_map(|(range, is_current)| {
+                    paragraph
+                        .get_rects_for_range(
+                            range,
+                            RectHeightStyle::default(),
+                            RectWidthStyle::default(),
+                        )
+                        .into_iter()
+                        .map(move |text_box| {
+                            let row = (text_box.rect.top / line_height).floor();
+                            (row, text_box.rect.left, text_box.rect.width(), is_current)
+                        })
+                })
+                .collect::<Vec<(f32, f32, f32, bool)>>()
+        }
+    };
+
+    // Left offset of the indentation guides, and whether it's the guide of the active scope
+    let indent_guides = if app_state.settings.editor.indent_guides {
+        let guide_width = editor.indentation.tab_width() as f32 * char_width;
+        (0..editor.indent_guides(line_index))
+            .map(|guide| {
+                let is_active = active_indent_guide
+                    .as_ref()
+                    .is_some_and(|(active, lines)| *active == guide && lines.contains(&line_index));
+                (guide as f32 * guide_width, is_active)
+            })
+            .collect::<Vec<(f32, bool)>>()
+    } else {
+        Vec::new()
+    };
 
     let hover_diagnostics = use_computed(&editor.diagnostics, {
         to_owned![rope];
@@ -73,7 +226,8 @@ pub fn EditorLine(
                     let rope = rope.borrow();
                     let line_str = rope.line(line_index).to_string();
                     let app_state = radio_app_state.read();
-                    let paragraph = create_paragraph(&line_str, font_size, &app_state);
+                    let paragraph =
+                        line_paragraph(&line_str, font_size, line_height, wrap_width, &app_state);
                     let mut text_boxs = paragraph.get_rects_for_range(
                         diagnostics.range.start.character as usize
                             ..diagnostics.range.end.character as usize,
@@ -95,10 +249,19 @@ pub fn EditorLine(
             data: EditorActionData::MouseDown {
                 data: e.data,
                 line_index,
+                add_cursor: *pressing_alt.peek(),
             },
         });
     };
 
+    let onclick_fold = move |e: MouseEvent| {
+        e.stop_propagation();
+        radio_app_state.apply(EditorAction {
+            tab_id,
+            data: EditorActionData::ToggleFold { line_index },
+        });
+    };
+
     let onmouseleave = move |_| {
         debouncer.cancel();
         let app_state = radio_app_state.read();
@@ -115,11 +278,25 @@ pub fn EditorLine(
         move |e: MouseEvent| {
             let coords = e.get_element_coordinates();
 
+            // Measure the column under the pointer to select columns
+            let is_pressing_alt = *pressing_alt.peek();
+            let column = is_pressing_alt.then(|| {
+                let rope = rope.borrow();
+                let line_str = rope.line(line_index).to_string();
+                let app_state = radio_app_state.read();
+                let paragraph =
+                    line_paragraph(&line_str, font_size, line_height, wrap_width, &app_state);
+                paragraph
+                    .get_glyph_position_at_coordinate((coords.x as i32, coords.y as i32))
+                    .position as usize
+            });
+
             radio_app_state.apply(EditorAction {
                 tab_id,
                 data: EditorActionData::MouseMove {
                     data: e.data,
                     line_index,
+                    column,
                 },
             });
 
@@ -131,7 +308,8 @@ pub fn EditorLine(
             let rope = rope.borrow();
             let line_str = rope.line(line_index).to_string();
 
-            let paragraph = create_paragraph(&line_str, font_size, &app_state);
+            let paragraph =
+                line_paragraph(&line_str, font_size, line_height, wrap_width, &app_state);
 
             if (coords.x as f32) < paragraph.max_intrinsic_width() {
                 debouncer.action((coords, line_index as u32, paragraph));
@@ -158,34 +336,118 @@ pub fn EditorLine(
         "rgb(135, 135, 135)"
     };
 
+    // Show whether the line starts a folded region or a foldable one
+    let fold_icon = fold_state.map(|is_folded| if is_folded { "▸" } else { "▾" });
+
     // Only highlight the active line when there is no text selected
     let line_background = if is_line_selected && !editable.has_any_highlight(editor_tab) {
         "rgb(70, 70, 70)"
+    } else if fold_state == Some(true) {
+        "rgb(45, 45, 45)"
     } else {
         "none"
     };
 
+    // Wrapped lines use the line height in every visual row
+    let paragraph_width = wrap_width.unwrap_or(longest_width);
+    let max_lines = wrap_width.is_none().then_some("1");
+    let text_line_height = wrap_width.map(|_| (line_height / font_size).to_string());
+
     rsx!(
         rect {
-            height: "{line_height}",
+            height: "{line_height * rows as f32}",
             direction: "horizontal",
             background: line_background,
-            cross_align: "center",
             rect {
                 width: "{gutter_width}",
+                height: "{line_height}",
                 direction: "horizontal",
                 main_align: "end",
+                cross_align: "center",
                 label {
-                    margin: "0 20 0 0",
                     font_size: "{font_size}",
                     color: gutter_color,
                     "{line_index + 1} "
                 }
+                rect {
+                    width: "20",
+                    main_align: "center",
+                    cross_align: "center",
+                    if let Some(fold_icon) = fold_icon {
+                        label {
+                            onclick: onclick_fold,
+                            font_size: "{font_size * 0.8}",
+                            color: "rgb(135, 135, 135)",
+                            "{fold_icon}"
+                        }
+                    }
+                }
             }
+            {indent_guides.into_iter().enumerate().map(|(i, (guide_left, is_active))| {
+                let background = if is_active {
+                    "rgb(150, 150, 150)"
+                } else {
+                    "rgb(85, 85, 85)"
+                };
+                rsx!(
+                    rect {
+                        key: "{i}",
+                        position: "absolute",
+                        position_left: "{gutter_width + guide_left}",
+                        width: "1",
+                        height: "{line_height * rows as f32}",
+                        background,
+                    }
+                )
+            })}
+            {trailing_whitespace.into_iter().enumerate().map(|(i, (row, left, width))| {
+                rsx!(
+                    rect {
+                        key: "{i}",
+                        position: "absolute",
+                        position_top: "{row * line_height}",
+                        position_left: "{gutter_width + left}",
+                        width: "{width}",
+                        height: "{line_height}",
+                        background: "rgb(251, 60, 44, 0.3)",
+                    }
+                )
+            })}
+            {find_matches.into_iter().enumerate().map(|(i, (row, left, width, is_current))| {
+                let background = if is_current {
+                    "rgb(255, 197, 61, 0.45)"
+                } else {
+                    "rgb(255, 197, 61, 0.2)"
+                };
+                rsx!(
+                    rect {
+                        key: "{i}",
+                        position: "absolute",
+                        position_top: "{row * line_height}",
+                        position_left: "{gutter_width + left}",
+                        width: "{width}",
+                        height: "{line_height}",
+                        background,
+                    }
+                )
+            })}
+            {extra_cursors.into_iter().enumerate().map(|(i, (cursor_row, cursor_left))| {
+                rsx!(
+                    rect {
+                        key: "{i}",
+                        position: "absolute",
+                        position_top: "{cursor_row * line_height + (line_height - font_size) / 2.0}",
+                        position_left: "{gutter_width + cursor_left}",
+                        width: "2",
+                        height: "{font_size}",
+                        background: "white",
+                    }
+                )
+            })}
             if let Some((text_box, content)) = hover_diagnostics.borrow().value.as_ref() {
                 rect {
                     position: "absolute",
-                    position_top: "{line_height}",
+                    position_top: "{line_height * rows as f32}",
                     position_left: "{gutter_width + text_box.rect.left}",
                     HoverBox {
                         content: "{content}"
@@ -197,12 +459,13 @@ pub fn EditorLine(
                 onmousemove,
                 onmouseleave,
                 min_width: "fill",
-                width: "{longest_width}",
+                width: "{paragraph_width}",
                 height: "fill",
                 main_align: "center",
                 cursor_index,
                 cursor_color: "white",
-                max_lines: "1",
+                max_lines,
+                line_height: text_line_height,
                 cursor_reference,
                 cursor_mode: "editable",
                 cursor_id: "{line_index}",
@@ -211,22 +474,53 @@ pub fn EditorLine(
                 highlight_mode: "expanded",
                 font_size: "{font_size}",
                 font_family: "Jetbrains Mono",
-                {line.iter().enumerate().map(|(i, (syntax_type, text))| {
+                {line.iter().enumerate().map(|(i, (syntax_type, text_node))| {
                     let rope = rope.borrow();
-                    let text: Cow<str> = match text {
+                    let show_spaces = render_whitespace == RenderWhitespace::All;
+                    let text: Cow<str> = match text_node {
                         TextNode::Range(word_pos) => {
                             rope.slice(word_pos.clone()).into()
                         },
-                        TextNode::LineOfChars { len, char } => {
+                        TextNode::LineOfChars { len, char }
+                            if render_whitespace == RenderWhitespace::Leading =>
+                        {
                             Cow::Owned(char.to_string().repeat(*len))
                         }
+                        // The indentation as it is, to be shown with the other whitespace
+                        TextNode::LineOfChars { len, .. } => {
+                            let line_start = rope.line_to_char(line_index);
+                            rope.slice(line_start..line_start + len).into()
+                        }
+                    };
+                    let pieces = whitespace_pieces(&text, show_spaces, eol_markers);
+
+                    let color = if rainbow_brackets {
+                        syntax_type.rainbow_color()
+                    } else {
+                        syntax_type.color()
+                    };
+
+                    // Emphasize the bracket next to the cursor and its pair
+                    let is_matching_bracket = match (syntax_type, text_node) {
+                        (SyntaxType::Bracket { .. }, TextNode::Range(word_pos)) => matching_brackets
+                            .is_some_and(|(a, b)| word_pos.start == a || word_pos.start == b),
+                        _ => false,
+                    };
+                    let (font_weight, decoration) = if is_matching_bracket {
+                        ("bold", "underline")
+                    } else {
+                        ("normal", "none")
                     };
 
                     rsx!(
-                        text {
-                            key: "{i}",
-                            color: syntax_type.color(),
-                            {text}
+                        for (j, (piece, is_mark)) in pieces.into_iter().enumerate() {
+                            text {
+                                key: "{i}-{j}",
+                                color: if is_mark { SyntaxType::SpaceMark.color() } else { color },
+                                font_weight,
+                                decoration,
+                                "{piece}"
+                            }
                         }
                     )
                 })}
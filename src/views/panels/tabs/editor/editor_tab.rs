@@ -1,27 +1,42 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{ops::Range, path::PathBuf, sync::Arc};
 
 use crate::{
+    encoding::{Encoding, LineEnding},
     fs::FSReadTransportInterface,
     lsp::{LSPClient, LspAction, LspActionData, LspConfig},
     state::{
-        AppSettings, AppState, Channel, EditorCommands, KeyboardShortcuts, PanelTab, PanelTabData,
-        RadioAppState, TabId, TabProps,
+        AppSettings, AppState, Channel, EditorCommands, KeybindingContext, Keybindings, Keymap,
+        PanelTab, PanelTabData, RadioAppState, TabId, TabProps,
     },
-    views::panels::tabs::editor::TabEditorUtils,
+    views::panels::tabs::editor::{AppStateEditorUtils, TabEditorUtils},
     Args,
 };
 
-use freya::prelude::keyboard::Modifiers;
 use freya::prelude::*;
 
 use skia_safe::textlayout::FontCollection;
 use tracing::info;
 
 use super::{
-    commands::{DecreaseFontSizeCommand, IncreaseFontSizeCommand, SaveFileCommand},
+    commands::{
+        AddCursorAboveCommand, AddCursorBelowCommand, AddNextOccurrenceCommand, CancelMarkCommand,
+        ChangeLineEndingCommand, ConvertIndentationToSpacesCommand,
+        ConvertIndentationToTabsCommand, CopyRegionCommand, DecreaseFontSizeCommand,
+        DeleteLinesCommand, DuplicateSelectionCommand, FindAndReplaceCommand, FindCommand,
+        FindNextCommand, FindPreviousCommand, FoldAllCommand, GoBackOneMinuteCommand,
+        GoForwardOneMinuteCommand, IncreaseFontSizeCommand, InsertLineAboveCommand,
+        InsertLineBelowCommand, JoinLinesCommand, JumpToMatchingBracketCommand, KillLineCommand,
+        MoveLinesDownCommand, MoveLinesUpCommand, MoveToLineEndCommand, MoveToLineStartCommand,
+        MoveWordBackwardCommand, MoveWordForwardCommand, NextUndoBranchCommand,
+        PreviousUndoBranchCommand, ReopenWithEncodingCommand, ReverseLinesCommand,
+        SaveAndCloseFileCommand, SaveFileCommand, SelectAllOccurrencesCommand,
+        SelectToBracketCommand, SetMarkCommand, ShowUndoHistoryCommand, SortLinesCommand,
+        ToggleBlockCommentCommand, ToggleFoldCommand, ToggleLineCommentCommand,
+        TrimTrailingWhitespaceCommand, UnfoldAllCommand, YankCommand, YankPopCommand,
+    },
     editor_data::{EditorData, EditorType},
     editor_ui::EditorUi,
-    SharedRope,
+    SharedRope, SortOrder,
 };
 
 /// A tab with an embedded Editor.
@@ -33,6 +48,11 @@ pub struct EditorTab {
 
 impl PanelTab for EditorTab {
     fn on_close(&mut self, app_state: &mut AppState) {
+        // Unsaved changes are discarded, so the history kept when saving still applies
+        if app_state.settings.editor.persistent_history && !self.editor.is_edited() {
+            self.editor.save_history();
+        }
+
         // Notify the language server that a document was closed
         let language_id = self.editor.editor_type.language_id();
         let language_server_id = language_id.language_server();
@@ -59,6 +79,10 @@ impl PanelTab for EditorTab {
         app_settings: &AppSettings,
         font_collection: &FontCollection,
     ) {
+        // The language definitions might have changed
+        self.editor.run_parser();
+        self.editor.update_indentation(&app_settings.editor);
+        self.editor.set_vim_mode(app_settings.editor.vim_mode);
         self.editor
             .measure_longest_line(app_settings.editor.font_size, font_collection);
     }
@@ -101,16 +125,30 @@ impl EditorTab {
 
     /// Open an EditorTab in the focused panel.
     pub fn open_with(
+        radio: RadioAppState,
+        app_state: &mut AppState,
+        path: PathBuf,
+        root_path: PathBuf,
+        read_transport: Box<dyn FSReadTransportInterface + 'static>,
+    ) {
+        Self::open_at(radio, app_state, path, root_path, read_transport, None);
+    }
+
+    /// Open an EditorTab in the focused panel, selecting some columns of a line once it's loaded.
+    pub fn open_at(
         mut radio: RadioAppState,
         app_state: &mut AppState,
         path: PathBuf,
         root_path: PathBuf,
         read_transport: Box<dyn FSReadTransportInterface + 'static>,
+        selection: Option<(usize, Range<usize>)>,
     ) {
+        app_state.push_recent_file(path.clone());
+
         let rope = SharedRope::default();
         let tab_id = TabId::new();
 
-        let data = EditorData::new(
+        let mut data = EditorData::new(
             EditorType::FS {
                 path: path.clone(),
                 root_path: root_path.clone(),
@@ -121,10 +159,17 @@ impl EditorTab {
             app_state.default_transport.clone(),
         );
 
+        data.set_vim_mode(app_state.settings.editor.vim_mode);
+
         let tab = Self::new(tab_id, data);
 
         // Dont create the same tab twice
         if !app_state.push_tab(tab, app_state.focused_panel) {
+            let open_tab = app_state.panel(app_state.focused_panel).active_tab();
+            let editor_tab = open_tab.and_then(|tab_id| app_state.try_editor_tab_mut(tab_id));
+            if let Some((editor_tab, (line, cols))) = editor_tab.zip(selection) {
+                editor_tab.editor.select_line_range(line, cols);
+            }
             return;
         }
 
@@ -132,21 +177,41 @@ impl EditorTab {
         spawn_forever({
             to_owned![path];
             async move {
-                let content = read_transport.read_to_string(&path).await;
+                let content = read_transport.read(&path).await;
                 if let Ok(content) = content {
+                    let encoding = Encoding::detect(&content);
+                    let content = encoding.decode(&content);
                     rope.borrow_mut().insert(0, &content);
 
                     let mut app_state = radio.write_channel(Channel::follow_tab(tab_id));
                     let font_size = app_state.font_size();
                     let font_collection = app_state.font_collection.clone();
+                    let editor_settings = app_state.settings.editor.clone();
 
                     let tab = app_state.tab_mut(&tab_id);
                     let editor_tab = tab.as_text_editor_mut().unwrap();
+                    editor_tab.editor.encoding = encoding;
+                    editor_tab.editor.line_ending = LineEnding::detect(&content);
+                    if editor_settings.persistent_history {
+                        editor_tab.editor.restore_history();
+                    }
                     editor_tab.editor.run_parser();
+                    editor_tab.editor.update_indentation(&editor_settings);
+                    if let Some((line, cols)) = selection {
+                        editor_tab.editor.select_line_range(line, cols);
+                    }
                     editor_tab
                         .editor
                         .measure_longest_line(font_size, &font_collection);
 
+                    // Ask for the folding regions now that the content is there
+                    if let Some(lsp) = app_state.editor_tab_lsp(tab_id) {
+                        lsp.send(LspAction {
+                            tab_id,
+                            action: LspActionData::FoldingRange,
+                        });
+                    }
+
                     info!("Loaded file content for {path:?}");
                 }
             }
@@ -186,7 +251,7 @@ impl EditorTab {
 
     /// Initialize the EditorTab module.
     pub fn init(
-        keyboard_shorcuts: &mut KeyboardShortcuts,
+        keybindings: &mut Keybindings,
         commands: &mut EditorCommands,
         radio_app_state: RadioAppState,
     ) {
@@ -194,32 +259,102 @@ impl EditorTab {
         commands.register(IncreaseFontSizeCommand(radio_app_state));
         commands.register(DecreaseFontSizeCommand(radio_app_state));
         commands.register(SaveFileCommand(radio_app_state));
+        commands.register(SaveAndCloseFileCommand(radio_app_state));
+        commands.register(JumpToMatchingBracketCommand(radio_app_state));
+        commands.register(SelectToBracketCommand(radio_app_state));
+        commands.register(ToggleFoldCommand(radio_app_state));
+        commands.register(FoldAllCommand(radio_app_state));
+        commands.register(UnfoldAllCommand(radio_app_state));
+        commands.register(AddCursorAboveCommand(radio_app_state));
+        commands.register(AddCursorBelowCommand(radio_app_state));
+        commands.register(AddNextOccurrenceCommand(radio_app_state));
+        commands.register(SelectAllOccurrencesCommand(radio_app_state));
+        commands.register(ConvertIndentationToSpacesCommand(radio_app_state));
+        commands.register(ConvertIndentationToTabsCommand(radio_app_state));
+        commands.register(ToggleLineCommentCommand(radio_app_state));
+        commands.register(ToggleBlockCommentCommand(radio_app_state));
+        commands.register(MoveLinesUpCommand(radio_app_state));
+        commands.register(MoveLinesDownCommand(radio_app_state));
+        commands.register(DuplicateSelectionCommand(radio_app_state));
+        commands.register(DeleteLinesCommand(radio_app_state));
+        commands.register(JoinLinesCommand(radio_app_state));
+        commands.register(InsertLineAboveCommand(radio_app_state));
+        commands.register(InsertLineBelowCommand(radio_app_state));
+        for order in SortOrder::ALL {
+            commands.register(SortLinesCommand::new(radio_app_state, order));
+        }
+        commands.register(ReverseLinesCommand(radio_app_state));
+        commands.register(TrimTrailingWhitespaceCommand(radio_app_state));
+        for encoding in Encoding::ALL {
+            commands.register(ReopenWithEncodingCommand::new(radio_app_state, encoding));
+        }
+        for line_ending in [LineEnding::Lf, LineEnding::Crlf] {
+            commands.register(ChangeLineEndingCommand::new(radio_app_state, line_ending));
+        }
+        commands.register(MoveToLineStartCommand(radio_app_state));
+        commands.register(MoveToLineEndCommand(radio_app_state));
+        commands.register(MoveWordForwardCommand(radio_app_state));
+        commands.register(MoveWordBackwardCommand(radio_app_state));
+        commands.register(SetMarkCommand(radio_app_state));
+        commands.register(CancelMarkCommand(radio_app_state));
+        commands.register(KillLineCommand(radio_app_state));
+        commands.register(CopyRegionCommand(radio_app_state));
+        commands.register(YankCommand(radio_app_state));
+        commands.register(YankPopCommand(radio_app_state));
+        commands.register(ShowUndoHistoryCommand(radio_app_state));
+        commands.register(NextUndoBranchCommand(radio_app_state));
+        commands.register(PreviousUndoBranchCommand(radio_app_state));
+        commands.register(GoBackOneMinuteCommand(radio_app_state));
+        commands.register(GoForwardOneMinuteCommand(radio_app_state));
+        commands.register(FindCommand(radio_app_state));
+        commands.register(FindAndReplaceCommand(radio_app_state));
+        commands.register(FindNextCommand(radio_app_state));
+        commands.register(FindPreviousCommand(radio_app_state));
+        commands.register(GoToLineCommand(radio_app_state));
+        commands.register(ChangeLanguageModeCommand(radio_app_state));
+        commands.register(SetIndentationCommand(radio_app_state));
 
-        // Register Shortcuts
-        keyboard_shorcuts.register(
-            |data: &KeyboardData,
-             commands: &mut EditorCommands,
-             _radio_app_state: RadioAppState| {
-                let is_pressing_alt = data.modifiers == Modifiers::ALT;
-                let is_pressing_ctrl = data.modifiers == Modifiers::CONTROL;
-                match data.code {
-                    // Pressing `Alt ,`
-                    Code::Period if is_pressing_alt => {
-                        commands.trigger(IncreaseFontSizeCommand::id());
-                    }
-                    // Pressing `Alt .`
-                    Code::Comma if is_pressing_alt => {
-                        commands.trigger(DecreaseFontSizeCommand::id());
-                    }
-                    // Pressing `Ctrl S`
-                    Code::KeyS if is_pressing_ctrl => {
-                        commands.trigger(SaveFileCommand::id());
-                    }
-                    _ => return false,
-                }
+        // Register the default Keybindings
+        let (any, editor) = (KeybindingContext::Any, KeybindingContext::Editor);
+        keybindings.register("Alt+.", IncreaseFontSizeCommand::id(), any);
+        keybindings.register("Alt+,", DecreaseFontSizeCommand::id(), any);
+        keybindings.register("Ctrl+S", SaveFileCommand::id(), any);
+        keybindings.register("Ctrl+Shift+\\", JumpToMatchingBracketCommand::id(), editor);
+        keybindings.register("Ctrl+Alt+\\", SelectToBracketCommand::id(), editor);
+        keybindings.register("Ctrl+Shift+[", ToggleFoldCommand::id(), editor);
+        keybindings.register("Ctrl+Alt+[", FoldAllCommand::id(), editor);
+        keybindings.register("Ctrl+Alt+]", UnfoldAllCommand::id(), editor);
+        keybindings.register("Ctrl+Alt+ArrowUp", AddCursorAboveCommand::id(), editor);
+        keybindings.register("Ctrl+Alt+ArrowDown", AddCursorBelowCommand::id(), editor);
+        keybindings.register("Ctrl+D", AddNextOccurrenceCommand::id(), editor);
+        keybindings.register("Ctrl+Shift+L", SelectAllOccurrencesCommand::id(), editor);
+        keybindings.register("Ctrl+/", ToggleLineCommentCommand::id(), editor);
+        keybindings.register("Ctrl+Shift+/", ToggleBlockCommentCommand::id(), editor);
+        keybindings.register("Ctrl+Shift+ArrowUp", MoveLinesUpCommand::id(), editor);
+        keybindings.register("Ctrl+Shift+ArrowDown", MoveLinesDownCommand::id(), editor);
+        keybindings.register("Ctrl+Shift+D", DuplicateSelectionCommand::id(), editor);
+        keybindings.register("Ctrl+Shift+K", DeleteLinesCommand::id(), editor);
+        keybindings.register("Ctrl+J", JoinLinesCommand::id(), editor);
+        keybindings.register("Ctrl+Shift+Enter", InsertLineAboveCommand::id(), editor);
+        keybindings.register("Ctrl+Enter", InsertLineBelowCommand::id(), editor);
+        keybindings.register("Ctrl+F", FindCommand::id(), editor);
+        keybindings.register("Ctrl+H", FindAndReplaceCommand::id(), editor);
+        keybindings.register("F3", FindNextCommand::id(), editor);
+        keybindings.register("Shift+F3", FindPreviousCommand::id(), editor);
+        // `Ctrl G` cancels the mark with the Emacs keymap
+        keybindings.register_keymap(Keymap::Default, "Ctrl+G", GoToLineCommand::id(), editor);
 
-                true
-            },
-        )
+        // Register the Emacs Keybindings, only used with `keymap = "emacs"`
+        let emacs = Keymap::Emacs;
+        keybindings.register_keymap(emacs, "Ctrl+A", MoveToLineStartCommand::id(), editor);
+        keybindings.register_keymap(emacs, "Ctrl+E", MoveToLineEndCommand::id(), editor);
+        keybindings.register_keymap(emacs, "Alt+F", MoveWordForwardCommand::id(), editor);
+        keybindings.register_keymap(emacs, "Alt+B", MoveWordBackwardCommand::id(), editor);
+        keybindings.register_keymap(emacs, "Ctrl+Space", SetMarkCommand::id(), editor);
+        keybindings.register_keymap(emacs, "Ctrl+G", CancelMarkCommand::id(), editor);
+        keybindings.register_keymap(emacs, "Ctrl+K", KillLineCommand::id(), editor);
+        keybindings.register_keymap(emacs, "Alt+W", CopyRegionCommand::id(), editor);
+        keybindings.register_keymap(emacs, "Ctrl+Y", YankCommand::id(), editor);
+        keybindings.register_keymap(emacs, "Alt+Y", YankPopCommand::id(), editor);
     }
 }
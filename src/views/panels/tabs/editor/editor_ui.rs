@@ -1,12 +1,15 @@
-use std::{ffi::OsStr, path::PathBuf, time::Duration};
+use std::{ffi::OsStr, path::PathBuf, rc::Rc, time::Duration};
 
 use crate::hooks::*;
 use crate::lsp::{LspAction, LspActionData};
-use crate::state::{EditorAction, EditorActionData, TabProps};
+use crate::state::{EditorAction, EditorActionData, EditorCommands, Keybindings, TabProps};
+use crate::views::panels::tabs::editor::gutter_width;
 use crate::views::panels::tabs::editor::AppStateEditorUtils;
 use crate::views::panels::tabs::editor::BuilderArgs;
 use crate::views::panels::tabs::editor::EditorLine;
-use crate::{components::*, state::Channel};
+use crate::views::panels::tabs::editor::FindBar;
+use crate::views::panels::tabs::editor::ScrollRequest;
+use crate::{components::*, state::Channel, utils::create_paragraph};
 
 use dioxus_radio::hooks::RadioReducer;
 use dioxus_radio::prelude::use_radio;
@@ -16,6 +19,9 @@ use lsp_types::Position;
 
 use skia_safe::textlayout::Paragraph;
 
+/// Room left for the vertical scrollbar when wrapping at the width of the viewport.
+const SCROLLBAR_SIZE: f32 = 15.0;
+
 #[allow(non_snake_case)]
 pub fn EditorUi(TabProps { tab_id }: TabProps) -> Element {
     // Subscribe to the changes of this Tab.
@@ -29,12 +35,28 @@ pub fn EditorUi(TabProps { tab_id }: TabProps) -> Element {
 
     let mut focus = use_focus_for_id(editor_tab.focus_id);
 
+    let editor_commands = use_context::<Signal<EditorCommands>>();
+    let keybindings = use_context::<Signal<Keybindings>>();
+
     // Initialize the editable text
     let editable = use_edit(radio_app_state, tab_id, editor_tab.editor.text_id);
 
     // The scroll positions of the editor
     let mut scroll_offsets = use_signal(|| (0, 0));
 
+    // The size of the editor viewport
+    let (viewport_reference, viewport_size) = use_node();
+
+    // Give the focus back to the text once the find bar is closed
+    let is_find_open = editor.find.is_some();
+    let mut was_find_open = use_signal(|| is_find_open);
+    use_effect(use_reactive(&is_find_open, move |is_find_open| {
+        if *was_find_open.peek() && !is_find_open {
+            focus.request_focus();
+        }
+        was_find_open.set(is_find_open);
+    }));
+
     let mut pressing_shift = use_signal(|| false);
     let mut pressing_alt = use_signal(|| false);
 
@@ -60,7 +82,96 @@ pub fn EditorUi(TabProps { tab_id }: TabProps) -> Element {
     let font_size = app_state.font_size();
 
     let line_height = (font_size * line_height).floor();
-    let lines_len = editor.metrics.syntax_blocks.len();
+    let visible_lines = editor.folding.visible_lines();
+    let lines_len = visible_lines.len();
+    let matching_brackets = editor.matching_brackets();
+
+    // Wrap the lines at the configured column, or at the width of the viewport
+    let char_width = create_paragraph("0", font_size, &app_state).max_intrinsic_width();
+    let viewport_width = viewport_size.area.width();
+    let wrap_columns = match (
+        app_state.settings.editor.soft_wrap,
+        app_state.settings.editor.wrap_column,
+    ) {
+        (false, _) => None,
+        (true, Some(column)) => Some(column.max(1)),
+        // Until the viewport is measured
+        (true, None) if viewport_width <= 0.0 => None,
+        (true, None) => {
+            let text_width = viewport_width - gutter_width(font_size) - SCROLLBAR_SIZE;
+            Some((text_width / char_width).floor().max(1.0) as usize)
+        }
+    };
+    use_effect(use_reactive(&wrap_columns, move |wrap_columns| {
+        let applied_columns = radio_app_state
+            .read()
+            .editor_tab(tab_id)
+            .editor
+            .soft_wrap
+            .as_ref()
+            .map(|soft_wrap| soft_wrap.columns);
+        if applied_columns != wrap_columns {
+            radio_app_state
+                .write_channel(Channel::follow_tab(tab_id))
+                .editor_tab_mut(tab_id)
+                .editor
+                .set_wrap_columns(wrap_columns);
+        }
+    }));
+
+    // Visual row where every visible line starts, when they are wrapped
+    let row_offsets = editor.soft_wrap.as_ref().map(|_| {
+        let mut rows = 0;
+        let mut row_offsets = vec![0];
+        for line in visible_lines.iter() {
+            rows += editor.line_rows(*line);
+            row_offsets.push(rows);
+        }
+        Rc::new(row_offsets)
+    });
+    let rows_len = row_offsets
+        .as_ref()
+        .map(|row_offsets| row_offsets[lines_len])
+        .unwrap_or(lines_len);
+    // Room for one more char avoids wrapping earlier due to rounding
+    let wrap_width = editor
+        .soft_wrap
+        .as_ref()
+        .map(|soft_wrap| (soft_wrap.columns as f32 + 0.5) * char_width);
+    let active_indent_guide = app_state
+        .settings
+        .editor
+        .indent_guides
+        .then(|| editor.active_indent_guide())
+        .flatten();
+
+    // Scroll the requested line into the viewport
+    let scroll_request = editor.scroll_request;
+    let viewport_height = viewport_size.area.height();
+    let mut applied_scroll_request = use_signal::<Option<ScrollRequest>>(|| None);
+    use_effect(use_reactive(
+        (&scroll_request, &line_height, &viewport_height),
+        move |(scroll_request, line_height, viewport_height)| {
+            if *applied_scroll_request.peek() == scroll_request {
+                return;
+            }
+            applied_scroll_request.set(scroll_request);
+
+            let Some(ScrollRequest { line, .. }) = scroll_request else {
+                return;
+            };
+
+            let scroll_top = -scroll_offsets.peek().1 as f32;
+            let line_top = line as f32 * line_height;
+            let line_bottom = line_top + line_height;
+
+            if line_top < scroll_top {
+                scroll_offsets.write().1 = -line_top as i32;
+            } else if line_bottom > scroll_top + viewport_height {
+                scroll_offsets.write().1 = -(line_bottom - viewport_height) as i32;
+            }
+        },
+    ));
 
     let onscroll = move |(axis, scroll): (Axis, i32)| match axis {
         Axis::X => {
@@ -85,6 +196,11 @@ pub fn EditorUi(TabProps { tab_id }: TabProps) -> Element {
     };
 
     let onkeyup = move |e: KeyboardEvent| {
+        // The keys are for the find bar while it's focused
+        if is_find_open && !focus.is_focused() {
+            return;
+        }
+
         match &e.key {
             Key::Shift => {
                 pressing_shift.set(false);
@@ -102,6 +218,10 @@ pub fn EditorUi(TabProps { tab_id }: TabProps) -> Element {
     };
 
     let onkeydown = move |e: KeyboardEvent| {
+        if is_find_open && !focus.is_focused() {
+            return;
+        }
+
         focus.prevent_navigation();
         e.stop_propagation();
 
@@ -115,15 +235,50 @@ pub fn EditorUi(TabProps { tab_id }: TabProps) -> Element {
             _ => {}
         };
 
+        let is_keybinding = keybindings
+            .read()
+            .is_bound(&e.data, &radio_app_state.read());
         radio_app_state.apply(EditorAction {
             tab_id,
             data: EditorActionData::KeyDown {
                 data: e.data,
                 scroll_offsets,
                 line_height,
-                lines_len,
+                lines_len: rows_len,
+                is_keybinding,
             },
         });
+
+        // Run the commands requested by Vim, e.g saving with `:w`
+        let has_vim_commands = radio_app_state
+            .read()
+            .editor_tab(tab_id)
+            .editor
+            .has_vim_commands();
+        if has_vim_commands {
+            let commands = radio_app_state
+                .write_channel(Channel::follow_tab(tab_id))
+                .editor_tab_mut(tab_id)
+                .editor
+                .take_vim_commands();
+            for command in commands {
+                // The rest of the line is the argument, e.g `:change-language-mode Plain Text`
+                let command = command.trim();
+                let (command_id, rest) = command
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((command, ""));
+                if command_id.is_empty() {
+                    continue;
+                }
+                let rest = rest.trim();
+                let args = if rest.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![rest.to_string()]
+                };
+                editor_commands.read().trigger_with_args(command_id, args);
+            }
+        }
     };
 
     rsx!(
@@ -137,7 +292,14 @@ pub fn EditorUi(TabProps { tab_id }: TabProps) -> Element {
                     root_path: root_path.clone(),
                 }
             }
+            if is_find_open {
+                FindBar {
+                    tab_id
+                }
+            }
             rect {
+                reference: viewport_reference,
+                height: "fill",
                 a11y_id: focus.attribute(),
                 onkeydown,
                 onkeyup,
@@ -148,23 +310,34 @@ pub fn EditorUi(TabProps { tab_id }: TabProps) -> Element {
                     onscroll,
                     length: lines_len,
                     item_size: line_height,
+                    row_offsets,
                     builder_args: BuilderArgs {
                         tab_id,
                         font_size,
                         line_height,
+                        matching_brackets,
+                        visible_lines,
+                        wrap_width,
+                        char_width,
+                        active_indent_guide,
                     },
                     pressing_alt,
                     pressing_shift,
-                    builder: move |i: usize, builder_args: &BuilderArgs| rsx!(
-                        EditorLine {
-                            key: "{i}",
-                            line_index: i,
-                            builder_args: builder_args.clone(),
-                            editable,
-                            debouncer,
-                            rope: rope.clone()
-                        }
-                    )
+                    builder: move |i: usize, builder_args: &BuilderArgs| {
+                        // Folded lines are skipped
+                        let line_index = builder_args.visible_lines[i];
+                        rsx!(
+                            EditorLine {
+                                key: "{line_index}",
+                                line_index,
+                                builder_args: builder_args.clone(),
+                                editable,
+                                debouncer,
+                                rope: rope.clone(),
+                                pressing_alt,
+                            }
+                        )
+                    }
                 }
             }
         }
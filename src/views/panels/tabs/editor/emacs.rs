@@ -0,0 +1,265 @@
+use std::ops::Range;
+
+use freya::events::{Key, Modifiers};
+use freya::hooks::{TextCursor, TextEditor};
+
+use super::EditorData;
+
+/// Texts removed by killing, the most recent one last.
+/// Kills are copied to the clipboard, and yanking picks up what was copied elsewhere.
+#[derive(Default)]
+pub struct KillRing {
+    kills: Vec<String>,
+    /// Kill inserted by the last yank, counting back from the most recent one.
+    yank_index: usize,
+}
+
+impl KillRing {
+    const MAX_KILLS: usize = 120;
+
+    /// Add a killed text, or append it to the last kill when killing repeatedly.
+    pub fn kill(&mut self, text: String, append: bool) {
+        match self.kills.last_mut() {
+            Some(last) if append => last.push_str(&text),
+            _ => {
+                self.kills.push(text);
+                if self.kills.len() > Self::MAX_KILLS {
+                    self.kills.remove(0);
+                }
+            }
+        }
+        self.yank_index = 0;
+    }
+
+    fn last(&self) -> Option<&str> {
+        self.kills.last().map(String::as_str)
+    }
+
+    /// Text to yank, after adding the clipboard when it was copied from somewhere else.
+    fn yank(&mut self, clipboard: Option<String>) -> Option<&str> {
+        if let Some(text) = clipboard.filter(|text| !text.is_empty()) {
+            if self.last() != Some(text.as_str()) {
+                self.kill(text, false);
+            }
+        }
+        self.yank_index = 0;
+        self.last()
+    }
+
+    /// The kill before the one yanked last, going back to the most recent one after the oldest.
+    fn yank_pop(&mut self) -> Option<&str> {
+        if self.kills.is_empty() {
+            return None;
+        }
+        self.yank_index = (self.yank_index + 1) % self.kills.len();
+        self.kills
+            .get(self.kills.len() - 1 - self.yank_index)
+            .map(String::as_str)
+    }
+}
+
+/// State of the editing with the Emacs keymap.
+#[derive(Default)]
+pub struct EmacsState {
+    /// Where the selection starts while the cursor moves, set with `Ctrl Space`.
+    mark: Option<usize>,
+    /// Cursor and history change after the last kill, killing again from there appends to it.
+    last_kill: Option<(usize, usize)>,
+    /// Range of the last yank and the history change after it, which yank-pop replaces.
+    last_yank: Option<(Range<usize>, usize)>,
+}
+
+impl EditorData {
+    /// Start selecting from the cursor as it moves.
+    pub fn set_mark(&mut self) {
+        self.clear_extra_cursors();
+        self.clear_selection();
+        self.emacs.mark = Some(self.cursor_pos());
+    }
+
+    /// Stop selecting from the mark, returns whether there was one.
+    pub fn cancel_mark(&mut self) -> bool {
+        let had_mark = self.emacs.mark.take().is_some();
+        if had_mark {
+            self.clear_selection();
+        }
+        had_mark
+    }
+
+    /// Whether a key moves the cursor from the mark, anything else deactivates it.
+    /// Typing also removes the selection, so the text goes where the cursor is.
+    pub(super) fn extends_mark(&mut self, key: &Key, modifiers: &Modifiers) -> bool {
+        if self.emacs.mark.is_none() {
+            return false;
+        }
+
+        let moves_cursor = matches!(
+            key,
+            Key::ArrowUp
+                | Key::ArrowDown
+                | Key::ArrowLeft
+                | Key::ArrowRight
+                | Key::Home
+                | Key::End
+                | Key::PageUp
+                | Key::PageDown
+        );
+        if moves_cursor {
+            return true;
+        }
+
+        self.emacs.mark = None;
+        if !modifiers.intersects(Modifiers::CONTROL | Modifiers::META) {
+            self.clear_selection();
+        }
+        false
+    }
+
+    /// Select from the mark to the cursor.
+    pub(super) fn select_from_mark(&mut self) {
+        if let Some(mark) = self.emacs.mark {
+            self.set_selection((mark, self.cursor_pos()));
+        }
+    }
+
+    /// Move the cursor to the start or the end of its line.
+    pub fn move_to_line_boundary(&mut self, end: bool) {
+        let row = self.cursor_row();
+        let col = if end { usize::MAX } else { 0 };
+        self.move_cursor_keeping_mark(self.line_position(row, col));
+    }
+
+    /// Move the cursor to the end of the next word, or to the start of the previous one.
+    pub fn move_word(&mut self, forward: bool) {
+        let pos = {
+            let rope = self.rope.borrow();
+            let len = rope.len_chars();
+            let is_word = |idx: usize| {
+                let ch = rope.char(idx);
+                ch.is_alphanumeric() || ch == '_'
+            };
+
+            let mut idx = rope.utf16_cu_to_char(self.cursor_pos());
+            if forward {
+                while idx < len && !is_word(idx) {
+                    idx += 1;
+                }
+                while idx < len && is_word(idx) {
+                    idx += 1;
+                }
+            } else {
+                while idx > 0 && !is_word(idx - 1) {
+                    idx -= 1;
+                }
+                while idx > 0 && is_word(idx - 1) {
+                    idx -= 1;
+                }
+            }
+            rope.char_to_utf16_cu(idx)
+        };
+        self.move_cursor_keeping_mark(pos);
+    }
+
+    fn move_cursor_keeping_mark(&mut self, pos: usize) {
+        let previous_row = self.cursor_row();
+        self.clear_extra_cursors();
+        self.cursor = TextCursor::new(pos);
+        if self.emacs.mark.is_some() {
+            self.select_from_mark();
+        } else {
+            self.clear_selection();
+        }
+        self.skip_folded_lines(previous_row, false);
+        self.request_scroll_to_cursor();
+    }
+
+    /// Kill the rest of the line, or its line break when the cursor is at the end of it.
+    pub fn kill_line(&mut self, kill_ring: &mut KillRing) -> bool {
+        let pos = self.cursor_pos();
+        let row = self.cursor_row();
+        let line_end = self.line_position(row, usize::MAX);
+        let end = if pos < line_end {
+            line_end
+        } else if row + 1 < self.len_lines() {
+            self.char_to_utf16_cu(self.line_to_char(row + 1))
+        } else {
+            return false;
+        };
+
+        let text = self.text_between(pos, end);
+        let append = self.emacs.last_kill == Some((pos, self.history.current_change()));
+        self.emacs.mark = None;
+        self.edit_lines(row, |editor| {
+            editor.remove(pos..end);
+            editor.cursor = TextCursor::new(pos);
+            editor.clear_selection();
+        });
+
+        kill_ring.kill(text, append);
+        self.emacs.last_kill = Some((pos, self.history.current_change()));
+        if let Some(text) = kill_ring.last() {
+            let _ = self.clipboard.set(text.to_string());
+        }
+        true
+    }
+
+    /// Copy the selection to the kill ring and stop selecting.
+    pub fn copy_region(&mut self, kill_ring: &mut KillRing) -> bool {
+        let Some((start, end)) = self.get_selection_range().filter(|(s, e)| s != e) else {
+            return false;
+        };
+
+        let text = self.text_between(start, end);
+        let _ = self.clipboard.set(text.clone());
+        kill_ring.kill(text, false);
+        self.emacs.mark = None;
+        self.clear_selection();
+        true
+    }
+
+    /// Insert the most recent kill, or what was copied to the clipboard after it.
+    pub fn yank(&mut self, kill_ring: &mut KillRing) -> bool {
+        let clipboard = self.clipboard.get().ok();
+        let Some(text) = kill_ring.yank(clipboard).map(str::to_string) else {
+            return false;
+        };
+
+        let pos = self.cursor_pos();
+        self.emacs.mark = None;
+        self.insert_yank(pos..pos, &text)
+    }
+
+    /// Replace the text inserted by the last yank with the kill before it.
+    pub fn yank_pop(&mut self, kill_ring: &mut KillRing) -> bool {
+        let Some((range, _)) = self.emacs.last_yank.clone().filter(|(range, change)| {
+            *change == self.history.current_change() && range.end == self.cursor_pos()
+        }) else {
+            return false;
+        };
+        let Some(text) = kill_ring.yank_pop().map(str::to_string) else {
+            return false;
+        };
+
+        self.insert_yank(range, &text)
+    }
+
+    fn insert_yank(&mut self, range: Range<usize>, text: &str) -> bool {
+        let row = self.char_to_line(self.utf16_cu_to_char(range.start));
+        let mut len = 0;
+        let edited = self.edit_lines(row, |editor| {
+            if !range.is_empty() {
+                editor.remove(range.clone());
+            }
+            len = editor.insert(text, range.start);
+            editor.cursor = TextCursor::new(range.start + len);
+            editor.clear_selection();
+        });
+
+        self.emacs.last_yank = Some((
+            range.start..range.start + len,
+            self.history.current_change(),
+        ));
+        self.request_scroll_to_cursor();
+        edited
+    }
+}
@@ -0,0 +1,453 @@
+use std::ops::Range;
+
+use freya::hooks::{TextCursor, TextEditor};
+use grep::{
+    matcher::{Captures, Matcher},
+    regex::{RegexMatcher, RegexMatcherBuilder},
+};
+
+use super::EditorData;
+
+/// Replacements of the matches of a line, as byte ranges of its text.
+#[derive(Clone, PartialEq)]
+pub struct LineReplacements {
+    /// Index of the line in the file.
+    pub line: usize,
+    /// Text of the line when it was searched.
+    pub text: String,
+    pub replacements: Vec<(Range<usize>, String)>,
+}
+
+impl LineReplacements {
+    /// Text of the line with the replacements applied.
+    pub fn replaced_text(&self) -> String {
+        let mut text = String::with_capacity(self.text.len());
+        let mut position = 0;
+        for (range, replacement) in &self.replacements {
+            text.push_str(&self.text[position..range.start]);
+            text.push_str(replacement);
+            position = range.end;
+        }
+        text.push_str(&self.text[position..]);
+        text
+    }
+}
+
+/// Toggles of the find bar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FindOption {
+    CaseSensitive,
+    WholeWord,
+    Regex,
+    InSelection,
+}
+
+/// State of the find bar of an editor.
+#[derive(Default)]
+pub struct FindState {
+    pub query: String,
+    pub replacement: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+    /// Part of the text being searched when finding in the selection, as UTF-16 code units.
+    pub scope: Option<Range<usize>>,
+    /// Whether the replace input is shown.
+    pub show_replace: bool,
+    /// Matches in the text, as UTF-16 code units.
+    pub matches: Vec<Range<usize>>,
+    /// Index of the match at the cursor.
+    pub current: Option<usize>,
+    /// Why the query can't be used, e.g an invalid regex.
+    pub error: Option<String>,
+}
+
+impl FindState {
+    pub fn is_enabled(&self, option: FindOption) -> bool {
+        match option {
+            FindOption::CaseSensitive => self.case_sensitive,
+            FindOption::WholeWord => self.whole_word,
+            FindOption::Regex => self.regex,
+            FindOption::InSelection => self.scope.is_some(),
+        }
+    }
+
+    /// Matcher of the query with the enabled options.
+    fn matcher(&self) -> Result<RegexMatcher, String> {
+        RegexMatcherBuilder::new()
+            .case_insensitive(!self.case_sensitive)
+            .word(self.whole_word)
+            .fixed_strings(!self.regex)
+            .multi_line(true)
+            .crlf(true)
+            .build(&self.query)
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Byte ranges of the matches in a text, leaving out the empty ones.
+fn matches_in(matcher: &RegexMatcher, haystack: &str) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+    matcher
+        .find_iter(haystack.as_bytes(), |found| {
+            if !found.is_empty() {
+                matches.push(found.start()..found.end());
+            }
+            true
+        })
+        .ok();
+    matches
+}
+
+/// Byte ranges of the matches in a text, with the text that replaces each one.
+/// Regex replacements can refer to the groups of the match, e.g `$1` or `${name}`.
+pub fn replacements_in(
+    matcher: &RegexMatcher,
+    haystack: &str,
+    replacement: &str,
+    expand_groups: bool,
+) -> Vec<(Range<usize>, String)> {
+    let mut replacements = Vec::new();
+    let Ok(mut captures) = matcher.new_captures() else {
+        return replacements;
+    };
+
+    matcher
+        .captures_iter(haystack.as_bytes(), &mut captures, |captures| {
+            let Some(found) = captures.get(0).filter(|found| !found.is_empty()) else {
+                return true;
+            };
+            let text = if expand_groups {
+                let mut text = Vec::new();
+                captures.interpolate(
+                    |name| matcher.capture_index(name),
+                    haystack.as_bytes(),
+                    replacement.as_bytes(),
+                    &mut text,
+                );
+                String::from_utf8_lossy(&text).into_owned()
+            } else {
+                replacement.to_string()
+            };
+            replacements.push((found.start()..found.end(), text));
+            true
+        })
+        .ok();
+
+    replacements
+}
+
+impl EditorData {
+    /// Show the find bar, searching the selected text or finding in the selected lines.
+    pub fn open_find(&mut self, show_replace: bool) {
+        let selected_text = self.get_selected_text().filter(|text| !text.is_empty());
+        let selection = self.get_selection_range();
+        let find = self.find.get_or_insert_with(FindState::default);
+        find.show_replace |= show_replace;
+
+        match selected_text {
+            Some(text) if text.contains('\n') => {
+                find.scope = selection.map(|(start, end)| start..end);
+            }
+            Some(text) => {
+                find.query = text;
+                find.scope = None;
+            }
+            None => {}
+        }
+
+        self.update_find_matches();
+    }
+
+    /// Hide the find bar, if it's shown.
+    pub fn close_find(&mut self) -> bool {
+        self.find.take().is_some()
+    }
+
+    pub fn set_find_query(&mut self, query: String) {
+        if let Some(find) = self.find.as_mut() {
+            find.query = query;
+            self.update_find_matches();
+            self.select_find_match();
+        }
+    }
+
+    pub fn set_find_replacement(&mut self, replacement: String) {
+        if let Some(find) = self.find.as_mut() {
+            find.replacement = replacement;
+        }
+    }
+
+    pub fn toggle_find_option(&mut self, option: FindOption) {
+        let selection = self
+            .get_selection_range()
+            .filter(|(start, end)| start != end);
+        let Some(find) = self.find.as_mut() else {
+            return;
+        };
+
+        match option {
+            FindOption::CaseSensitive => find.case_sensitive = !find.case_sensitive,
+            FindOption::WholeWord => find.whole_word = !find.whole_word,
+            FindOption::Regex => find.regex = !find.regex,
+            // Only the selection at the time it's enabled is searched
+            FindOption::InSelection if find.scope.is_some() => find.scope = None,
+            FindOption::InSelection => find.scope = selection.map(|(start, end)| start..end),
+        }
+
+        self.update_find_matches();
+    }
+
+    /// Start of the searched text as a byte index, and the text itself.
+    fn searched_text(&self) -> (usize, String) {
+        let rope = self.rope.borrow();
+        let len = rope.len_utf16_cu();
+        let scope = self
+            .find
+            .as_ref()
+            .and_then(|find| find.scope.clone())
+            .map(|scope| scope.start.min(len)..scope.end.min(len))
+            .unwrap_or(0..len);
+        let start_char = rope.utf16_cu_to_char(scope.start);
+        let end_char = rope.utf16_cu_to_char(scope.end);
+        (
+            rope.char_to_byte(start_char),
+            rope.slice(start_char..end_char).to_string(),
+        )
+    }
+
+    /// Search the query again, e.g after the text changes.
+    pub fn update_find_matches(&mut self) {
+        let cursor = self.cursor_pos();
+        let Some(find) = self.find.as_mut() else {
+            return;
+        };
+
+        find.matches.clear();
+        find.current = None;
+        find.error = None;
+        if find.query.is_empty() {
+            return;
+        }
+
+        let matcher = match find.matcher() {
+            Ok(matcher) => matcher,
+            Err(err) => {
+                find.error = Some(err);
+                return;
+            }
+        };
+
+        let (start_byte, haystack) = self.searched_text();
+        let rope = self.rope.borrow();
+        let to_utf16 = |byte: usize| rope.char_to_utf16_cu(rope.byte_to_char(start_byte + byte));
+        let matches = matches_in(&matcher, &haystack)
+            .into_iter()
+            .map(|range| to_utf16(range.start)..to_utf16(range.end))
+            .collect::<Vec<Range<usize>>>();
+
+        let Some(find) = self.find.as_mut() else {
+            return;
+        };
+        find.matches = matches;
+
+        // The match at the cursor, or the next one
+        find.current = find
+            .matches
+            .iter()
+            .position(|found| found.end >= cursor)
+            .or((!find.matches.is_empty()).then_some(0));
+    }
+
+    /// Matches in a line and whether they are the current one, as columns of the line.
+    pub fn find_matches_in_line(&self, line: usize) -> Vec<(Range<usize>, bool)> {
+        let Some(find) = self.find.as_ref().filter(|find| !find.matches.is_empty()) else {
+            return Vec::new();
+        };
+
+        let rope = self.rope.borrow();
+        let line_start = rope.char_to_utf16_cu(rope.line_to_char(line));
+        let line_end = line_start + rope.line(line).len_utf16_cu();
+
+        let first = find
+            .matches
+            .partition_point(|found| found.end <= line_start);
+        find.matches[first..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, found)| found.start < line_end)
+            .map(|(i, found)| {
+                let start = found.start.max(line_start) - line_start;
+                let end = found.end.min(line_end) - line_start;
+                (start..end, find.current == Some(first + i))
+            })
+            .collect()
+    }
+
+    /// Select the next or previous match, wrapping around the ends.
+    pub fn find_next(&mut self, forward: bool) -> bool {
+        let selection = self.get_selection_range();
+        let Some(find) = self.find.as_mut().filter(|find| !find.matches.is_empty()) else {
+            return false;
+        };
+
+        // The match at the cursor is selected first when going forward
+        let len = find.matches.len();
+        let current = match find.current {
+            Some(current)
+                if forward
+                    && selection
+                        != Some((find.matches[current].start, find.matches[current].end)) =>
+            {
+                current
+            }
+            Some(current) if forward => (current + 1) % len,
+            Some(current) => (current + len - 1) % len,
+            None => 0,
+        };
+        find.current = Some(current);
+
+        self.select_find_match()
+    }
+
+    /// Select the current match and scroll to it.
+    fn select_find_match(&mut self) -> bool {
+        let Some(found) = self
+            .find
+            .as_ref()
+            .and_then(|find| find.matches.get(find.current?))
+            .cloned()
+        else {
+            return false;
+        };
+
+        self.clear_extra_cursors();
+        self.set_selection((found.start, found.end));
+        self.cursor = TextCursor::new(found.end);
+        self.request_scroll_to_cursor();
+        true
+    }
+
+    /// Matches with the text that replaces them, as UTF-16 code units.
+    fn match_replacements(&self) -> Vec<(Range<usize>, String)> {
+        let Some(find) = self.find.as_ref() else {
+            return Vec::new();
+        };
+        let Ok(matcher) = find.matcher() else {
+            return Vec::new();
+        };
+
+        let (start_byte, haystack) = self.searched_text();
+        let rope = self.rope.borrow();
+        let to_utf16 = |byte: usize| rope.char_to_utf16_cu(rope.byte_to_char(start_byte + byte));
+        replacements_in(&matcher, &haystack, &find.replacement, find.regex)
+            .into_iter()
+            .map(|(range, text)| (to_utf16(range.start)..to_utf16(range.end), text))
+            .collect()
+    }
+
+    /// Replace the current match and select the next one.
+    pub fn replace_current(&mut self) -> bool {
+        let Some(current) = self
+            .find
+            .as_ref()
+            .and_then(|find| find.matches.get(find.current?))
+            .cloned()
+        else {
+            return false;
+        };
+        let Some((range, text)) = self
+            .match_replacements()
+            .into_iter()
+            .find(|(range, _)| *range == current)
+        else {
+            return false;
+        };
+
+        let first_change = self.history.current_change();
+        self.replace_range(range.clone(), &text);
+        self.group_history(first_change);
+        self.clear_selection();
+        let cursor = range.start + text.encode_utf16().count();
+        self.cursor = TextCursor::new(cursor);
+        self.run_parser();
+
+        // Continue after the replaced text, so it's not matched again
+        if let Some(find) = self.find.as_mut().filter(|find| !find.matches.is_empty()) {
+            let next = find.matches.iter().position(|found| found.start >= cursor);
+            find.current = next.or(Some(0));
+        }
+        self.select_find_match();
+        true
+    }
+
+    /// Replace every match, undone as a single change.
+    pub fn replace_all(&mut self) -> usize {
+        let replacements = self.match_replacements();
+        if replacements.is_empty() {
+            return 0;
+        }
+
+        // From the bottom so the positions of the pending matches don't change
+        let first_change = self.history.current_change();
+        for (range, text) in replacements.iter().rev() {
+            self.replace_range(range.clone(), text);
+        }
+        self.group_history(first_change);
+
+        self.clear_extra_cursors();
+        self.clear_selection();
+        let cursor = self.cursor_pos().min(self.len_utf16_cu());
+        self.cursor = TextCursor::new(cursor);
+        self.run_parser();
+        replacements.len()
+    }
+
+    /// Replace the matches of lines searched outside of the editor, undone as a single change.
+    /// Nothing is replaced if some of the lines changed since they were searched.
+    pub fn replace_in_lines(&mut self, lines: &[LineReplacements]) -> Option<usize> {
+        let is_outdated = lines.iter().any(|line| {
+            !self
+                .line(line.line)
+                .is_some_and(|current| current.text.trim_end_matches(['\n', '\r']) == line.text)
+        });
+        if is_outdated {
+            return None;
+        }
+
+        // From the bottom so the positions of the pending matches don't change
+        let first_change = self.history.current_change();
+        let mut replaced = 0;
+        for line in lines.iter().rev() {
+            for (range, text) in line.replacements.iter().rev() {
+                let col = line.text[..range.start].encode_utf16().count();
+                let start = self.line_position(line.line, col);
+                let end = start + line.text[range.clone()].encode_utf16().count();
+                self.replace_range(start..end, text);
+                replaced += 1;
+            }
+        }
+        self.group_history(first_change);
+
+        self.clear_extra_cursors();
+        self.clear_selection();
+        let cursor = self.cursor_pos().min(self.len_utf16_cu());
+        self.cursor = TextCursor::new(cursor);
+        self.run_parser();
+        Some(replaced)
+    }
+
+    /// Replace a range of the text, keeping the searched selection around it.
+    fn replace_range(&mut self, range: Range<usize>, text: &str) {
+        let removed = self.remove(range.clone());
+        let inserted = if text.is_empty() {
+            0
+        } else {
+            self.insert(text, range.start)
+        };
+
+        if let Some(scope) = self.find.as_mut().and_then(|find| find.scope.as_mut()) {
+            scope.end = (scope.end + inserted).saturating_sub(removed);
+        }
+    }
+}
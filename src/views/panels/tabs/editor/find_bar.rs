@@ -0,0 +1,207 @@
+use dioxus_radio::prelude::use_radio;
+use freya::prelude::*;
+
+use crate::state::{Channel, RadioAppState, TabId};
+use crate::TextArea;
+
+use super::{AppStateEditorUtils, EditorData, FindOption};
+
+/// Toggles of the find bar, with their text.
+const FIND_OPTIONS: [(FindOption, &str); 4] = [
+    (FindOption::CaseSensitive, "Aa"),
+    (FindOption::WholeWord, "ab|"),
+    (FindOption::Regex, ".*"),
+    (FindOption::InSelection, "[…]"),
+];
+
+/// Run an action over the editor of the tab with the find bar.
+fn with_editor(
+    mut radio_app_state: RadioAppState,
+    tab_id: TabId,
+    action: impl FnOnce(&mut EditorData),
+) {
+    let mut app_state = radio_app_state.write_channel(Channel::follow_tab(tab_id));
+    action(&mut app_state.editor_tab_mut(tab_id).editor);
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn FindBar(tab_id: TabId) -> Element {
+    let radio_app_state = use_radio(Channel::follow_tab(tab_id));
+
+    let app_state = radio_app_state.read();
+    let Some(find) = app_state.editor_tab(tab_id).editor.find.as_ref() else {
+        return VNode::empty();
+    };
+
+    let results = match find.current {
+        _ if find.query.is_empty() => String::new(),
+        Some(current) => format!("{} of {}", current + 1, find.matches.len()),
+        None => "No results".to_string(),
+    };
+    let options = FIND_OPTIONS.map(|(option, text)| (option, text, find.is_enabled(option)));
+
+    let onchange_query = move |query: String| {
+        with_editor(radio_app_state, tab_id, |editor| {
+            if editor.find.as_ref().is_some_and(|find| find.query != query) {
+                editor.set_find_query(query);
+            }
+        });
+    };
+
+    let onchange_replacement = move |replacement: String| {
+        with_editor(radio_app_state, tab_id, |editor| {
+            editor.set_find_replacement(replacement);
+        });
+    };
+
+    let find_next = move |forward: bool| {
+        with_editor(radio_app_state, tab_id, |editor| {
+            editor.find_next(forward);
+        });
+    };
+
+    rsx!(
+        rect {
+            width: "100%",
+            padding: "4 10",
+            spacing: "4",
+            background: "rgb(35, 38, 39)",
+            color: "rgb(215, 215, 215)",
+            rect {
+                width: "100%",
+                direction: "horizontal",
+                cross_align: "center",
+                spacing: "4",
+                rect {
+                    width: "fill",
+                    TextArea {
+                        placeholder: "Find...",
+                        value: "{find.query}",
+                        onchange: onchange_query,
+                        onsubmit: move |_| find_next(true),
+                    }
+                }
+                for (option, text, is_enabled) in options {
+                    FindBarButton {
+                        key: "{option:?}",
+                        is_enabled,
+                        onclick: move |_| {
+                            with_editor(radio_app_state, tab_id, |editor| {
+                                editor.toggle_find_option(option);
+                            });
+                        },
+                        label {
+                            "{text}"
+                        }
+                    }
+                }
+                label {
+                    width: "90",
+                    text_align: "center",
+                    "{results}"
+                }
+                FindBarButton {
+                    onclick: move |_| find_next(false),
+                    label {
+                        "↑"
+                    }
+                }
+                FindBarButton {
+                    onclick: move |_| find_next(true),
+                    label {
+                        "↓"
+                    }
+                }
+                FindBarButton {
+                    onclick: move |_| {
+                        with_editor(radio_app_state, tab_id, |editor| {
+                            editor.close_find();
+                        });
+                    },
+                    label {
+                        "×"
+                    }
+                }
+            }
+            if find.show_replace {
+                rect {
+                    width: "100%",
+                    direction: "horizontal",
+                    cross_align: "center",
+                    spacing: "4",
+                    rect {
+                        width: "fill",
+                        TextArea {
+                            placeholder: "Replace...",
+                            value: "{find.replacement}",
+                            onchange: onchange_replacement,
+                            onsubmit: move |_| {
+                                with_editor(radio_app_state, tab_id, |editor| {
+                                    editor.replace_current();
+                                });
+                            },
+                            auto_focus: false,
+                        }
+                    }
+                    FindBarButton {
+                        onclick: move |_| {
+                            with_editor(radio_app_state, tab_id, |editor| {
+                                editor.replace_current();
+                            });
+                        },
+                        label {
+                            "Replace"
+                        }
+                    }
+                    FindBarButton {
+                        onclick: move |_| {
+                            with_editor(radio_app_state, tab_id, |editor| {
+                                editor.replace_all();
+                            });
+                        },
+                        label {
+                            "Replace All"
+                        }
+                    }
+                }
+            }
+            if let Some(error) = &find.error {
+                label {
+                    color: "rgb(251, 60, 44)",
+                    max_lines: "2",
+                    text_overflow: "ellipsis",
+                    "{error}"
+                }
+            }
+        }
+    )
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn FindBarButton(
+    children: Element,
+    onclick: EventHandler<()>,
+    #[props(default = false)] is_enabled: bool,
+) -> Element {
+    let background = if is_enabled {
+        "rgb(70, 70, 70)"
+    } else {
+        "none"
+    };
+
+    rsx!(
+        Button {
+            onpress: move |_| onclick.call(()),
+            theme: theme_with!(ButtonTheme {
+                margin: "0".into(),
+                padding: "6 8".into(),
+                background: background.into(),
+                hover_background: "rgb(60, 60, 60)".into(),
+                border_fill: "none".into(),
+            }),
+            {children}
+        }
+    )
+}
@@ -0,0 +1,251 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+use freya::prelude::Rope;
+
+use crate::parser::{find_multiline_brackets, SyntaxBlocks};
+
+/// A range of lines that can be collapsed.
+/// The start line is always visible, the following lines until the end line (included) get hidden.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FoldingRegion {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl FoldingRegion {
+    pub fn contains(&self, line: usize) -> bool {
+        line >= self.start_line && line <= self.end_line
+    }
+
+    pub fn hides(&self, line: usize) -> bool {
+        line > self.start_line && line <= self.end_line
+    }
+}
+
+/// The foldable regions of an editor and which ones are folded.
+#[derive(Default)]
+pub struct EditorFolding {
+    regions: BTreeMap<usize, usize>,
+    lsp_regions: Option<Vec<FoldingRegion>>,
+    folded: BTreeSet<usize>,
+    visible_lines: Rc<Vec<usize>>,
+}
+
+impl EditorFolding {
+    /// Indexes of the lines that are not hidden by a fold, in order.
+    pub fn visible_lines(&self) -> Rc<Vec<usize>> {
+        self.visible_lines.clone()
+    }
+
+    /// Position of the given line among the visible lines.
+    pub fn visible_index(&self, line: usize) -> usize {
+        match self.visible_lines.binary_search(&line) {
+            Ok(idx) => idx,
+            Err(idx) => idx.saturating_sub(1),
+        }
+    }
+
+    pub fn region(&self, start_line: usize) -> Option<FoldingRegion> {
+        self.regions.get(&start_line).map(|end_line| FoldingRegion {
+            start_line,
+            end_line: *end_line,
+        })
+    }
+
+    pub fn is_folded(&self, start_line: usize) -> bool {
+        self.folded.contains(&start_line)
+    }
+
+    pub fn is_hidden(&self, line: usize) -> bool {
+        self.folded_regions().any(|region| region.hides(line))
+    }
+
+    /// The smallest region that contains the given line.
+    pub fn innermost_region(&self, line: usize) -> Option<FoldingRegion> {
+        self.regions
+            .range(..=line)
+            .rev()
+            .map(|(start_line, end_line)| FoldingRegion {
+                start_line: *start_line,
+                end_line: *end_line,
+            })
+            .find(|region| region.contains(line))
+    }
+
+    /// The start line of the outermost fold that hides the given line.
+    pub fn outermost_fold_start(&self, line: usize) -> Option<usize> {
+        self.folded_regions()
+            .find(|region| region.hides(line))
+            .map(|region| region.start_line)
+    }
+
+    fn folded_regions(&self) -> impl Iterator<Item = FoldingRegion> + '_ {
+        self.folded
+            .iter()
+            .filter_map(|start_line| self.region(*start_line))
+    }
+
+    /// Use the regions reported by the Language Server instead of the computed ones.
+    pub fn set_lsp_regions(&mut self, regions: Vec<FoldingRegion>) {
+        self.lsp_regions = Some(regions).filter(|regions| !regions.is_empty());
+    }
+
+    /// Recompute the foldable regions after the text has changed.
+    /// The Language Server regions are only used when they are known to be in sync with the text.
+    pub fn update_regions(
+        &mut self,
+        rope: &Rope,
+        syntax_blocks: &SyntaxBlocks,
+        lsp_in_sync: bool,
+        tab_width: usize,
+    ) {
+        self.regions.clear();
+
+        match self.lsp_regions.as_ref().filter(|_| lsp_in_sync) {
+            Some(lsp_regions) => {
+                for region in lsp_regions {
+                    self.regions.insert(region.start_line, region.end_line);
+                }
+            }
+            None => {
+                for (start_line, end_line) in
+                    indentation_regions(rope, syntax_blocks.len(), tab_width)
+                {
+                    self.regions.insert(start_line, end_line);
+                }
+                // Brackets are more precise than the indentation
+                for (open_line, close_line) in find_multiline_brackets(rope, syntax_blocks) {
+                    if close_line - 1 > open_line {
+                        self.regions.insert(open_line, close_line - 1);
+                    }
+                }
+            }
+        }
+
+        self.folded
+            .retain(|start_line| self.regions.contains_key(start_line));
+        self.update_visible_lines(syntax_blocks.len());
+    }
+
+    /// Move the folds after `edited_line` by the number of lines that were added or removed.
+    pub fn shift_lines(&mut self, edited_line: usize, delta: isize) {
+        if delta == 0 {
+            return;
+        }
+
+        self.folded = self
+            .folded
+            .iter()
+            .filter_map(|start_line| {
+                if *start_line <= edited_line {
+                    Some(*start_line)
+                } else {
+                    start_line
+                        .checked_add_signed(delta)
+                        .filter(|start_line| *start_line > edited_line)
+                }
+            })
+            .collect();
+    }
+
+    pub fn toggle(&mut self, start_line: usize, lines_len: usize) -> bool {
+        if !self.regions.contains_key(&start_line) {
+            return false;
+        }
+
+        if !self.folded.remove(&start_line) {
+            self.folded.insert(start_line);
+        }
+        self.update_visible_lines(lines_len);
+        true
+    }
+
+    pub fn fold_all(&mut self, lines_len: usize) {
+        self.folded = self.regions.keys().copied().collect();
+        self.update_visible_lines(lines_len);
+    }
+
+    pub fn unfold_all(&mut self, lines_len: usize) {
+        self.folded.clear();
+        self.update_visible_lines(lines_len);
+    }
+
+    /// Unfold every region that hides the given line.
+    pub fn reveal_line(&mut self, line: usize, lines_len: usize) {
+        let hiding = self
+            .folded_regions()
+            .filter(|region| region.hides(line))
+            .map(|region| region.start_line)
+            .collect::<Vec<usize>>();
+
+        if !hiding.is_empty() {
+            for start_line in hiding {
+                self.folded.remove(&start_line);
+            }
+            self.update_visible_lines(lines_len);
+        }
+    }
+
+    fn update_visible_lines(&mut self, lines_len: usize) {
+        let mut visible_lines = Vec::with_capacity(lines_len);
+        let mut line = 0;
+
+        while line < lines_len {
+            visible_lines.push(line);
+            line = match self.region(line).filter(|_| self.is_folded(line)) {
+                Some(region) => region.end_line + 1,
+                None => line + 1,
+            };
+        }
+
+        self.visible_lines = Rc::new(visible_lines);
+    }
+}
+
+/// Indentation width of a line, or `None` if the line is blank.
+fn line_indentation(rope: &Rope, line: usize, tab_width: usize) -> Option<usize> {
+    let mut indentation = 0;
+    for ch in rope.line(line).chars() {
+        match ch {
+            ' ' => indentation += 1,
+            '\t' => indentation += tab_width,
+            '\n' | '\r' => return None,
+            _ => return Some(indentation),
+        }
+    }
+    None
+}
+
+/// Regions made of the lines that are more indented than the line before them.
+fn indentation_regions(rope: &Rope, lines_len: usize, tab_width: usize) -> Vec<(usize, usize)> {
+    let lines_len = lines_len.min(rope.len_lines());
+    let indentations = (0..lines_len)
+        .map(|line| line_indentation(rope, line, tab_width))
+        .collect::<Vec<Option<usize>>>();
+
+    let mut regions = Vec::new();
+
+    for (start_line, indentation) in indentations.iter().enumerate() {
+        let Some(indentation) = indentation else {
+            continue;
+        };
+
+        // Blank lines are only part of the region when followed by more indented lines
+        let end_line = indentations[start_line + 1..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, line_indentation)| {
+                line_indentation.is_none_or(|line_indentation| line_indentation > *indentation)
+            })
+            .filter(|(_, line_indentation)| line_indentation.is_some())
+            .last()
+            .map(|(offset, _)| start_line + 1 + offset);
+
+        if let Some(end_line) = end_line {
+            regions.push((start_line, end_line));
+        }
+    }
+
+    regions
+}
@@ -0,0 +1,383 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Range, RangeInclusive},
+};
+
+use freya::hooks::{TextCursor, TextEditor};
+
+use crate::indentation::leading_whitespace;
+
+use super::EditorData;
+
+/// Line and column, in UTF-16 code units.
+type Point = (usize, usize);
+
+/// How lines get sorted.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+    /// Numbers are compared by their value.
+    Natural,
+    /// Ascending, without repeated lines.
+    Unique,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 4] = [
+        Self::Ascending,
+        Self::Descending,
+        Self::Natural,
+        Self::Unique,
+    ];
+}
+
+impl Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Ascending => "Ascending",
+            Self::Descending => "Descending",
+            Self::Natural => "Natural",
+            Self::Unique => "Unique",
+        })
+    }
+}
+
+impl EditorData {
+    /// Swap the selected lines with the line above or below them.
+    pub fn move_lines(&mut self, up: bool) -> bool {
+        let lines = self.selected_lines();
+        let (first, last) = (*lines.start(), *lines.end());
+        let range = if up {
+            if first == 0 {
+                return false;
+            }
+            first - 1..=last
+        } else {
+            if last + 1 >= self.len_lines() {
+                return false;
+            }
+            first..=last + 1
+        };
+
+        let mut texts = self.lines_text(range.clone());
+        if up {
+            texts.rotate_left(1);
+        } else {
+            texts.rotate_right(1);
+        }
+
+        let points = self.cursor_points();
+        self.edit_lines(first, |editor| {
+            editor.replace_lines(range, &texts);
+            editor.load_cursor_points(points, if up { -1 } else { 1 });
+        })
+    }
+
+    /// Duplicate the selected text, or the line of the cursor when there is no selection.
+    pub fn duplicate_selection(&mut self) -> bool {
+        let line = self.cursor_row();
+
+        if let Some((start, end)) = self.get_selection_range().filter(|(s, e)| s != e) {
+            let text = self.text_between(start, end);
+            return self.edit_lines(line, |editor| {
+                // The copy gets selected
+                let len = editor.insert(&text, end);
+                editor.selected = Some((end, end + len));
+                editor.cursor = TextCursor::new(end + len);
+            });
+        }
+
+        let texts = vec![self.line_text(line); 2];
+        let points = self.cursor_points();
+        self.edit_lines(line, |editor| {
+            editor.replace_lines(line..=line, &texts);
+            editor.load_cursor_points(points, 1);
+        })
+    }
+
+    /// Remove the selected lines, with their line breaks.
+    pub fn delete_lines(&mut self) -> bool {
+        let lines = self.selected_lines();
+        let (first, last) = (*lines.start(), *lines.end());
+        let col = self.cursor_col();
+
+        let range = self.lines_range(first, last);
+        if range.is_empty() {
+            return false;
+        }
+
+        self.edit_lines(first, |editor| {
+            editor.remove(range);
+            let line = first.min(editor.len_lines() - 1);
+            editor.cursor = TextCursor::new(editor.line_position(line, col));
+            editor.clear_selection();
+        })
+    }
+
+    /// Join the selected lines, or the line of the cursor with the next one,
+    /// separated by a single space.
+    pub fn join_lines(&mut self) -> bool {
+        let lines = self.selected_lines();
+        let first = *lines.start();
+        let last = (*lines.end()).max(first + 1);
+        if last >= self.len_lines() {
+            return false;
+        }
+
+        let mut joined = self.line_text(first);
+        let mut join_col = 0;
+        for text in self.lines_text(first + 1..=last) {
+            let text = text.trim_start();
+            joined.truncate(joined.trim_end().len());
+            join_col = joined.encode_utf16().count();
+            if !joined.is_empty() && !text.is_empty() {
+                joined.push(' ');
+            }
+            joined.push_str(text);
+        }
+
+        self.edit_lines(first, |editor| {
+            editor.replace_lines(first..=last, &[joined]);
+            editor.cursor = TextCursor::new(editor.line_start(first) + join_col);
+            editor.clear_selection();
+        })
+    }
+
+    /// Add an empty line above or below the line of the cursor, with its indentation.
+    pub fn insert_line(&mut self, above: bool) -> bool {
+        let line = self.cursor_row();
+        let text = self.line_text(line);
+        let (len, _) = leading_whitespace(&text, self.indentation.tab_width());
+        let indentation = &text[..len];
+        let line_break = self.line_break();
+
+        let edited_line = if above { line.saturating_sub(1) } else { line };
+        self.edit_lines(edited_line, |editor| {
+            if above {
+                let pos = editor.line_start(line);
+                editor.insert(&format!("{indentation}{line_break}"), pos);
+                editor.cursor = TextCursor::new(pos + len);
+            } else {
+                let pos = editor.line_position(line, usize::MAX);
+                let inserted = editor.insert(&format!("{line_break}{indentation}"), pos);
+                editor.cursor = TextCursor::new(pos + inserted);
+            }
+            editor.clear_selection();
+        })
+    }
+
+    /// Sort the selected lines, or all of them when there is no multiline selection.
+    pub fn sort_lines(&mut self, order: SortOrder) -> bool {
+        self.rearrange_lines(|lines| match order {
+            SortOrder::Ascending => lines.sort(),
+            SortOrder::Descending => {
+                lines.sort();
+                lines.reverse();
+            }
+            SortOrder::Natural => lines.sort_by(|a, b| natural_cmp(a, b)),
+            SortOrder::Unique => {
+                lines.sort();
+                lines.dedup();
+            }
+        })
+    }
+
+    /// Reverse the selected lines, or all of them when there is no multiline selection.
+    pub fn reverse_lines(&mut self) -> bool {
+        self.rearrange_lines(|lines| lines.reverse())
+    }
+
+    /// Remove the whitespace at the end of every line.
+    pub fn trim_trailing_whitespace(&mut self) -> bool {
+        let (row, col) = (self.cursor_row(), self.cursor_col());
+
+        self.edit_lines(0, |editor| {
+            // From the bottom so the positions of the pending lines don't change
+            for line in (0..editor.len_lines()).rev() {
+                let text = editor.line_text(line);
+                let trimmed_len = text.trim_end().encode_utf16().count();
+                let len = text.encode_utf16().count();
+                if trimmed_len < len {
+                    let line_start = editor.line_start(line);
+                    editor.remove(line_start + trimmed_len..line_start + len);
+                }
+            }
+            editor.cursor = TextCursor::new(editor.line_position(row, col));
+            editor.clear_selection();
+        })
+    }
+
+    fn rearrange_lines(&mut self, rearrange: impl FnOnce(&mut Vec<String>)) -> bool {
+        let mut range = self.selected_lines();
+        let whole_text = range.start() == range.end();
+        if whole_text {
+            // The empty line after the last line break stays at the end
+            let mut last = self.len_lines() - 1;
+            if last > 0 && self.line_text(last).is_empty() {
+                last -= 1;
+            }
+            range = 0..=last;
+        }
+
+        let mut lines = self.lines_text(range.clone());
+        rearrange(&mut lines);
+
+        let first = *range.start();
+        let (row, col) = (self.cursor_row(), self.cursor_col());
+        self.edit_lines(first, |editor| {
+            editor.replace_lines(range, &lines);
+            let last = first + lines.len().saturating_sub(1);
+            if whole_text {
+                let row = row.min(editor.len_lines() - 1);
+                editor.cursor = TextCursor::new(editor.line_position(row, col));
+                editor.clear_selection();
+            } else {
+                // The rearranged lines stay selected
+                let end = editor.line_position(last, usize::MAX);
+                editor.selected = Some((editor.line_start(first), end));
+                editor.cursor = TextCursor::new(end);
+            }
+        })
+    }
+
+    /// Run an edit of lines as a single history step, starting in the given line.
+    pub(super) fn edit_lines(&mut self, edited_line: usize, edit: impl FnOnce(&mut Self)) -> bool {
+        let first_change = self.history.current_change();
+        let lines_len = self.len_lines();
+
+        self.clear_extra_cursors();
+        edit(self);
+
+        if self.history.current_change() == first_change {
+            return false;
+        }
+
+        let delta = self.len_lines() as isize - lines_len as isize;
+        self.folding.shift_lines(edited_line, delta);
+        self.group_history(first_change);
+        self.run_parser();
+        true
+    }
+
+    /// Replace the text of some lines, the line break of the last one is kept.
+    pub(super) fn replace_lines(&mut self, lines: RangeInclusive<usize>, new_lines: &[String]) {
+        let start = self.line_start(*lines.start());
+        let end = self.line_position(*lines.end(), usize::MAX);
+        let text = new_lines.join(self.line_break());
+        if self.text_between(start, end) == text {
+            return;
+        }
+
+        if start < end {
+            self.remove(start..end);
+        }
+        if !text.is_empty() {
+            self.insert(&text, start);
+        }
+    }
+
+    /// Lines of the selection, or the line of the cursor.
+    /// A selection that ends at the start of a line doesn't include that line.
+    fn selected_lines(&self) -> RangeInclusive<usize> {
+        let line = self.cursor_row();
+        let Some((start, end)) = self.get_selection_range().filter(|(s, e)| s != e) else {
+            return line..=line;
+        };
+
+        let (first, _) = self.point(start);
+        let (mut last, end_col) = self.point(end);
+        if last > first && end_col == 0 {
+            last -= 1;
+        }
+        first..=last
+    }
+
+    /// Text of a line, without its line break.
+    pub(super) fn line_text(&self, line: usize) -> String {
+        self.line(line)
+            .map(|line| line.text.trim_end_matches(['\n', '\r']).to_string())
+            .unwrap_or_default()
+    }
+
+    pub(super) fn lines_text(&self, lines: RangeInclusive<usize>) -> Vec<String> {
+        lines.map(|line| self.line_text(line)).collect()
+    }
+
+    /// Range of some lines with a line break, so removing it leaves no empty line behind.
+    pub(super) fn lines_range(&self, first: usize, last: usize) -> Range<usize> {
+        if last + 1 < self.len_lines() {
+            self.line_start(first)..self.line_start(last + 1)
+        } else if first > 0 {
+            // The last line has no line break, so the one before it goes instead
+            self.line_position(first - 1, usize::MAX)..self.line_position(last, usize::MAX)
+        } else {
+            0..self.line_position(last, usize::MAX)
+        }
+    }
+
+    fn line_start(&self, line: usize) -> usize {
+        self.char_to_utf16_cu(self.line_to_char(line))
+    }
+
+    fn point(&self, pos: usize) -> Point {
+        let line = self.char_to_line(self.utf16_cu_to_char(pos));
+        (line, pos - self.line_start(line))
+    }
+
+    /// The cursor and its selection as points, to restore them after editing the lines.
+    fn cursor_points(&self) -> (Point, Option<(Point, Point)>) {
+        (
+            self.point(self.cursor_pos()),
+            self.selected
+                .map(|(from, to)| (self.point(from), self.point(to))),
+        )
+    }
+
+    fn load_cursor_points(
+        &mut self,
+        (cursor, selection): (Point, Option<(Point, Point)>),
+        line_delta: isize,
+    ) {
+        let to_pos = |editor: &Self, (line, col): Point| {
+            editor.line_position(line.saturating_add_signed(line_delta), col)
+        };
+        self.cursor = TextCursor::new(to_pos(self, cursor));
+        self.selected = selection.map(|(from, to)| (to_pos(self, from), to_pos(self, to)));
+    }
+}
+
+/// Compare two texts, with the numbers inside them compared by their value.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        match (a.chars().next(), b.chars().next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let a_len = a.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(a.len());
+                let b_len = b.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(b.len());
+                let a_number = a[..a_len].trim_start_matches('0');
+                let b_number = b[..b_len].trim_start_matches('0');
+                let ordering = a_number
+                    .len()
+                    .cmp(&b_number.len())
+                    .then_with(|| a_number.cmp(b_number));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a = &a[a_len..];
+                b = &b[b_len..];
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a = &a[x.len_utf8()..];
+                b = &b[y.len_utf8()..];
+            }
+        }
+    }
+}
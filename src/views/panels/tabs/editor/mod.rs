@@ -1,12 +1,36 @@
+mod column_selection;
 mod commands;
+mod comments;
+mod cursors;
 mod editor_data;
 mod editor_line;
 mod editor_tab;
 mod editor_ui;
+mod emacs;
+mod find;
+mod find_bar;
+mod folding;
 mod hover_box;
+mod lines;
+mod typing;
+mod undo_tree;
 mod utils;
+mod vim;
+mod whitespace;
+mod wrap;
 
+pub use column_selection::*;
+pub use cursors::*;
 pub use editor_data::*;
 pub use editor_line::*;
 pub use editor_tab::*;
+pub use emacs::*;
+pub use find::*;
+pub use find_bar::*;
+pub use folding::*;
+pub use lines::*;
+pub use undo_tree::*;
 pub use utils::*;
+pub use vim::*;
+pub use whitespace::*;
+pub use wrap::*;
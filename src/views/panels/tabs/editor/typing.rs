@@ -0,0 +1,205 @@
+use freya::events::{Key, Modifiers};
+use freya::hooks::{TextCursor, TextEditor};
+use freya_hooks::TextEvent;
+
+use crate::indentation::leading_whitespace;
+
+use super::EditorData;
+
+impl EditorData {
+    /// Edits made while typing that follow the rules of the language,
+    /// like auto-closing pairs and auto-indentation.
+    /// Returns `None` when the key must be processed as usual.
+    pub(super) fn process_typing_key(
+        &mut self,
+        key: &Key,
+        modifiers: &Modifiers,
+    ) -> Option<TextEvent> {
+        if modifiers.intersects(Modifiers::CONTROL | Modifiers::ALT | Modifiers::META) {
+            return None;
+        }
+
+        match key {
+            Key::Enter => Some(self.insert_line_break()),
+            Key::Backspace => self.remove_empty_pair(),
+            Key::Character(text) => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => self.type_char(ch),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn type_char(&mut self, ch: char) -> Option<TextEvent> {
+        let language = self.language_id().config();
+        let closing_pair = language
+            .auto_closing_pairs
+            .iter()
+            .find(|(open, _)| *open == ch)
+            .map(|(_, close)| *close);
+
+        // Wrap the selected text with the pair
+        if let Some((start, end)) = self.get_selection_range().filter(|(s, e)| s != e) {
+            let close = closing_pair?;
+            self.insert(&close.to_string(), end);
+            let len = self.insert(&ch.to_string(), start);
+            self.selected = Some((start + len, end + len));
+            self.cursor = TextCursor::new(end + len);
+            return Some(TextEvent::TEXT_CHANGED);
+        }
+
+        let pos = self.cursor_pos();
+        let next_char = self.char_at(pos);
+
+        // Type over the closing char instead of adding another one
+        let is_closing = language
+            .auto_closing_pairs
+            .iter()
+            .any(|(_, close)| *close == ch);
+        if is_closing && next_char == Some(ch) {
+            self.clear_selection();
+            self.cursor = TextCursor::new(pos + ch.len_utf16());
+            return Some(TextEvent::CURSOR_CHANGED);
+        }
+
+        if let Some(close) = closing_pair {
+            // Only close when there is nothing that could be part of the pair after the cursor
+            let closes_before = next_char.is_none_or(|next| {
+                next.is_whitespace()
+                    || language
+                        .auto_closing_pairs
+                        .iter()
+                        .any(|(_, close)| *close == next)
+            });
+            // Quotes right after a word are more likely an apostrophe
+            let after_word = ch == close
+                && self
+                    .char_before(pos)
+                    .is_some_and(|prev| prev.is_alphanumeric());
+
+            if closes_before && !after_word {
+                self.clear_selection();
+                let len = self.insert(&format!("{ch}{close}"), pos);
+                self.cursor = TextCursor::new(pos + len - close.len_utf16());
+                return Some(TextEvent::TEXT_CHANGED);
+            }
+        }
+
+        // Closing brackets typed in an empty line go back one indentation level
+        let is_closing_bracket = language.brackets.iter().any(|(_, close)| *close == ch);
+        if is_closing_bracket {
+            let line = self.cursor_row();
+            let line_start = self.char_to_utf16_cu(self.line_to_char(line));
+            let before_cursor = self.text_between(line_start, pos);
+            if !before_cursor.is_empty() && before_cursor.trim().is_empty() {
+                let (len, width) = leading_whitespace(&before_cursor, self.indentation.tab_width());
+                let level = self.indentation.size.max(1) as usize;
+                let whitespace = self
+                    .indentation
+                    .whitespace(width.saturating_sub(1) / level * level);
+
+                self.clear_selection();
+                self.remove(line_start..line_start + len);
+                let len = self.insert(&format!("{whitespace}{ch}"), line_start);
+                self.cursor = TextCursor::new(line_start + len);
+                return Some(TextEvent::TEXT_CHANGED);
+            }
+        }
+
+        None
+    }
+
+    /// Remove both chars of an empty pair when removing the opening one.
+    fn remove_empty_pair(&mut self) -> Option<TextEvent> {
+        if self.get_selection_range().is_some_and(|(s, e)| s != e) {
+            return None;
+        }
+
+        let pos = self.cursor_pos();
+        let prev_char = self.char_before(pos)?;
+        let next_char = self.char_at(pos)?;
+        let language = self.language_id().config();
+        let is_pair = language
+            .auto_closing_pairs
+            .contains(&(prev_char, next_char));
+        if !is_pair {
+            return None;
+        }
+
+        let start = pos - prev_char.len_utf16();
+        self.clear_selection();
+        self.remove(start..pos + next_char.len_utf16());
+        self.cursor = TextCursor::new(start);
+        Some(TextEvent::TEXT_CHANGED)
+    }
+
+    /// Break the line keeping its indentation, one level deeper after an opening bracket.
+    /// Between a pair of brackets the closing one goes to its own line.
+    fn insert_line_break(&mut self) -> TextEvent {
+        if let Some((start, end)) = self.get_selection_range() {
+            if start != end {
+                self.remove(start..end);
+            }
+            self.cursor = TextCursor::new(start);
+        }
+        self.clear_selection();
+
+        let language = self.language_id().config();
+        let line_break = self.line_break();
+
+        let pos = self.cursor_pos();
+        let line_start = self.char_to_utf16_cu(self.line_to_char(self.cursor_row()));
+        let before_cursor = self.text_between(line_start, pos);
+        let (_, width) = leading_whitespace(&before_cursor, self.indentation.tab_width());
+        let last_char = before_cursor.trim_end().chars().last();
+
+        let indents = last_char.is_some_and(|ch| language.indent_after.contains(&ch));
+        let inner_width = if indents {
+            width + self.indentation.size as usize
+        } else {
+            width
+        };
+        let inner_indentation = self.indentation.whitespace(inner_width);
+
+        let between_brackets = last_char.zip(self.char_at(pos)).is_some_and(|pair| {
+            language.brackets.contains(&pair) || language.auto_closing_pairs.contains(&pair)
+        });
+
+        let text = if indents && between_brackets {
+            let closing_indentation = self.indentation.whitespace(width);
+            format!("{line_break}{inner_indentation}{line_break}{closing_indentation}")
+        } else {
+            format!("{line_break}{inner_indentation}")
+        };
+
+        self.insert(&text, pos);
+        let cursor_offset = line_break.len() + inner_indentation.encode_utf16().count();
+        self.cursor = TextCursor::new(pos + cursor_offset);
+        TextEvent::TEXT_CHANGED
+    }
+
+    /// Char that starts in the given position.
+    fn char_at(&self, pos: usize) -> Option<char> {
+        let rope = self.rope.borrow();
+        if pos >= rope.len_utf16_cu() {
+            return None;
+        }
+        rope.get_char(rope.utf16_cu_to_char(pos))
+    }
+
+    /// Char that ends in the given position.
+    fn char_before(&self, pos: usize) -> Option<char> {
+        let rope = self.rope.borrow();
+        let idx = rope.utf16_cu_to_char(pos.min(rope.len_utf16_cu()));
+        rope.get_char(idx.checked_sub(1)?)
+    }
+
+    pub(super) fn text_between(&self, start: usize, end: usize) -> String {
+        let rope = self.rope.borrow();
+        rope.slice(rope.utf16_cu_to_char(start)..rope.utf16_cu_to_char(end))
+            .to_string()
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use freya::hooks::{HistoryChange, TextCursor, TextEditor};
+
+use super::{apply_change, EditorData};
+
+/// State of the text before any change.
+const ROOT: usize = 0;
+
+/// A change of the undo tree, and the state of the text after it.
+pub struct UndoNode {
+    parent: usize,
+    change: Option<HistoryChange>,
+    /// When the change was made.
+    pub time: Instant,
+    /// Whether it's followed by other changes done together, so it's not a state of its own.
+    grouped: bool,
+}
+
+/// Every change of the history, keeping the ones undone before making other changes.
+/// The history of the editor is the branch being edited.
+pub struct UndoTree {
+    /// Nodes in the order they were made, the first one is the root.
+    nodes: Vec<UndoNode>,
+    /// Nodes of the changes in the history of the editor, including the ones that can be redone.
+    branch: Vec<usize>,
+}
+
+impl Default for UndoTree {
+    fn default() -> Self {
+        Self {
+            nodes: vec![UndoNode {
+                parent: ROOT,
+                change: None,
+                time: Instant::now(),
+                grouped: false,
+            }],
+            branch: Vec::new(),
+        }
+    }
+}
+
+impl UndoTree {
+    /// Tree with a single branch of changes, grouped by the given ranges.
+    pub fn from_changes(changes: Vec<HistoryChange>, groups: &[Range<usize>]) -> Self {
+        let mut tree = Self::default();
+        for (position, change) in changes.into_iter().enumerate() {
+            tree.push(position, change);
+        }
+        for group in groups {
+            tree.group(group.clone());
+        }
+        tree
+    }
+
+    /// Add a change after a position of the branch, the changes after it are left in another branch.
+    pub fn push(&mut self, position: usize, change: HistoryChange) {
+        self.branch.truncate(position);
+        self.nodes.push(UndoNode {
+            parent: self.node_at(position),
+            change: Some(change),
+            time: Instant::now(),
+            grouped: false,
+        });
+        self.branch.push(self.nodes.len() - 1);
+    }
+
+    /// Make the changes of the branch in the range a single state.
+    pub fn group(&mut self, changes: Range<usize>) {
+        for position in changes.start..changes.end.saturating_sub(1) {
+            if let Some(node) = self.branch.get(position) {
+                self.nodes[*node].grouped = true;
+            }
+        }
+    }
+
+    /// Ranges of changes of the branch that are undone and redone together.
+    pub fn groups(&self) -> Vec<Range<usize>> {
+        let mut groups = Vec::new();
+        let mut group_start = None;
+        for (position, node) in self.branch.iter().enumerate() {
+            if self.nodes[*node].grouped {
+                group_start.get_or_insert(position);
+            } else if let Some(start) = group_start.take() {
+                groups.push(start..position + 1);
+            }
+        }
+        groups
+    }
+
+    /// Changes of the branch, as kept in the history of the editor.
+    pub fn branch_changes(&self) -> Vec<HistoryChange> {
+        self.branch
+            .iter()
+            .filter_map(|node| self.nodes[*node].change.clone())
+            .collect()
+    }
+
+    /// Node of the state after the given number of changes of the branch.
+    pub fn node_at(&self, position: usize) -> usize {
+        position
+            .checked_sub(1)
+            .map(|idx| self.branch[idx])
+            .unwrap_or(ROOT)
+    }
+
+    pub fn node(&self, node: usize) -> &UndoNode {
+        &self.nodes[node]
+    }
+
+    /// Nodes that are states of the text, from the oldest to the most recent one.
+    pub fn states(&self) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(|node| !self.nodes[*node].grouped)
+    }
+
+    /// Changes from the previous state to the given one.
+    pub fn state_changes(&self, node: usize) -> Vec<&HistoryChange> {
+        let mut changes = Vec::new();
+        let mut current = node;
+        while let Some(change) = &self.nodes[current].change {
+            changes.push(change);
+            current = self.nodes[current].parent;
+            if !self.nodes[current].grouped {
+                break;
+            }
+        }
+        changes.reverse();
+        changes
+    }
+
+    /// Whether the state is in the branch, before or after the current position.
+    pub fn is_in_branch(&self, node: usize) -> bool {
+        node == ROOT || self.branch.contains(&node)
+    }
+
+    /// Nodes from the root to the given one, the root excluded.
+    fn ancestors(&self, node: usize) -> Vec<usize> {
+        let mut ancestors = Vec::new();
+        let mut current = node;
+        while current != ROOT {
+            ancestors.push(current);
+            current = self.nodes[current].parent;
+        }
+        ancestors.reverse();
+        ancestors
+    }
+
+    /// Position of the branch where the path to the node leaves it, and the nodes after it.
+    fn route(&self, node: usize) -> (usize, Vec<usize>) {
+        let ancestors = self.ancestors(node);
+        let shared = ancestors
+            .iter()
+            .zip(&self.branch)
+            .take_while(|(ancestor, node)| ancestor == node)
+            .count();
+        (shared, ancestors[shared..].to_vec())
+    }
+
+    /// Children of a node, in the order they were made.
+    fn children(&self, node: usize) -> Vec<usize> {
+        (node + 1..self.nodes.len())
+            .filter(|child| self.nodes[*child].parent == node)
+            .collect()
+    }
+
+    /// Most recent state that follows a node, going through its most recent children.
+    fn latest_state(&self, node: usize) -> usize {
+        let mut current = node;
+        while let Some(child) = self.children(current).last() {
+            current = *child;
+        }
+        current
+    }
+}
+
+impl EditorData {
+    /// Go to a state of the undo tree, undoing and redoing the changes on the way.
+    pub fn go_to_undo_state(&mut self, node: usize) -> bool {
+        let current_change = self.history.current_change();
+        if self.undo_tree.node_at(current_change) == node {
+            return false;
+        }
+
+        let (position, replay) = self.undo_tree.route(node);
+        let mut cursor = None;
+        while self.history.current_change() > position && self.history.can_undo() {
+            cursor = self.history.undo(&mut self.rope.borrow_mut()).or(cursor);
+        }
+        while self.history.current_change() < position && self.history.can_redo() {
+            cursor = self.history.redo(&mut self.rope.borrow_mut()).or(cursor);
+        }
+
+        // The changes of another branch are made again
+        if !replay.is_empty() {
+            self.undo_tree.branch.truncate(position);
+        }
+        for node in replay {
+            let Some(change) = self.undo_tree.node(node).change.clone() else {
+                continue;
+            };
+            cursor = Some(match &change {
+                HistoryChange::InsertChar { idx, len, .. }
+                | HistoryChange::InsertText { idx, len, .. } => idx + len,
+                HistoryChange::Remove { idx, .. } => *idx,
+            });
+            apply_change(&mut self.rope.borrow_mut(), &change);
+            self.history.push_change(change);
+            self.undo_tree.branch.push(node);
+        }
+        self.history_groups = self.undo_tree.groups();
+
+        self.clear_extra_cursors();
+        self.clear_selection();
+        if let Some(cursor) = cursor {
+            let cursor = cursor.min(self.len_utf16_cu());
+            self.cursor = TextCursor::new(cursor);
+        }
+        self.run_parser();
+        self.request_scroll_to_cursor();
+        true
+    }
+
+    /// Go to the most recent state of the next or previous branch of the closest fork.
+    pub fn switch_undo_branch(&mut self, forward: bool) -> bool {
+        let tree = &self.undo_tree;
+        let mut child = tree.node_at(self.history.current_change());
+        while child != ROOT {
+            let parent = tree.nodes[child].parent;
+            let siblings = tree.children(parent);
+            if siblings.len() > 1 {
+                let idx = siblings.iter().position(|node| *node == child).unwrap_or(0);
+                let sibling = if forward {
+                    siblings[(idx + 1) % siblings.len()]
+                } else {
+                    siblings[(idx + siblings.len() - 1) % siblings.len()]
+                };
+                let state = tree.latest_state(sibling);
+                return self.go_to_undo_state(state);
+            }
+            child = parent;
+        }
+        false
+    }
+
+    /// Go to the state made right after or before the current one, in any branch.
+    pub fn step_undo_state(&mut self, forward: bool) -> bool {
+        let current = self.undo_tree.node_at(self.history.current_change());
+        let state = if forward {
+            self.undo_tree.states().find(|node| *node > current)
+        } else {
+            self.undo_tree.states().rev().find(|node| *node < current)
+        };
+        state.is_some_and(|state| self.go_to_undo_state(state))
+    }
+
+    /// Go to the most recent state made before or after some time from the current one.
+    pub fn travel_in_time(&mut self, duration: Duration, forward: bool) -> bool {
+        let current = self.undo_tree.node_at(self.history.current_change());
+        let current_time = self.undo_tree.node(current).time;
+        let state = if forward {
+            let Some(time) = current_time.checked_add(duration) else {
+                return false;
+            };
+            self.undo_tree
+                .states()
+                .rev()
+                .find(|node| self.undo_tree.node(*node).time <= time)
+                .filter(|node| *node > current)
+        } else {
+            let time = current_time.checked_sub(duration).unwrap_or(current_time);
+            self.undo_tree
+                .states()
+                .rev()
+                .find(|node| *node < current && self.undo_tree.node(*node).time <= time)
+                .or(Some(ROOT))
+        };
+        state.is_some_and(|state| self.go_to_undo_state(state))
+    }
+}
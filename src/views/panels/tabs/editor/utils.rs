@@ -6,14 +6,21 @@ use crate::{
     state::{AppState, PanelTab, TabId},
 };
 
-use super::{EditorTab, SharedRope};
+use super::EditorTab;
+
+/// Path, content as it gets saved, history change of that content and transport of an editor.
+pub type EditorTabData = (Option<PathBuf>, Vec<u8>, usize, FSTransport);
 
 pub trait AppStateEditorUtils {
     fn editor_tab(&self, tab_id: TabId) -> &EditorTab;
 
     fn editor_tab_mut(&mut self, tab_id: TabId) -> &mut EditorTab;
 
-    fn editor_tab_data(&self, tab_id: TabId) -> Option<(Option<PathBuf>, SharedRope, FSTransport)>;
+    fn try_editor_tab(&self, tab_id: TabId) -> Option<&EditorTab>;
+
+    fn try_editor_tab_mut(&mut self, tab_id: TabId) -> Option<&mut EditorTab>;
+
+    fn editor_tab_data(&self, tab_id: TabId) -> Option<EditorTabData>;
 
     fn editor_tab_lsp(&self, tab_id: TabId) -> Option<LSPClient>;
 }
@@ -31,11 +38,20 @@ impl AppStateEditorUtils for AppState {
             .unwrap()
     }
 
-    fn editor_tab_data(&self, tab_id: TabId) -> Option<(Option<PathBuf>, SharedRope, FSTransport)> {
+    fn try_editor_tab(&self, tab_id: TabId) -> Option<&EditorTab> {
+        self.tabs.get(&tab_id)?.as_text_editor()
+    }
+
+    fn try_editor_tab_mut(&mut self, tab_id: TabId) -> Option<&mut EditorTab> {
+        self.tabs.get_mut(&tab_id)?.as_text_editor_mut()
+    }
+
+    fn editor_tab_data(&self, tab_id: TabId) -> Option<EditorTabData> {
         let tab = self.tabs.get(&tab_id)?.as_text_editor()?;
         Some((
             tab.editor.path().cloned(),
-            tab.editor.rope.clone(),
+            tab.editor.encoded_content(),
+            tab.editor.history.current_change(),
             tab.editor.transport.clone(),
         ))
     }
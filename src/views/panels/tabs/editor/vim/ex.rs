@@ -0,0 +1,270 @@
+use std::time::Duration;
+
+use grep::{
+    matcher::{Captures, Matcher},
+    regex::{RegexMatcher, RegexMatcherBuilder},
+};
+
+use crate::global_defaults::CloseTabCommand;
+
+use super::super::commands::{SaveAndCloseFileCommand, SaveFileCommand};
+use super::motions::{first_non_blank, last_line};
+use super::{EditorData, VimState};
+
+impl EditorData {
+    /// Run a command typed after `:`, returns whether it moved through the history.
+    /// Anything that is not a known ex command is run as the command with that id.
+    pub(super) fn run_ex_command(&mut self, vim: &mut VimState, command: &str) -> bool {
+        let (lines, command) = self.parse_ex_range(vim, command.trim());
+        let command = command.trim();
+
+        if let Some((travel, forward)) = parse_history_travel(command) {
+            match travel {
+                HistoryTravel::Steps(steps) => {
+                    for _ in 0..steps {
+                        self.step_undo_state(forward);
+                    }
+                }
+                HistoryTravel::Time(duration) => {
+                    self.travel_in_time(duration, forward);
+                }
+            }
+            return true;
+        }
+
+        match command {
+            "w" => vim.pending_commands.push(SaveFileCommand::id().to_string()),
+            "q" if self.is_edited() => {
+                self.message = Some("No write since last change (add ! to override)".to_string());
+            }
+            "q" | "q!" => vim.pending_commands.push(CloseTabCommand::id().to_string()),
+            // `:x` only writes when there are changes
+            "x" if !self.is_edited() => {
+                vim.pending_commands.push(CloseTabCommand::id().to_string())
+            }
+            // The tab is closed once the file is written, so nothing is lost if it fails
+            "wq" | "x" => vim
+                .pending_commands
+                .push(SaveAndCloseFileCommand::id().to_string()),
+            // Only a line number, e.g `:42`
+            "" => {
+                if let Some((_, line)) = lines {
+                    self.move_to_line(line);
+                }
+            }
+            _ => {
+                let substitution = command
+                    .strip_prefix('s')
+                    .filter(|args| args.starts_with(|ch: char| ch.is_ascii_punctuation()));
+                if let Some(args) = substitution {
+                    let lines = lines.unwrap_or((self.cursor_row(), self.cursor_row()));
+                    self.substitute(lines, args);
+                } else {
+                    vim.pending_commands.push(command.to_string());
+                }
+            }
+        }
+        false
+    }
+
+    /// Lines of the range at the start of an ex command, e.g `%` or `'<,'>`.
+    fn parse_ex_range<'a>(
+        &self,
+        vim: &VimState,
+        command: &'a str,
+    ) -> (Option<(usize, usize)>, &'a str) {
+        let last_line = last_line(&self.rope.borrow());
+        if let Some(rest) = command.strip_prefix('%') {
+            return (Some((0, last_line)), rest);
+        }
+        if let Some(rest) = command.strip_prefix("'<,'>") {
+            return (vim.last_visual_lines, rest);
+        }
+
+        let address = |address: &str| match address {
+            "." => Some(self.cursor_row()),
+            "$" => Some(last_line),
+            number => number
+                .parse::<usize>()
+                .ok()
+                .map(|number| number.saturating_sub(1).min(last_line)),
+        };
+
+        let len = command
+            .find(|ch: char| !ch.is_ascii_digit() && !matches!(ch, '.' | '$' | ','))
+            .unwrap_or(command.len());
+        let (range, rest) = command.split_at(len);
+        if range.is_empty() {
+            return (None, rest);
+        }
+
+        let lines = match range.split_once(',') {
+            Some((first, last)) => address(first).zip(address(last)),
+            None => address(range).map(|line| (line, line)),
+        };
+        (lines, rest)
+    }
+
+    fn move_to_line(&mut self, line: usize) {
+        let pos = first_non_blank(&self.rope.borrow(), line);
+        self.set_vim_pos(pos);
+    }
+
+    /// Replace the matches of a pattern in some lines, e.g with `/foo/bar/g`.
+    /// Patterns use the regex syntax, and `\1` or `&` in the replacement are the captured groups.
+    fn substitute(&mut self, (first, last): (usize, usize), args: &str) {
+        let mut chars = args.chars();
+        let Some(delimiter) = chars.next() else {
+            return;
+        };
+        let parts = split_unescaped(chars.as_str(), delimiter);
+        let pattern = parts.first().map(String::as_str).unwrap_or_default();
+        let replacement =
+            replacement_template(parts.get(1).map(String::as_str).unwrap_or_default());
+        let flags = parts.get(2).map(String::as_str).unwrap_or_default();
+
+        let Ok(matcher) = RegexMatcherBuilder::new()
+            .case_insensitive(flags.contains('i'))
+            .build(pattern)
+        else {
+            return;
+        };
+        let all = flags.contains('g');
+
+        let (first, last) = (first.min(last), first.max(last));
+        let mut last_replaced = None;
+        // From the bottom so the positions of the pending lines don't change
+        for line in (first..=last).rev() {
+            let text = self.line_text(line);
+            if let Some(replaced) = replace_in_line(&matcher, &text, &replacement, all) {
+                if replaced != text {
+                    self.replace_lines(line..=line, &[replaced]);
+                }
+                last_replaced.get_or_insert(line);
+            }
+        }
+
+        if let Some(line) = last_replaced {
+            self.move_to_line(line);
+        }
+    }
+}
+
+/// How far `:earlier` and `:later` go through the states of the undo tree.
+enum HistoryTravel {
+    Steps(usize),
+    Time(Duration),
+}
+
+/// Parse `:earlier` and `:later`, e.g `earlier 10`, `later 30s` or `earlier 5m`.
+fn parse_history_travel(command: &str) -> Option<(HistoryTravel, bool)> {
+    let (args, forward) = match command.split_once(' ') {
+        Some(("earlier" | "ea", args)) => (args.trim(), false),
+        Some(("later" | "lat", args)) => (args.trim(), true),
+        None if matches!(command, "earlier" | "ea") => ("", false),
+        None if matches!(command, "later" | "lat") => ("", true),
+        _ => return None,
+    };
+    if args.is_empty() {
+        return Some((HistoryTravel::Steps(1), forward));
+    }
+
+    let (number, unit) = args.split_at(
+        args.find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(args.len()),
+    );
+    let number = number.parse::<u64>().ok()?;
+    let unit_secs = match unit {
+        "" => return Some((HistoryTravel::Steps(number as usize), forward)),
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        _ => return None,
+    };
+    let secs = number.checked_mul(unit_secs)?;
+    Some((HistoryTravel::Time(Duration::from_secs(secs)), forward))
+}
+
+/// Split the arguments of a substitution by its delimiter, which can be escaped with `\`.
+fn split_unescaped(text: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        let part = parts.last_mut().unwrap();
+        match ch {
+            '\\' => match chars.next() {
+                Some(next) if next == delimiter => part.push(next),
+                Some(next) => {
+                    part.push(ch);
+                    part.push(next);
+                }
+                None => part.push(ch),
+            },
+            _ if ch == delimiter => parts.push(String::new()),
+            _ => part.push(ch),
+        }
+    }
+    parts
+}
+
+/// Turn a Vim replacement, where `&` and `\1` are the captured groups,
+/// into the `${0}` and `${1}` syntax used to interpolate the captures.
+fn replacement_template(replacement: &str) -> String {
+    let mut template = String::new();
+    let mut chars = replacement.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next() {
+                Some(digit) if digit.is_ascii_digit() => {
+                    template.push_str(&format!("${{{digit}}}"));
+                }
+                Some('n') => template.push('\n'),
+                Some('t') => template.push('\t'),
+                Some(next) => template.push(next),
+                None => template.push(ch),
+            },
+            '&' => template.push_str("${0}"),
+            '$' => template.push_str("$$"),
+            _ => template.push(ch),
+        }
+    }
+    template
+}
+
+/// The line with the first match, or all of them, replaced.
+/// Returns `None` when nothing matched.
+fn replace_in_line(
+    matcher: &RegexMatcher,
+    text: &str,
+    replacement: &str,
+    all: bool,
+) -> Option<String> {
+    let mut captures = matcher.new_captures().ok()?;
+    let mut replaced = Vec::new();
+    let mut matched = false;
+
+    matcher
+        .replace_with_captures(
+            text.as_bytes(),
+            &mut captures,
+            &mut replaced,
+            |captures, dst| {
+                matched = true;
+                captures.interpolate(
+                    |name| matcher.capture_index(name),
+                    text.as_bytes(),
+                    replacement.as_bytes(),
+                    dst,
+                );
+                all
+            },
+        )
+        .ok()?;
+
+    if matched {
+        String::from_utf8(replaced).ok()
+    } else {
+        None
+    }
+}
@@ -0,0 +1,1211 @@
+mod ex;
+mod motions;
+
+use std::{collections::HashMap, fmt::Display, mem};
+
+use freya::events::{Code, Key, KeyboardData, Modifiers};
+use freya::hooks::{TextCursor, TextEditor};
+
+use crate::indentation::leading_whitespace;
+
+use self::motions::*;
+use super::EditorData;
+
+/// `Ctrl R`, as the control char it produces in terminals.
+const REDO: char = '\u{12}';
+
+/// Commands that are not motions nor operators.
+const COMMANDS: &str = "xXsSDCYpPiIaAoOJuUvV:~.dcy<>\u{12}";
+
+/// Biggest count accepted, so typos don't freeze the editor.
+const MAX_COUNT: usize = 9999;
+
+/// Modes of the Vim emulation.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+    /// Typing an ex command after `:`.
+    CommandLine,
+}
+
+impl Display for VimMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Normal => "NORMAL",
+            Self::Insert => "INSERT",
+            Self::Visual => "VISUAL",
+            Self::VisualLine => "VISUAL LINE",
+            Self::CommandLine => "COMMAND",
+        })
+    }
+}
+
+/// Text stored in a register.
+#[derive(Clone, Debug, Default)]
+struct Register {
+    text: String,
+    /// Whole lines, which get put below or above the line of the cursor.
+    linewise: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Outdent,
+}
+
+impl Operator {
+    fn from_key(key: char) -> Option<Self> {
+        match key {
+            'd' => Some(Self::Delete),
+            'c' => Some(Self::Change),
+            'y' => Some(Self::Yank),
+            '>' => Some(Self::Indent),
+            '<' => Some(Self::Outdent),
+            _ => None,
+        }
+    }
+}
+
+/// What an operator works on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Target {
+    Motion(Motion),
+    TextObject(TextObject),
+    /// Typing the operator twice, e.g `dd`.
+    Lines,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Action {
+    Move(Motion),
+    Operate(Operator, Target),
+    /// Select a text object in visual mode.
+    Select(TextObject),
+    /// Any other command, with the char typed after it, e.g the new char of `r`.
+    Command(char, Option<char>),
+}
+
+struct VimCommand {
+    register: Option<char>,
+    count: Option<usize>,
+    action: Action,
+}
+
+impl VimCommand {
+    /// Whether it changes the text, so it can be repeated with `.`.
+    fn is_change(&self) -> bool {
+        match self.action {
+            Action::Operate(operator, _) => operator != Operator::Yank,
+            Action::Command(key, _) => "xXsSDCpPJr~oOiIaA".contains(key),
+            _ => false,
+        }
+    }
+}
+
+enum Parsed<T> {
+    Done(T),
+    /// More keys are needed.
+    Pending,
+    Invalid,
+}
+
+/// State of the Vim emulation of an editor.
+#[derive(Default)]
+pub struct VimState {
+    pub(crate) mode: VimMode,
+    /// Keys of the command being typed, e.g `2d`.
+    keys: Vec<char>,
+    /// Text typed after `:`.
+    command_line: String,
+    registers: HashMap<char, Register>,
+    /// Where the visual selection started, as a char index.
+    visual_anchor: usize,
+    /// Lines of the last visual selection, used by the `'<,'>` range.
+    last_visual_lines: Option<(usize, usize)>,
+    /// Column kept while moving up and down through shorter lines.
+    column: Option<usize>,
+    last_find: Option<FindChar>,
+    /// Keys of the last change, repeated with `.`.
+    last_change: Vec<Key>,
+    /// Keys of the change being made, until going back to normal mode.
+    change: Option<Vec<Key>>,
+    /// History change where insert mode started, so everything typed gets undone at once.
+    insert_start: Option<usize>,
+    /// Ids of the commands requested by ex commands, like saving with `:w`.
+    pending_commands: Vec<String>,
+}
+
+impl VimState {
+    fn is_visual(&self) -> bool {
+        matches!(self.mode, VimMode::Visual | VimMode::VisualLine)
+    }
+}
+
+impl Display for VimState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.mode {
+            VimMode::CommandLine => write!(f, ":{}", self.command_line),
+            mode if self.keys.is_empty() => write!(f, "{mode}"),
+            mode => write!(f, "{mode} {}", self.keys.iter().collect::<String>()),
+        }
+    }
+}
+
+/// Char of a key in normal and visual modes.
+/// The arrows and a few other keys work like their Vim counterparts.
+fn key_char(key: &Key) -> Option<char> {
+    match key {
+        Key::Character(text) => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Some(ch),
+                _ => None,
+            }
+        }
+        Key::ArrowLeft | Key::Backspace => Some('h'),
+        Key::ArrowRight => Some('l'),
+        Key::ArrowUp => Some('k'),
+        Key::ArrowDown | Key::Enter => Some('j'),
+        Key::Home => Some('0'),
+        Key::End => Some('$'),
+        Key::Delete => Some('x'),
+        _ => None,
+    }
+}
+
+fn parse_count(keys: &[char]) -> (Option<usize>, &[char]) {
+    // A leading `0` is the motion to the start of the line
+    let len = keys
+        .iter()
+        .enumerate()
+        .take_while(|(idx, ch)| ch.is_ascii_digit() && (*idx > 0 || **ch != '0'))
+        .count();
+    if len == 0 {
+        return (None, keys);
+    }
+
+    let count = keys[..len]
+        .iter()
+        .collect::<String>()
+        .parse::<usize>()
+        .map_or(MAX_COUNT, |count| count.min(MAX_COUNT));
+    (Some(count), &keys[len..])
+}
+
+fn parse_motion(keys: &[char]) -> Parsed<Motion> {
+    let motion = match keys {
+        [] | ['g'] | ['f' | 'F' | 't' | 'T'] => return Parsed::Pending,
+        ['h'] => Motion::Left,
+        ['l' | ' '] => Motion::Right,
+        ['k'] => Motion::Up,
+        ['j'] => Motion::Down,
+        ['w'] => Motion::NextWordStart { big: false },
+        ['W'] => Motion::NextWordStart { big: true },
+        ['b'] => Motion::PreviousWordStart { big: false },
+        ['B'] => Motion::PreviousWordStart { big: true },
+        ['e'] => Motion::WordEnd { big: false },
+        ['E'] => Motion::WordEnd { big: true },
+        ['0'] => Motion::LineStart,
+        ['^'] => Motion::FirstNonBlank,
+        ['$'] => Motion::LineEnd,
+        ['g', 'g'] => Motion::FirstLine,
+        ['G'] => Motion::LastLine,
+        [key @ ('f' | 'F' | 't' | 'T'), ch] => Motion::FindChar(FindChar {
+            ch: *ch,
+            forward: key.is_lowercase(),
+            till: matches!(key, 't' | 'T'),
+        }),
+        [';'] => Motion::RepeatFind { reverse: false },
+        [','] => Motion::RepeatFind { reverse: true },
+        ['%'] => Motion::MatchingBracket,
+        ['}'] => Motion::ParagraphForward,
+        ['{'] => Motion::ParagraphBackward,
+        _ => return Parsed::Invalid,
+    };
+    Parsed::Done(motion)
+}
+
+/// Parse the keys of a command, e.g `"a2dw`.
+fn parse_command(keys: &[char], visual: bool) -> Parsed<VimCommand> {
+    let (register, keys) = match keys {
+        ['"'] => return Parsed::Pending,
+        ['"', register, rest @ ..] => (Some(*register), rest),
+        _ => (None, keys),
+    };
+    let (mut count, keys) = parse_count(keys);
+    let Some((&key, rest)) = keys.split_first() else {
+        return Parsed::Pending;
+    };
+
+    let action = match Operator::from_key(key) {
+        Some(operator) if !visual => {
+            // Both counts multiply, e.g `2d3w` deletes six words
+            let (motion_count, rest) = parse_count(rest);
+            if motion_count.is_some() {
+                count = Some((count.unwrap_or(1) * motion_count.unwrap_or(1)).min(MAX_COUNT));
+            }
+
+            let target = match rest {
+                [] | ['i' | 'a'] => return Parsed::Pending,
+                [same] if *same == key => Target::Lines,
+                [kind @ ('i' | 'a'), ch] => match TextObject::parse(*ch, *kind == 'a') {
+                    Some(object) => Target::TextObject(object),
+                    None => return Parsed::Invalid,
+                },
+                _ => match parse_motion(rest) {
+                    Parsed::Done(motion) => Target::Motion(motion),
+                    Parsed::Pending => return Parsed::Pending,
+                    Parsed::Invalid => return Parsed::Invalid,
+                },
+            };
+            Action::Operate(operator, target)
+        }
+        _ if visual && matches!(key, 'i' | 'a') => match rest {
+            [] => return Parsed::Pending,
+            [ch] => match TextObject::parse(*ch, key == 'a') {
+                Some(object) => Action::Select(object),
+                None => return Parsed::Invalid,
+            },
+            _ => return Parsed::Invalid,
+        },
+        _ => match parse_motion(keys) {
+            Parsed::Done(motion) => Action::Move(motion),
+            Parsed::Pending => return Parsed::Pending,
+            Parsed::Invalid => match (key, rest) {
+                ('r', []) => return Parsed::Pending,
+                ('r', [ch]) => Action::Command('r', Some(*ch)),
+                ('g', [ch @ ('-' | '+')]) => Action::Command('g', Some(*ch)),
+                (_, []) if COMMANDS.contains(key) => Action::Command(key, None),
+                _ => return Parsed::Invalid,
+            },
+        },
+    };
+
+    Parsed::Done(VimCommand {
+        register,
+        count,
+        action,
+    })
+}
+
+fn toggle_case(text: &str) -> String {
+    text.chars()
+        .flat_map(|ch| {
+            if ch.is_uppercase() {
+                ch.to_lowercase().collect::<Vec<char>>()
+            } else {
+                ch.to_uppercase().collect::<Vec<char>>()
+            }
+        })
+        .collect()
+}
+
+impl EditorData {
+    /// Turn the Vim emulation on or off.
+    pub fn set_vim_mode(&mut self, enabled: bool) {
+        match (enabled, self.vim.is_some()) {
+            (true, false) => {
+                self.vim = Some(VimState::default());
+                self.clear_extra_cursors();
+                self.clear_selection();
+                self.clamp_vim_cursor();
+            }
+            (false, true) => self.vim = None,
+            _ => {}
+        }
+    }
+
+    /// Process a key with the Vim emulation, returns whether it was handled.
+    /// The keys typed in insert mode are left to the editor.
+    pub fn process_vim_key(&mut self, data: &KeyboardData) -> bool {
+        let Some(mut vim) = self.vim.take() else {
+            return false;
+        };
+        let handled = self.process_vim_key_with(&mut vim, data);
+        self.vim = Some(vim);
+        handled
+    }
+
+    /// Go back to normal mode, or cancel the command being typed.
+    /// Returns `false` when there was nothing to cancel.
+    pub fn vim_escape(&mut self) -> bool {
+        let Some(mut vim) = self.vim.take() else {
+            return false;
+        };
+        let escaped = match vim.mode {
+            VimMode::Normal => {
+                let had_keys = !vim.keys.is_empty();
+                vim.keys.clear();
+                had_keys
+            }
+            VimMode::Insert => {
+                self.exit_insert_mode(&mut vim);
+                true
+            }
+            VimMode::Visual | VimMode::VisualLine => {
+                self.exit_visual_mode(&mut vim);
+                true
+            }
+            VimMode::CommandLine => {
+                vim.command_line.clear();
+                vim.mode = VimMode::Normal;
+                true
+            }
+        };
+        self.vim = Some(vim);
+        escaped
+    }
+
+    pub fn has_vim_commands(&self) -> bool {
+        self.vim
+            .as_ref()
+            .is_some_and(|vim| !vim.pending_commands.is_empty())
+    }
+
+    /// Take the ids of the commands requested by ex commands.
+    pub fn take_vim_commands(&mut self) -> Vec<String> {
+        self.vim
+            .as_mut()
+            .map(|vim| mem::take(&mut vim.pending_commands))
+            .unwrap_or_default()
+    }
+
+    fn process_vim_key_with(&mut self, vim: &mut VimState, data: &KeyboardData) -> bool {
+        let has_modifiers = data
+            .modifiers
+            .intersects(Modifiers::CONTROL | Modifiers::ALT | Modifiers::META);
+        let is_redo = data.modifiers == Modifiers::CONTROL && data.code == Code::KeyR;
+
+        if vim.mode == VimMode::Insert {
+            // What gets typed is part of the change repeated with `.`
+            if let Some(change) = vim.change.as_mut().filter(|_| !has_modifiers) {
+                change.push(data.key.clone());
+            }
+            return false;
+        }
+        // `Esc` is handled by the global shortcuts
+        if (has_modifiers && !is_redo) || data.key == Key::Escape {
+            return false;
+        }
+
+        let previous_row = self.cursor_row();
+        let previous_lines_len = self.len_lines();
+        let first_change = self.history.current_change();
+
+        let moved_in_history = if vim.mode == VimMode::CommandLine {
+            self.process_command_line_key(vim, &data.key)
+        } else {
+            let key = if is_redo {
+                Some(REDO)
+            } else {
+                key_char(&data.key)
+            };
+            key.is_some_and(|key| self.process_vim_char(vim, key))
+        };
+
+        match vim.mode {
+            VimMode::Normal => {
+                self.clear_selection();
+                self.clamp_vim_cursor();
+            }
+            VimMode::Visual | VimMode::VisualLine => {
+                self.clamp_vim_cursor();
+                self.update_visual_selection(vim);
+            }
+            VimMode::Insert => {
+                vim.insert_start.get_or_insert(first_change);
+            }
+            VimMode::CommandLine => {}
+        }
+
+        let text_changed = self.history.current_change() != first_change;
+        if text_changed {
+            let delta = self.len_lines() as isize - previous_lines_len as isize;
+            self.folding
+                .shift_lines(previous_row.min(self.cursor_row()), delta);
+            // Undoing and redoing moves through the existing groups,
+            // and insert mode is grouped when leaving it
+            if !moved_in_history && vim.mode != VimMode::Insert {
+                self.group_history(first_change);
+            }
+            self.run_parser();
+        }
+        self.skip_folded_lines(previous_row, text_changed);
+        if self.cursor_row() != previous_row {
+            self.request_scroll_to_cursor();
+        }
+
+        true
+    }
+
+    /// Process a key of normal and visual modes, returns whether it undid or redid changes.
+    fn process_vim_char(&mut self, vim: &mut VimState, key: char) -> bool {
+        vim.keys.push(key);
+        let visual = vim.is_visual();
+        let command = match parse_command(&vim.keys, visual) {
+            Parsed::Done(command) => command,
+            Parsed::Pending => return false,
+            Parsed::Invalid => {
+                vim.keys.clear();
+                return false;
+            }
+        };
+
+        let keys = mem::take(&mut vim.keys);
+        if !visual && command.is_change() {
+            vim.change = Some(
+                keys.into_iter()
+                    .map(|key| Key::Character(key.to_string()))
+                    .collect(),
+            );
+        }
+
+        let moved_in_history = self.run_vim_command(vim, command);
+
+        // Changes that don't go through insert mode are complete already
+        if vim.mode != VimMode::Insert {
+            if let Some(change) = vim.change.take() {
+                vim.last_change = change;
+            }
+        }
+        moved_in_history
+    }
+
+    fn run_vim_command(
+        &mut self,
+        vim: &mut VimState,
+        VimCommand {
+            register,
+            count,
+            action,
+        }: VimCommand,
+    ) -> bool {
+        let register = register.unwrap_or('"');
+        match action {
+            Action::Move(motion) => {
+                if let Some(target) = self.vim_motion_target(vim, motion, count) {
+                    self.set_vim_pos(target);
+                }
+            }
+            Action::Operate(operator, target) => {
+                if let Some((start, end, linewise)) =
+                    self.vim_target_range(vim, operator, target, count)
+                {
+                    self.vim_operate(vim, operator, register, start, end, linewise);
+                }
+            }
+            Action::Select(object) => {
+                let range = text_object_range(&self.rope.borrow(), self.vim_pos(), object);
+                if let Some((start, end)) = range.filter(|(start, end)| start < end) {
+                    vim.visual_anchor = start;
+                    self.set_vim_pos(end - 1);
+                }
+            }
+            Action::Command(key, _) if vim.is_visual() => {
+                self.run_visual_command(vim, key, register);
+            }
+            Action::Command(key, arg) => {
+                return self.run_normal_command(vim, key, arg, register, count);
+            }
+        }
+        false
+    }
+
+    /// Run a command of normal mode, returns whether it undid or redid changes.
+    fn run_normal_command(
+        &mut self,
+        vim: &mut VimState,
+        key: char,
+        arg: Option<char>,
+        register: char,
+        count: Option<usize>,
+    ) -> bool {
+        let count_or_one = count.unwrap_or(1);
+        let pos = self.vim_pos();
+        let (line, line_start, line_end) = {
+            let rope = self.rope.borrow();
+            let line = rope.char_to_line(pos);
+            (line, line_start(&rope, line), line_end(&rope, line))
+        };
+        let operate = |editor: &mut Self, vim: &mut VimState, operator, target| {
+            editor.run_vim_command(
+                vim,
+                VimCommand {
+                    register: Some(register),
+                    count,
+                    action: Action::Operate(operator, target),
+                },
+            )
+        };
+
+        match key {
+            'x' | 's' => {
+                let end = (pos + count_or_one).min(line_end);
+                let operator = if key == 'x' {
+                    Operator::Delete
+                } else {
+                    Operator::Change
+                };
+                self.vim_operate(vim, operator, register, pos, end, false);
+            }
+            'X' => {
+                let start = pos.saturating_sub(count_or_one).max(line_start);
+                self.vim_operate(vim, Operator::Delete, register, start, pos, false);
+            }
+            'S' => {
+                operate(self, vim, Operator::Change, Target::Lines);
+            }
+            'D' => {
+                operate(self, vim, Operator::Delete, Target::Motion(Motion::LineEnd));
+            }
+            'C' => {
+                operate(self, vim, Operator::Change, Target::Motion(Motion::LineEnd));
+            }
+            'Y' => {
+                operate(self, vim, Operator::Yank, Target::Lines);
+            }
+            'p' | 'P' => self.vim_put(vim, register, count_or_one, key == 'P'),
+            'i' | 'a' | 'I' | 'A' => {
+                let insert_pos = match key {
+                    'a' => (pos + 1).min(line_end),
+                    'I' => first_non_blank(&self.rope.borrow(), line),
+                    'A' => line_end,
+                    _ => pos,
+                };
+                self.set_vim_pos(insert_pos);
+                vim.mode = VimMode::Insert;
+            }
+            'o' | 'O' => {
+                self.insert_line(key == 'O');
+                vim.mode = VimMode::Insert;
+            }
+            'J' => {
+                for _ in 1..count_or_one.max(2) {
+                    self.join_lines();
+                }
+            }
+            'u' | REDO => {
+                for _ in 0..count_or_one {
+                    let idx = if key == 'u' { self.undo() } else { self.redo() };
+                    if let Some(idx) = idx {
+                        self.cursor = TextCursor::new(idx);
+                    }
+                }
+                return true;
+            }
+            '.' => {
+                let keys = vim.last_change.clone();
+                for _ in 0..count_or_one {
+                    for key in &keys {
+                        self.replay_vim_key(vim, key);
+                    }
+                    if vim.mode == VimMode::Insert {
+                        self.exit_insert_mode(vim);
+                    }
+                }
+                vim.last_change = keys;
+            }
+            'v' | 'V' => {
+                vim.visual_anchor = pos;
+                vim.mode = if key == 'v' {
+                    VimMode::Visual
+                } else {
+                    VimMode::VisualLine
+                };
+            }
+            ':' => vim.mode = VimMode::CommandLine,
+            '~' => {
+                let end = (pos + count_or_one).min(line_end);
+                if pos < end {
+                    let text = toggle_case(&self.chars_text(pos, end));
+                    self.replace_chars(pos, end, &text);
+                    self.set_vim_pos(end);
+                }
+            }
+            // Through the states of the undo tree in the order they were made
+            'g' => {
+                for _ in 0..count_or_one {
+                    self.step_undo_state(arg == Some('+'));
+                }
+                return true;
+            }
+            'r' => {
+                let end = pos + count_or_one;
+                if let Some(ch) = arg.filter(|_| end <= line_end) {
+                    self.replace_chars(pos, end, &ch.to_string().repeat(count_or_one));
+                    self.set_vim_pos(end - 1);
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn run_visual_command(&mut self, vim: &mut VimState, key: char, register: char) {
+        let (start, end) = self.visual_range(vim);
+        let linewise = vim.mode == VimMode::VisualLine;
+        let (first_line, last_line) = {
+            let rope = self.rope.borrow();
+            (
+                rope.char_to_line(start),
+                rope.char_to_line(end.saturating_sub(1).max(start)),
+            )
+        };
+        let lines_start = self.rope.borrow().line_to_char(first_line);
+
+        match key {
+            'v' | 'V' => {
+                let mode = if key == 'v' {
+                    VimMode::Visual
+                } else {
+                    VimMode::VisualLine
+                };
+                if vim.mode == mode {
+                    self.exit_visual_mode(vim);
+                } else {
+                    vim.mode = mode;
+                }
+            }
+            // Go to the other end of the selection
+            'o' => {
+                let pos = self.vim_pos();
+                self.set_vim_pos(vim.visual_anchor);
+                vim.visual_anchor = pos;
+            }
+            'd' | 'x' | 'c' | 's' | 'y' => {
+                let operator = match key {
+                    'd' | 'x' => Operator::Delete,
+                    'c' | 's' => Operator::Change,
+                    _ => Operator::Yank,
+                };
+                self.exit_visual_mode(vim);
+                if linewise {
+                    self.vim_operate_lines(vim, operator, register, first_line, last_line);
+                } else {
+                    self.vim_operate(vim, operator, register, start, end, false);
+                }
+            }
+            'D' | 'X' | 'C' | 'S' | 'Y' | '>' | '<' => {
+                let operator = match key {
+                    'D' | 'X' => Operator::Delete,
+                    'C' | 'S' => Operator::Change,
+                    'Y' => Operator::Yank,
+                    '>' => Operator::Indent,
+                    _ => Operator::Outdent,
+                };
+                self.exit_visual_mode(vim);
+                self.vim_operate_lines(vim, operator, register, first_line, last_line);
+            }
+            '~' | 'u' | 'U' => {
+                let (start, end) = if linewise {
+                    let rope = self.rope.borrow();
+                    (lines_start, line_end(&rope, last_line))
+                } else {
+                    (start, end)
+                };
+                let text = self.chars_text(start, end);
+                let text = match key {
+                    'u' => text.to_lowercase(),
+                    'U' => text.to_uppercase(),
+                    _ => toggle_case(&text),
+                };
+                self.exit_visual_mode(vim);
+                self.replace_chars(start, end, &text);
+                self.set_vim_pos(start);
+            }
+            'J' => {
+                // The visual selection is the selection of the editor
+                self.join_lines();
+                self.exit_visual_mode(vim);
+            }
+            'p' | 'P' => {
+                let Some(replacement) = self.load_register(vim, register) else {
+                    return;
+                };
+                self.exit_visual_mode(vim);
+                if linewise {
+                    self.vim_operate_lines(vim, Operator::Delete, register, first_line, last_line);
+                    let (line, text) = (first_line.min(self.len_lines() - 1), replacement.text);
+                    let line_start = self.rope.borrow().line_to_char(line);
+                    self.insert_chars(&format!("{text}{}", self.line_break()), line_start);
+                    self.set_vim_pos(line_start);
+                } else {
+                    self.vim_operate(vim, Operator::Delete, register, start, end, false);
+                    self.insert_chars(&replacement.text, start);
+                    self.set_vim_pos(start);
+                }
+            }
+            ':' => {
+                self.exit_visual_mode(vim);
+                vim.command_line = "'<,'>".to_string();
+                vim.mode = VimMode::CommandLine;
+            }
+            _ => {}
+        }
+    }
+
+    /// Where a motion takes the cursor, as a char index.
+    fn vim_motion_target(
+        &self,
+        vim: &mut VimState,
+        motion: Motion,
+        count: Option<usize>,
+    ) -> Option<usize> {
+        if motion == Motion::MatchingBracket {
+            vim.column = None;
+            return self.matching_brackets().map(|(_, matching)| matching);
+        }
+
+        let count_or_one = count.unwrap_or(1);
+        let pos = self.vim_pos();
+        let rope = self.rope.borrow();
+        let line = rope.char_to_line(pos);
+        let last_line = last_line(&rope);
+
+        let target = match motion {
+            Motion::Left => pos
+                .saturating_sub(count_or_one)
+                .max(line_start(&rope, line)),
+            Motion::Right => (pos + count_or_one).min(line_end(&rope, line)),
+            Motion::Up | Motion::Down => {
+                let target_line = if motion == Motion::Up {
+                    line.saturating_sub(count_or_one)
+                } else {
+                    (line + count_or_one).min(last_line)
+                };
+                let column = *vim.column.get_or_insert(pos - line_start(&rope, line));
+                line_start(&rope, target_line)
+                    .saturating_add(column)
+                    .min(line_end(&rope, target_line))
+            }
+            Motion::NextWordStart { big } => {
+                (0..count_or_one).fold(pos, |pos, _| next_word_start(&rope, pos, big))
+            }
+            Motion::PreviousWordStart { big } => {
+                (0..count_or_one).fold(pos, |pos, _| previous_word_start(&rope, pos, big))
+            }
+            Motion::WordEnd { big } => {
+                (0..count_or_one).fold(pos, |pos, _| word_end(&rope, pos, big))
+            }
+            Motion::LineStart => line_start(&rope, line),
+            Motion::FirstNonBlank => first_non_blank(&rope, line),
+            Motion::LineEnd => last_char(&rope, (line + count_or_one - 1).min(last_line)),
+            Motion::FirstLine => {
+                first_non_blank(&rope, count.map_or(0, |count| count - 1).min(last_line))
+            }
+            Motion::LastLine => first_non_blank(
+                &rope,
+                count.map_or(last_line, |count| count - 1).min(last_line),
+            ),
+            Motion::FindChar(find) => {
+                vim.last_find = Some(find);
+                find_char(&rope, pos, find, count_or_one)?
+            }
+            Motion::RepeatFind { reverse } => {
+                let find = vim.last_find?;
+                let find = if reverse { find.reversed() } else { find };
+                find_char(&rope, pos, find, count_or_one)?
+            }
+            Motion::ParagraphForward => {
+                (0..count_or_one).fold(pos, |pos, _| paragraph_forward(&rope, pos))
+            }
+            Motion::ParagraphBackward => {
+                (0..count_or_one).fold(pos, |pos, _| paragraph_backward(&rope, pos))
+            }
+            Motion::MatchingBracket => unreachable!(),
+        };
+
+        match motion {
+            Motion::Up | Motion::Down => {}
+            // Moving up and down afterwards stays at the end of the lines
+            Motion::LineEnd => vim.column = Some(usize::MAX),
+            _ => vim.column = None,
+        }
+        Some(target)
+    }
+
+    /// Range of chars covered by an operator, and whether it covers whole lines.
+    fn vim_target_range(
+        &self,
+        vim: &mut VimState,
+        operator: Operator,
+        target: Target,
+        count: Option<usize>,
+    ) -> Option<(usize, usize, bool)> {
+        let pos = self.vim_pos();
+        match target {
+            Target::Lines => {
+                let rope = self.rope.borrow();
+                let last_line =
+                    (rope.char_to_line(pos) + count.unwrap_or(1) - 1).min(last_line(&rope));
+                Some((pos, line_start(&rope, last_line), true))
+            }
+            Target::TextObject(object) => text_object_range(&self.rope.borrow(), pos, object)
+                .map(|(start, end)| (start, end, false)),
+            Target::Motion(motion) => {
+                let target = self.vim_motion_target(vim, motion, count)?;
+                let rope = self.rope.borrow();
+                let range = match motion.kind() {
+                    MotionKind::Linewise => return Some((pos, target, true)),
+                    MotionKind::Inclusive => {
+                        (pos.min(target), inclusive_end(&rope, pos.max(target)))
+                    }
+                    MotionKind::Exclusive => (pos.min(target), pos.max(target)),
+                };
+
+                let (start, mut end) = range;
+                if let Motion::NextWordStart { .. } = motion {
+                    // Words are not joined with the next line
+                    let line = rope.char_to_line(start);
+                    if rope.char_to_line(end) > line {
+                        end = line_end(&rope, line).max(start);
+                    }
+                    // `cw` leaves the whitespace after the word, like `ce`
+                    let on_word = start < end && !rope.char(start).is_whitespace();
+                    if operator == Operator::Change && on_word {
+                        while end > start && rope.char(end - 1).is_whitespace() {
+                            end -= 1;
+                        }
+                    }
+                }
+                Some((start, end, false))
+            }
+        }
+    }
+
+    fn vim_operate(
+        &mut self,
+        vim: &mut VimState,
+        operator: Operator,
+        register: char,
+        start: usize,
+        end: usize,
+        linewise: bool,
+    ) {
+        if linewise || matches!(operator, Operator::Indent | Operator::Outdent) {
+            let (first, last) = {
+                let rope = self.rope.borrow();
+                let last = if linewise {
+                    start.max(end)
+                } else {
+                    end.saturating_sub(1).max(start)
+                };
+                (rope.char_to_line(start.min(end)), rope.char_to_line(last))
+            };
+            self.vim_operate_lines(vim, operator, register, first, last);
+            return;
+        }
+
+        let text = self.chars_text(start, end);
+        if !text.is_empty() {
+            let yanked = operator == Operator::Yank;
+            self.store_register(
+                vim,
+                register,
+                Register {
+                    text,
+                    linewise: false,
+                },
+                yanked,
+            );
+        }
+
+        if operator != Operator::Yank && start < end {
+            self.replace_chars(start, end, "");
+        }
+        self.set_vim_pos(start);
+        if operator == Operator::Change {
+            vim.mode = VimMode::Insert;
+        }
+    }
+
+    fn vim_operate_lines(
+        &mut self,
+        vim: &mut VimState,
+        operator: Operator,
+        register: char,
+        first: usize,
+        last: usize,
+    ) {
+        let lines = self.lines_text(first..=last);
+        if matches!(
+            operator,
+            Operator::Delete | Operator::Change | Operator::Yank
+        ) {
+            self.store_register(
+                vim,
+                register,
+                Register {
+                    text: lines.join(self.line_break()),
+                    linewise: true,
+                },
+                operator == Operator::Yank,
+            );
+        }
+
+        match operator {
+            Operator::Delete => {
+                let range = self.lines_range(first, last);
+                if !range.is_empty() {
+                    self.remove(range);
+                }
+                let line = first.min(self.len_lines() - 1);
+                let pos = first_non_blank(&self.rope.borrow(), line);
+                self.set_vim_pos(pos);
+            }
+            Operator::Change => {
+                // The indentation of the first line stays
+                let (len, _) = leading_whitespace(&lines[0], self.indentation.tab_width());
+                self.replace_lines(first..=last, &[lines[0][..len].to_string()]);
+                let pos = line_end(&self.rope.borrow(), first);
+                self.set_vim_pos(pos);
+                vim.mode = VimMode::Insert;
+            }
+            Operator::Yank => {
+                let row = self.cursor_row();
+                if first < row {
+                    self.cursor = TextCursor::new(self.line_position(first, self.cursor_col()));
+                }
+            }
+            Operator::Indent | Operator::Outdent => {
+                let lines = lines
+                    .iter()
+                    .map(|line| self.indent_line(line, operator == Operator::Outdent))
+                    .collect::<Vec<String>>();
+                self.replace_lines(first..=last, &lines);
+                let pos = first_non_blank(&self.rope.borrow(), first);
+                self.set_vim_pos(pos);
+            }
+        }
+    }
+
+    /// Indent a line one level, or outdent it.
+    fn indent_line(&self, line: &str, outdent: bool) -> String {
+        if line.trim().is_empty() {
+            return line.to_string();
+        }
+        let (len, width) = leading_whitespace(line, self.indentation.tab_width());
+        let level = self.indentation.size.max(1) as usize;
+        let width = if outdent {
+            width.saturating_sub(1) / level * level
+        } else {
+            (width / level + 1) * level
+        };
+        self.indentation.whitespace(width) + &line[len..]
+    }
+
+    /// Put the text of a register after the cursor, or before it.
+    fn vim_put(&mut self, vim: &mut VimState, register: char, count: usize, before: bool) {
+        let Some(Register { text, linewise }) = self.load_register(vim, register) else {
+            return;
+        };
+        let pos = self.vim_pos();
+        let (line, line_start, line_end) = {
+            let rope = self.rope.borrow();
+            let line = rope.char_to_line(pos);
+            (line, line_start(&rope, line), line_end(&rope, line))
+        };
+
+        if linewise {
+            let line_break = self.line_break();
+            let text = vec![text; count].join(line_break);
+            let target_line = if before {
+                self.insert_chars(&format!("{text}{line_break}"), line_start);
+                line
+            } else {
+                self.insert_chars(&format!("{line_break}{text}"), line_end);
+                line + 1
+            };
+            let pos = first_non_blank(&self.rope.borrow(), target_line);
+            self.set_vim_pos(pos);
+        } else {
+            let text = text.repeat(count);
+            let at = if before || pos >= line_end {
+                pos
+            } else {
+                pos + 1
+            };
+            self.insert_chars(&text, at);
+            // The cursor ends in the last char that was put
+            self.set_vim_pos((at + text.chars().count()).saturating_sub(1));
+        }
+    }
+
+    fn store_register(&mut self, vim: &mut VimState, name: char, register: Register, yanked: bool) {
+        match name {
+            // The black hole register
+            '_' => return,
+            '+' | '*' => {
+                let mut text = register.text.clone();
+                if register.linewise {
+                    text.push('\n');
+                }
+                let _ = self.clipboard.set(text);
+            }
+            // Uppercase names append to the register
+            'A'..='Z' => {
+                let stored = vim.registers.entry(name.to_ascii_lowercase()).or_default();
+                if register.linewise && !stored.text.is_empty() {
+                    stored.text.push('\n');
+                }
+                stored.text.push_str(&register.text);
+                stored.linewise |= register.linewise;
+            }
+            'a'..='z' => {
+                vim.registers.insert(name, register.clone());
+            }
+            _ => {}
+        }
+
+        if yanked {
+            vim.registers.insert('0', register.clone());
+        }
+        vim.registers.insert('"', register);
+    }
+
+    fn load_register(&mut self, vim: &VimState, name: char) -> Option<Register> {
+        match name {
+            '+' | '*' => {
+                let text = self.clipboard.get().ok()?;
+                let linewise = text.ends_with('\n');
+                Some(Register {
+                    text: text.trim_end_matches(['\n', '\r']).to_string(),
+                    linewise,
+                })
+            }
+            name => vim.registers.get(&name.to_ascii_lowercase()).cloned(),
+        }
+    }
+
+    /// Process again a key of the last change.
+    fn replay_vim_key(&mut self, vim: &mut VimState, key: &Key) {
+        if vim.mode == VimMode::Insert {
+            self.process_editor_key(key, &Code::Unidentified, &Modifiers::empty());
+        } else if let Key::Character(text) = key {
+            for ch in text.chars() {
+                self.process_vim_char(vim, ch);
+            }
+        }
+    }
+
+    fn exit_insert_mode(&mut self, vim: &mut VimState) {
+        vim.mode = VimMode::Normal;
+        if let Some(change) = vim.change.take() {
+            vim.last_change = change;
+        }
+        if let Some(first_change) = vim.insert_start.take() {
+            if self.history.current_change() != first_change {
+                self.group_history(first_change);
+            }
+        }
+
+        // The cursor goes back to the last typed char
+        let pos = self.vim_pos();
+        let line_start = self.line_to_char(self.char_to_line(pos));
+        if pos > line_start {
+            self.set_vim_pos(pos - 1);
+        }
+        self.clear_selection();
+    }
+
+    fn exit_visual_mode(&mut self, vim: &mut VimState) {
+        let (start, end) = self.visual_range(vim);
+        let rope = self.rope.borrow();
+        vim.last_visual_lines = Some((
+            rope.char_to_line(start),
+            rope.char_to_line(end.saturating_sub(1).max(start)),
+        ));
+        drop(rope);
+
+        vim.mode = VimMode::Normal;
+        self.clear_selection();
+        self.clamp_vim_cursor();
+    }
+
+    /// Chars between the visual anchor and the cursor, both included.
+    fn visual_range(&self, vim: &VimState) -> (usize, usize) {
+        let pos = self.vim_pos();
+        let (start, end) = (vim.visual_anchor.min(pos), vim.visual_anchor.max(pos));
+        (start, inclusive_end(&self.rope.borrow(), end))
+    }
+
+    fn update_visual_selection(&mut self, vim: &VimState) {
+        let (start, end) = self.visual_range(vim);
+        let (start, end) = if vim.mode == VimMode::VisualLine {
+            let rope = self.rope.borrow();
+            let last_line = rope.char_to_line(end.saturating_sub(1).max(start));
+            (
+                line_start(&rope, rope.char_to_line(start)),
+                line_end(&rope, last_line),
+            )
+        } else {
+            (start, end)
+        };
+        self.selected = Some((self.char_to_utf16_cu(start), self.char_to_utf16_cu(end)));
+    }
+
+    /// Keep the cursor on a char, in normal mode it can't be after the last char of a line.
+    fn clamp_vim_cursor(&mut self) {
+        let pos = self.vim_pos();
+        let last_char = {
+            let rope = self.rope.borrow();
+            last_char(&rope, rope.char_to_line(pos))
+        };
+        if pos > last_char {
+            self.set_vim_pos(last_char);
+        }
+    }
+
+    /// Process a key of the command line, returns whether it moved through the history.
+    fn process_command_line_key(&mut self, vim: &mut VimState, key: &Key) -> bool {
+        match key {
+            Key::Character(text) => vim.command_line.push_str(text),
+            Key::Backspace => {
+                if vim.command_line.pop().is_none() {
+                    vim.mode = VimMode::Normal;
+                }
+            }
+            Key::Enter => {
+                let command = mem::take(&mut vim.command_line);
+                vim.mode = VimMode::Normal;
+                return self.run_ex_command(vim, &command);
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Position of the cursor, as a char index.
+    fn vim_pos(&self) -> usize {
+        self.utf16_cu_to_char(self.cursor_pos())
+    }
+
+    fn set_vim_pos(&mut self, idx: usize) {
+        self.cursor = TextCursor::new(self.char_to_utf16_cu(idx));
+    }
+
+    fn chars_text(&self, start: usize, end: usize) -> String {
+        self.rope.borrow().slice(start..end).to_string()
+    }
+
+    fn insert_chars(&mut self, text: &str, idx: usize) {
+        if !text.is_empty() {
+            self.insert(text, self.char_to_utf16_cu(idx));
+        }
+    }
+
+    fn replace_chars(&mut self, start: usize, end: usize, text: &str) {
+        if start < end {
+            self.remove(self.char_to_utf16_cu(start)..self.char_to_utf16_cu(end));
+        }
+        self.insert_chars(text, start);
+    }
+}
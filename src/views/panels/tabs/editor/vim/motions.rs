@@ -0,0 +1,440 @@
+use freya::prelude::Rope;
+
+/// Where a motion moves the cursor.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// `w`, or `W` for WORDs, which are made of any non-whitespace char.
+    NextWordStart {
+        big: bool,
+    },
+    /// `b` and `B`.
+    PreviousWordStart {
+        big: bool,
+    },
+    /// `e` and `E`.
+    WordEnd {
+        big: bool,
+    },
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    /// `gg`, to the first line or the line of the count.
+    FirstLine,
+    /// `G`, to the last line or the line of the count.
+    LastLine,
+    /// `f`, `F`, `t` and `T`.
+    FindChar(FindChar),
+    /// `;`, or `,` for the opposite direction.
+    RepeatFind {
+        reverse: bool,
+    },
+    MatchingBracket,
+    ParagraphForward,
+    ParagraphBackward,
+}
+
+/// Search of a char in the line of the cursor.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FindChar {
+    pub ch: char,
+    pub forward: bool,
+    /// Stop right before the char.
+    pub till: bool,
+}
+
+impl FindChar {
+    pub fn reversed(self) -> Self {
+        Self {
+            forward: !self.forward,
+            ..self
+        }
+    }
+}
+
+/// What an operator covers when used with a motion.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MotionKind {
+    /// Up to the target, without it.
+    Exclusive,
+    /// Up to the target, with it.
+    Inclusive,
+    /// Every line from the cursor to the target.
+    Linewise,
+}
+
+impl Motion {
+    pub fn kind(&self) -> MotionKind {
+        match self {
+            Self::Up | Self::Down | Self::FirstLine | Self::LastLine => MotionKind::Linewise,
+            Self::WordEnd { .. } | Self::LineEnd | Self::MatchingBracket => MotionKind::Inclusive,
+            Self::FindChar(FindChar { forward, .. }) => {
+                if *forward {
+                    MotionKind::Inclusive
+                } else {
+                    MotionKind::Exclusive
+                }
+            }
+            _ => MotionKind::Exclusive,
+        }
+    }
+}
+
+/// A range of text selected around the cursor, like a word or the content of brackets.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextObject {
+    /// `iw` and `aw`, or `iW` and `aW` for WORDs.
+    Word { big: bool, around: bool },
+    /// `i(`, `a{`, `ib`, `aB`...
+    Pair {
+        open: char,
+        close: char,
+        around: bool,
+    },
+    /// `i"`, `a'`...
+    Quote { quote: char, around: bool },
+}
+
+impl TextObject {
+    pub fn parse(ch: char, around: bool) -> Option<Self> {
+        let pair = |open, close| {
+            Some(Self::Pair {
+                open,
+                close,
+                around,
+            })
+        };
+        match ch {
+            'w' => Some(Self::Word { big: false, around }),
+            'W' => Some(Self::Word { big: true, around }),
+            '(' | ')' | 'b' => pair('(', ')'),
+            '{' | '}' | 'B' => pair('{', '}'),
+            '[' | ']' => pair('[', ']'),
+            '<' | '>' => pair('<', '>'),
+            '"' | '\'' | '`' => Some(Self::Quote { quote: ch, around }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CharClass {
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+fn char_class(ch: char, big: bool) -> CharClass {
+    if ch.is_whitespace() {
+        CharClass::Whitespace
+    } else if big || ch.is_alphanumeric() || ch == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// First char of a line, positions in Vim mode are char indexes instead of UTF-16 code units.
+pub fn line_start(rope: &Rope, line: usize) -> usize {
+    rope.line_to_char(line)
+}
+
+/// End of the content of a line, before its line break.
+pub fn line_end(rope: &Rope, line: usize) -> usize {
+    let start = rope.line_to_char(line);
+    let text = rope.line(line);
+    let mut len = text.len_chars();
+    while len > 0 && matches!(text.char(len - 1), '\n' | '\r') {
+        len -= 1;
+    }
+    start + len
+}
+
+/// Last char of a line, where the cursor can be in normal mode.
+pub fn last_char(rope: &Rope, line: usize) -> usize {
+    line_end(rope, line)
+        .saturating_sub(1)
+        .max(line_start(rope, line))
+}
+
+/// Last line of the text, the empty one after a final line break doesn't count.
+pub fn last_line(rope: &Rope) -> usize {
+    let last = rope.len_lines() - 1;
+    if last > 0 && line_start(rope, last) == rope.len_chars() {
+        last - 1
+    } else {
+        last
+    }
+}
+
+pub fn first_non_blank(rope: &Rope, line: usize) -> usize {
+    let end = line_end(rope, line);
+    (line_start(rope, line)..end)
+        .find(|idx| !rope.char(*idx).is_whitespace())
+        .unwrap_or(end)
+}
+
+fn is_line_break(rope: &Rope, idx: usize) -> bool {
+    idx < rope.len_chars() && matches!(rope.char(idx), '\n' | '\r')
+}
+
+/// Where an inclusive range that ends in the given char ends, line breaks are left out.
+pub fn inclusive_end(rope: &Rope, idx: usize) -> usize {
+    if idx >= rope.len_chars() || is_line_break(rope, idx) {
+        idx
+    } else {
+        idx + 1
+    }
+}
+
+pub fn next_word_start(rope: &Rope, pos: usize, big: bool) -> usize {
+    let len = rope.len_chars();
+    if pos >= len {
+        return len;
+    }
+
+    let mut idx = pos;
+    let class = char_class(rope.char(idx), big);
+    if class != CharClass::Whitespace {
+        while idx < len && char_class(rope.char(idx), big) == class {
+            idx += 1;
+        }
+    }
+    while idx < len && rope.char(idx).is_whitespace() {
+        // Empty lines count as words
+        if rope.char(idx) == '\n' && idx + 1 < len && is_line_break(rope, idx + 1) {
+            return idx + 1;
+        }
+        idx += 1;
+    }
+    idx
+}
+
+pub fn previous_word_start(rope: &Rope, pos: usize, big: bool) -> usize {
+    let mut idx = pos.min(rope.len_chars());
+    while idx > 0 && rope.char(idx - 1).is_whitespace() {
+        idx -= 1;
+    }
+    if idx == 0 {
+        return 0;
+    }
+
+    let class = char_class(rope.char(idx - 1), big);
+    while idx > 0 && char_class(rope.char(idx - 1), big) == class {
+        idx -= 1;
+    }
+    idx
+}
+
+pub fn word_end(rope: &Rope, pos: usize, big: bool) -> usize {
+    let len = rope.len_chars();
+    let mut idx = pos + 1;
+    while idx < len && rope.char(idx).is_whitespace() {
+        idx += 1;
+    }
+    if idx >= len {
+        return len.saturating_sub(1);
+    }
+
+    let class = char_class(rope.char(idx), big);
+    while idx + 1 < len && char_class(rope.char(idx + 1), big) == class {
+        idx += 1;
+    }
+    idx
+}
+
+/// The `count`th appearance of a char in the line of the cursor.
+pub fn find_char(rope: &Rope, pos: usize, find: FindChar, count: usize) -> Option<usize> {
+    let line = rope.char_to_line(pos);
+    let (start, end) = (line_start(rope, line), line_end(rope, line));
+
+    let found = if find.forward {
+        // Repeating a `t` must not get stuck right before the same char
+        let from = pos + 1 + find.till as usize;
+        (from.min(end)..end)
+            .filter(|idx| rope.char(*idx) == find.ch)
+            .nth(count - 1)?
+    } else {
+        let to = pos.saturating_sub(find.till as usize).max(start);
+        (start..to)
+            .rev()
+            .filter(|idx| rope.char(*idx) == find.ch)
+            .nth(count - 1)?
+    };
+
+    Some(match (find.till, find.forward) {
+        (true, true) => found - 1,
+        (true, false) => found + 1,
+        _ => found,
+    })
+}
+
+fn is_blank_line(rope: &Rope, line: usize) -> bool {
+    line_start(rope, line) == line_end(rope, line)
+}
+
+pub fn paragraph_forward(rope: &Rope, pos: usize) -> usize {
+    let last_line = last_line(rope);
+    let mut line = rope.char_to_line(pos);
+    while line < last_line && is_blank_line(rope, line) {
+        line += 1;
+    }
+    while line < last_line && !is_blank_line(rope, line) {
+        line += 1;
+    }
+    if is_blank_line(rope, line) {
+        line_start(rope, line)
+    } else {
+        line_end(rope, line)
+    }
+}
+
+pub fn paragraph_backward(rope: &Rope, pos: usize) -> usize {
+    let mut line = rope.char_to_line(pos);
+    while line > 0 && is_blank_line(rope, line) {
+        line -= 1;
+    }
+    while line > 0 && !is_blank_line(rope, line) {
+        line -= 1;
+    }
+    line_start(rope, line)
+}
+
+/// Range of chars of a text object, without its end.
+pub fn text_object_range(rope: &Rope, pos: usize, object: TextObject) -> Option<(usize, usize)> {
+    if pos >= rope.len_chars() {
+        return None;
+    }
+    match object {
+        TextObject::Word { big, around } => Some(word_range(rope, pos, big, around)),
+        TextObject::Pair {
+            open,
+            close,
+            around,
+        } => pair_range(rope, pos, open, close, around),
+        TextObject::Quote { quote, around } => quote_range(rope, pos, quote, around),
+    }
+}
+
+fn word_range(rope: &Rope, pos: usize, big: bool, around: bool) -> (usize, usize) {
+    let line = rope.char_to_line(pos);
+    let (line_start, line_end) = (line_start(rope, line), line_end(rope, line));
+    let same_class_run = |from: usize| {
+        let class = char_class(rope.char(from), big);
+        let mut start = from;
+        while start > line_start && char_class(rope.char(start - 1), big) == class {
+            start -= 1;
+        }
+        let mut end = from;
+        while end < line_end && char_class(rope.char(end), big) == class {
+            end += 1;
+        }
+        (start, end)
+    };
+
+    if pos >= line_end {
+        return (pos, pos);
+    }
+
+    let (start, end) = same_class_run(pos);
+    if !around {
+        return (start, end);
+    }
+
+    if rope.char(pos).is_whitespace() {
+        // The whitespace and the word after it
+        if end < line_end {
+            return (start, same_class_run(end).1);
+        }
+        return (start, end);
+    }
+
+    // The word and the whitespace after it, or before it when there is none after
+    if end < line_end && rope.char(end).is_whitespace() {
+        (start, same_class_run(end).1)
+    } else if start > line_start && rope.char(start - 1).is_whitespace() {
+        (same_class_run(start - 1).0, end)
+    } else {
+        (start, end)
+    }
+}
+
+fn pair_range(
+    rope: &Rope,
+    pos: usize,
+    open: char,
+    close: char,
+    around: bool,
+) -> Option<(usize, usize)> {
+    let mut depth = 0;
+    let mut start = None;
+    for idx in (0..=pos).rev() {
+        let ch = rope.char(idx);
+        if ch == close && idx != pos {
+            depth += 1;
+        } else if ch == open {
+            if depth == 0 {
+                start = Some(idx);
+                break;
+            }
+            depth -= 1;
+        }
+    }
+    let start = start?;
+
+    let mut depth = 0;
+    let mut end = None;
+    for idx in start + 1..rope.len_chars() {
+        let ch = rope.char(idx);
+        if ch == open {
+            depth += 1;
+        } else if ch == close {
+            if depth == 0 {
+                end = Some(idx);
+                break;
+            }
+            depth -= 1;
+        }
+    }
+    let end = end?;
+
+    if around {
+        return Some((start, end + 1));
+    }
+
+    // In blocks spanning multiple lines the lines of the brackets are kept
+    let (start_line, end_line) = (rope.char_to_line(start), rope.char_to_line(end));
+    let mut inner = (start + 1, end);
+    if end_line > start_line + 1 {
+        let content_end = line_end(rope, start_line);
+        let closing_start = line_start(rope, end_line);
+        let only_whitespace_before_close =
+            (closing_start..end).all(|idx| rope.char(idx).is_whitespace());
+        if content_end == start + 1 && only_whitespace_before_close {
+            inner = (line_start(rope, start_line + 1), closing_start);
+        }
+    }
+    Some(inner)
+}
+
+fn quote_range(rope: &Rope, pos: usize, quote: char, around: bool) -> Option<(usize, usize)> {
+    let line = rope.char_to_line(pos);
+    let quotes = (line_start(rope, line)..line_end(rope, line))
+        .filter(|idx| rope.char(*idx) == quote && (*idx == 0 || rope.char(idx - 1) != '\\'))
+        .collect::<Vec<usize>>();
+
+    // The pair around the cursor, or the first one after it
+    let (start, end) = quotes
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .find(|(_, end)| pos <= *end)?;
+
+    if around {
+        Some((start, end + 1))
+    } else {
+        Some((start + 1, end))
+    }
+}
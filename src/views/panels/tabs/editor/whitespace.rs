@@ -0,0 +1,110 @@
+use std::ops::Range;
+
+use freya::hooks::TextEditor;
+
+use crate::indentation::leading_whitespace;
+
+use super::EditorData;
+
+/// Split a text into the pieces shown as they are and the whitespace shown with marks,
+/// where spaces are dots, tabs are arrows and line breaks are `¬`.
+/// Every char keeps one mark so the cursor stays where it was.
+pub fn whitespace_pieces(text: &str, show_spaces: bool, show_eol: bool) -> Vec<(String, bool)> {
+    let mut pieces: Vec<(String, bool)> = Vec::new();
+
+    for ch in text.chars() {
+        let mark = match ch {
+            ' ' if show_spaces => Some('·'),
+            '\t' if show_spaces => Some('→'),
+            '\n' if show_eol => Some('¬'),
+            _ => None,
+        };
+        let is_mark = mark.is_some();
+        let ch = mark.unwrap_or(ch);
+        match pieces.last_mut() {
+            Some((piece, last_is_mark)) if *last_is_mark == is_mark => piece.push(ch),
+            _ => pieces.push((ch.to_string(), is_mark)),
+        }
+    }
+
+    pieces
+}
+
+impl EditorData {
+    /// Width of the indentation of a line, or `None` if it's blank.
+    fn line_indent(&self, line: usize) -> Option<usize> {
+        let text = self.line_text(line);
+        if text.trim().is_empty() {
+            return None;
+        }
+        Some(leading_whitespace(&text, self.indentation.tab_width()).1)
+    }
+
+    /// Width of the indentation of a line, blank lines take the widest of the lines around them.
+    fn indent_width(&self, line: usize) -> usize {
+        self.line_indent(line).unwrap_or_else(|| {
+            let above = (0..line).rev().find_map(|line| self.line_indent(line));
+            let below = (line + 1..self.len_lines()).find_map(|line| self.line_indent(line));
+            above.max(below).unwrap_or_default()
+        })
+    }
+
+    /// Number of indentation guides of a line.
+    pub fn indent_guides(&self, line: usize) -> usize {
+        self.indent_width(line) / self.indentation.tab_width()
+    }
+
+    /// Guide of the scope with the cursor and the lines it spans.
+    /// The cursor in the line that opens a scope uses the guide of that scope.
+    pub fn active_indent_guide(&self) -> Option<(usize, Range<usize>)> {
+        let size = self.indentation.tab_width();
+        let len_lines = self.len_lines();
+        let row = self.cursor_row();
+        let width = self.indent_width(row);
+        let next_width = (row + 1..len_lines).find_map(|line| self.line_indent(line));
+
+        let opens_scope = self.line_indent(row).is_some() && next_width.is_some_and(|w| w > width);
+        let (guide, first_line) = if opens_scope {
+            (width / size, row + 1)
+        } else if width >= size {
+            (width / size - 1, row)
+        } else {
+            return None;
+        };
+
+        // Blank lines don't end the scope
+        let in_scope = |line: usize| {
+            self.line_indent(line)
+                .is_none_or(|width| width > guide * size)
+        };
+        let mut start = first_line;
+        while start > 0 && in_scope(start - 1) {
+            start -= 1;
+        }
+        let mut end = first_line;
+        while end + 1 < len_lines && in_scope(end + 1) {
+            end += 1;
+        }
+
+        // Leave out the blank lines around it
+        while start < end && self.line_indent(start).is_none() {
+            start += 1;
+        }
+        while end > start && self.line_indent(end).is_none() {
+            end -= 1;
+        }
+
+        Some((guide, start..end + 1))
+    }
+
+    /// Columns of the whitespace at the end of a line, as UTF-16 code units.
+    pub fn trailing_whitespace(&self, line: usize) -> Option<Range<usize>> {
+        let text = self.line_text(line);
+        let trimmed = text.trim_end();
+        if trimmed.is_empty() || trimmed.len() == text.len() {
+            return None;
+        }
+        let start = trimmed.encode_utf16().count();
+        Some(start..text.encode_utf16().count())
+    }
+}
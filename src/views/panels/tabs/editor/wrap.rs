@@ -0,0 +1,191 @@
+use freya::events::Key;
+use freya::hooks::{TextCursor, TextEditor};
+use freya_hooks::TextEvent;
+
+use super::EditorData;
+
+/// Long lines wrapped into multiple visual rows.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SoftWrap {
+    /// Columns that fit in a visual row.
+    pub(crate) columns: usize,
+    /// Visual rows of every line.
+    rows: Vec<usize>,
+}
+
+/// Columns where the visual rows of a line start, the first one is always `0`.
+/// Rows break after whitespace when possible, like the text layout does,
+/// and the whitespace at the end of a row can go past its width.
+fn wrap_line(text: &str, columns: usize, tab_width: usize) -> Vec<usize> {
+    let columns = columns.max(1);
+    let mut starts = vec![0];
+    let mut width = 0;
+    // Start of the word being measured and the width of the row before it
+    let mut word_start: Option<(usize, usize)> = None;
+
+    for (idx, ch) in text.chars().enumerate() {
+        let ch_width = if ch == '\t' { tab_width } else { 1 };
+        if ch.is_whitespace() {
+            width += ch_width;
+            word_start = None;
+            continue;
+        }
+
+        let (start, width_before) = *word_start.get_or_insert((idx, width));
+        if width + ch_width > columns {
+            if width_before > 0 {
+                // The whole word goes to the next row
+                starts.push(start);
+                width -= width_before;
+                word_start = Some((start, 0));
+            } else {
+                // The word is longer than a row
+                starts.push(idx);
+                width = 0;
+                word_start = Some((idx, 0));
+            }
+        }
+        width += ch_width;
+    }
+
+    starts
+}
+
+impl EditorData {
+    /// Wrap the lines at the given columns, or stop wrapping them.
+    pub fn set_wrap_columns(&mut self, columns: Option<usize>) {
+        if self.soft_wrap.as_ref().map(|soft_wrap| soft_wrap.columns) == columns {
+            return;
+        }
+
+        self.soft_wrap = columns.map(|columns| SoftWrap {
+            columns,
+            rows: Vec::new(),
+        });
+        self.update_wrapped_rows();
+        self.request_scroll_to_cursor();
+    }
+
+    pub(super) fn update_wrapped_rows(&mut self) {
+        let Some(columns) = self.soft_wrap.as_ref().map(|soft_wrap| soft_wrap.columns) else {
+            return;
+        };
+
+        let rows = (0..self.len_lines())
+            .map(|line| self.wrap_starts(line, columns).len())
+            .collect();
+        if let Some(soft_wrap) = self.soft_wrap.as_mut() {
+            soft_wrap.rows = rows;
+        }
+    }
+
+    /// Visual rows of a line, `1` when the lines are not wrapped.
+    pub fn line_rows(&self, line: usize) -> usize {
+        self.soft_wrap
+            .as_ref()
+            .and_then(|soft_wrap| soft_wrap.rows.get(line).copied())
+            .unwrap_or(1)
+    }
+
+    /// Visual row of the cursor among the visible lines.
+    pub(super) fn cursor_visual_row(&self) -> usize {
+        let row = self.cursor_row();
+        let visible_index = self.folding.visible_index(row);
+        let Some(columns) = self.soft_wrap.as_ref().map(|soft_wrap| soft_wrap.columns) else {
+            return visible_index;
+        };
+
+        let rows_before = self
+            .folding
+            .visible_lines()
+            .iter()
+            .take(visible_index)
+            .map(|line| self.line_rows(*line))
+            .sum::<usize>();
+        let col = self.cursor_char_col();
+        let row_in_line = self
+            .wrap_starts(row, columns)
+            .iter()
+            .rposition(|start| *start <= col)
+            .unwrap_or_default();
+        rows_before + row_in_line
+    }
+
+    /// Move the cursor to the visual row above or below it, keeping its column in the row.
+    pub(super) fn process_wrapped_vertical_key(&mut self, key: &Key, select: bool) -> TextEvent {
+        let Some(columns) = self.soft_wrap.as_ref().map(|soft_wrap| soft_wrap.columns) else {
+            return TextEvent::empty();
+        };
+
+        let pos = self.cursor_pos();
+        let line = self.cursor_row();
+        let col = self.cursor_char_col();
+        let starts = self.wrap_starts(line, columns);
+        let row = starts
+            .iter()
+            .rposition(|start| *start <= col)
+            .unwrap_or_default();
+        let row_col = col - starts[row];
+
+        let visible_lines = self.folding.visible_lines();
+        let visible_index = self.folding.visible_index(line);
+        let target = if *key == Key::ArrowUp {
+            if row > 0 {
+                Some((line, starts, row - 1))
+            } else {
+                visible_index
+                    .checked_sub(1)
+                    .map(|idx| visible_lines[idx])
+                    .map(|line| {
+                        let starts = self.wrap_starts(line, columns);
+                        let last_row = starts.len() - 1;
+                        (line, starts, last_row)
+                    })
+            }
+        } else if row + 1 < starts.len() {
+            Some((line, starts, row + 1))
+        } else {
+            visible_lines
+                .get(visible_index + 1)
+                .map(|line| (*line, self.wrap_starts(*line, columns), 0))
+        };
+
+        let new_pos = match target {
+            Some((line, starts, row)) => {
+                let line_len = self.line_text(line).chars().count();
+                // Stay in the row, as its end is shown at the start of the next one
+                let row_end = starts
+                    .get(row + 1)
+                    .map(|start| start - 1)
+                    .unwrap_or(line_len);
+                let col = (starts[row] + row_col).min(row_end);
+                let rope = self.rope.borrow();
+                rope.char_to_utf16_cu(rope.line_to_char(line) + col)
+            }
+            // Like moving past the first or last line without wrapping
+            None if *key == Key::ArrowUp => 0,
+            None => self.rope.borrow().len_utf16_cu(),
+        };
+
+        if select {
+            let anchor = self.selected.map(|(anchor, _)| anchor).unwrap_or(pos);
+            self.selected = Some((anchor, new_pos));
+        } else {
+            self.clear_selection();
+        }
+        self.cursor = TextCursor::new(new_pos);
+        TextEvent::CURSOR_CHANGED
+    }
+
+    /// Columns where the visual rows of a line start, as char indexes.
+    fn wrap_starts(&self, line: usize, columns: usize) -> Vec<usize> {
+        wrap_line(&self.line_text(line), columns, self.indentation.tab_width())
+    }
+
+    /// Column of the cursor, as a char index.
+    fn cursor_char_col(&self) -> usize {
+        let rope = self.rope.borrow();
+        let idx = rope.utf16_cu_to_char(self.cursor_pos());
+        idx - rope.line_to_char(rope.char_to_line(idx))
+    }
+}
@@ -0,0 +1,204 @@
+use std::time::Duration;
+
+use dioxus_radio::prelude::use_radio;
+use freya::hooks::HistoryChange;
+use freya::prelude::*;
+
+use crate::state::{AppState, Channel, PanelTab, PanelTabData, TabId, TabProps};
+use crate::views::panels::tabs::editor::AppStateEditorUtils;
+
+/// Most chars shown of the changes of a state.
+const MAX_PREVIEW_LEN: usize = 60;
+
+/// A tab with the states of the undo tree of an editor.
+pub struct HistoryTab {
+    id: TabId,
+    focus_id: AccessibilityId,
+    editor_tab_id: TabId,
+    title: String,
+}
+
+impl PanelTab for HistoryTab {
+    fn get_data(&self) -> PanelTabData {
+        PanelTabData {
+            id: self.id,
+            title: self.title.clone(),
+            edited: false,
+            focus_id: self.focus_id,
+            content_id: format!("history-{}", self.editor_tab_id),
+        }
+    }
+
+    fn render(&self) -> fn(TabProps) -> Element {
+        render
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl HistoryTab {
+    pub fn new(editor_tab_id: TabId, title: String) -> Self {
+        Self {
+            id: TabId::new(),
+            focus_id: UseFocus::new_id(),
+            editor_tab_id,
+            title,
+        }
+    }
+
+    /// Open the history of an editor in the focused panel.
+    pub fn open_with(app_state: &mut AppState, editor_tab_id: TabId) {
+        let Some(editor_tab) = app_state.try_editor_tab(editor_tab_id) else {
+            return;
+        };
+        let title = format!("{} history", editor_tab.editor.editor_type.title());
+        app_state.push_tab(Self::new(editor_tab_id, title), app_state.focused_panel);
+    }
+}
+
+/// How long ago something happened, e.g `3m ago`.
+fn time_ago(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..=4 => "now".to_string(),
+        5..=59 => format!("{secs}s ago"),
+        60..=3599 => format!("{}m ago", secs / 60),
+        _ => format!("{}h ago", secs / 3600),
+    }
+}
+
+/// Short summary of some changes, with `+` for the inserted text and `-` for the removed one.
+fn changes_preview(changes: &[&HistoryChange]) -> String {
+    let preview = changes
+        .iter()
+        .map(|change| match change {
+            HistoryChange::InsertChar { ch, .. } => format!("+{ch}"),
+            HistoryChange::InsertText { text, .. } => format!("+{text}"),
+            HistoryChange::Remove { text, .. } => format!("-{text}"),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+        .replace('\n', "⏎")
+        .replace('\r', "");
+
+    if preview.chars().count() > MAX_PREVIEW_LEN {
+        let preview = preview.chars().take(MAX_PREVIEW_LEN).collect::<String>();
+        format!("{preview}…")
+    } else {
+        preview
+    }
+}
+
+pub fn render(TabProps { tab_id }: TabProps) -> Element {
+    let radio_app_state = use_radio::<AppState, Channel>(Channel::follow_tab(tab_id));
+    let editor_tab_id = radio_app_state
+        .read()
+        .tab(&tab_id)
+        .as_any()
+        .downcast_ref::<HistoryTab>()
+        .map(|tab| tab.editor_tab_id)
+        .unwrap();
+    // Follow the changes of the editor
+    let mut editor_radio = use_radio::<AppState, Channel>(Channel::follow_tab(editor_tab_id));
+
+    let app_state = editor_radio.read();
+    let Some(editor_tab) = app_state.try_editor_tab(editor_tab_id) else {
+        return rsx!(
+            rect {
+                height: "100%",
+                width: "100%",
+                background: "rgb(29, 32, 33)",
+                padding: "20",
+                label {
+                    "The file was closed."
+                }
+            }
+        );
+    };
+
+    let editor = &editor_tab.editor;
+    let tree = &editor.undo_tree;
+    let current = tree.node_at(editor.history.current_change());
+
+    // From the most recent state to the original content
+    let states = tree
+        .states()
+        .rev()
+        .map(|node| {
+            let changes = tree.state_changes(node);
+            let preview = if changes.is_empty() {
+                "Original content".to_string()
+            } else {
+                changes_preview(&changes)
+            };
+            let time = time_ago(tree.node(node).time.elapsed());
+            (node, time, preview, tree.is_in_branch(node))
+        })
+        .collect::<Vec<(usize, String, String, bool)>>();
+
+    rsx!(
+        rect {
+            height: "100%",
+            width: "100%",
+            background: "rgb(29, 32, 33)",
+            padding: "10",
+            ScrollView {
+                for (node, time, preview, is_in_branch) in states {
+                    {
+                        let background = if node == current {
+                            "rgb(70, 70, 70)"
+                        } else {
+                            "none"
+                        };
+                        // The states of other branches are dimmed
+                        let color = if is_in_branch {
+                            "rgb(235, 235, 235)"
+                        } else {
+                            "rgb(135, 135, 135)"
+                        };
+                        let onclick = move |_| {
+                            editor_radio
+                                .write_channel(Channel::follow_tab(editor_tab_id))
+                                .editor_tab_mut(editor_tab_id)
+                                .editor
+                                .go_to_undo_state(node);
+                        };
+
+                        rsx!(
+                            rect {
+                                key: "{node}",
+                                onclick,
+                                background,
+                                color,
+                                padding: "8 6",
+                                width: "100%",
+                                corner_radius: "10",
+                                direction: "horizontal",
+                                spacing: "10",
+                                label {
+                                    width: "50",
+                                    "#{node}"
+                                }
+                                label {
+                                    width: "70",
+                                    "{time}"
+                                }
+                                label {
+                                    max_lines: "1",
+                                    text_overflow: "ellipsis",
+                                    "{preview}"
+                                }
+                            }
+                        )
+                    }
+                }
+            }
+        }
+    )
+}
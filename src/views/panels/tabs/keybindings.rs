@@ -0,0 +1,145 @@
+use freya::prelude::*;
+
+use crate::state::{
+    AppState, EditorCommands, Keybindings, PanelTab, PanelTabData, TabId, TabProps,
+};
+
+/// A tab with the keybindings in use, generated from the defaults and the settings.
+pub struct KeybindingsTab {
+    id: TabId,
+    focus_id: AccessibilityId,
+}
+
+impl PanelTab for KeybindingsTab {
+    fn get_data(&self) -> PanelTabData {
+        PanelTabData {
+            id: self.id,
+            title: "Keybindings".to_string(),
+            edited: false,
+            focus_id: self.focus_id,
+            content_id: "keybindings".to_string(),
+        }
+    }
+
+    fn render(&self) -> fn(TabProps) -> Element {
+        render
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl KeybindingsTab {
+    fn new() -> Self {
+        Self {
+            id: TabId::new(),
+            focus_id: UseFocus::new_id(),
+        }
+    }
+
+    pub fn open_with(app_state: &mut AppState) {
+        app_state.push_tab(Self::new(), app_state.focused_panel);
+    }
+}
+
+pub fn render(_: TabProps) -> Element {
+    // Follows the keybindings resolved again when the settings change
+    let keybindings = use_context::<Signal<Keybindings>>();
+    let editor_commands = use_context::<Signal<EditorCommands>>();
+
+    let commands = editor_commands.read();
+    let keybindings = keybindings.read();
+    let effective_keybindings = keybindings.effective();
+
+    let mut rows = effective_keybindings
+        .keybindings
+        .iter()
+        .map(|keybinding| {
+            let text = commands
+                .commands
+                .get(&keybinding.command_id)
+                .map(|command| command.text().to_string())
+                .unwrap_or_else(|| keybinding.command_id.clone());
+            let source = if keybinding.is_custom {
+                "Custom"
+            } else {
+                "Default"
+            };
+            (
+                keybinding.keys_text(),
+                text,
+                keybinding.when.to_string(),
+                source,
+            )
+        })
+        .collect::<Vec<(String, String, String, &str)>>();
+    rows.sort_by(|(a_keys, a_text, ..), (b_keys, b_text, ..)| {
+        a_text.cmp(b_text).then_with(|| a_keys.cmp(b_keys))
+    });
+
+    rsx!(
+        rect {
+            height: "100%",
+            width: "100%",
+            background: "rgb(29, 32, 33)",
+            padding: "10",
+            ScrollView {
+                label {
+                    margin: "0 6 6 6",
+                    color: "rgb(165, 165, 165)",
+                    "Change them with [[keybindings]] in valin.toml, e.g keys = \"Ctrl+M Ctrl+S\" and command = \"save-file\"."
+                }
+                if !effective_keybindings.conflicts.is_empty() {
+                    label {
+                        margin: "6",
+                        color: "rgb(255, 120, 100)",
+                        font_weight: "bold",
+                        "Conflicts"
+                    }
+                }
+                for (i, conflict) in effective_keybindings.conflicts.iter().enumerate() {
+                    label {
+                        key: "conflict-{i}",
+                        margin: "0 6",
+                        color: "rgb(255, 120, 100)",
+                        "{conflict}"
+                    }
+                }
+                for (i, (keys, text, when, source)) in rows.into_iter().enumerate() {
+                    rect {
+                        key: "{i}",
+                        padding: "8 6",
+                        width: "100%",
+                        direction: "horizontal",
+                        spacing: "10",
+                        label {
+                            width: "200",
+                            color: "rgb(255, 197, 61)",
+                            "{keys}"
+                        }
+                        label {
+                            width: "120",
+                            color: "rgb(165, 165, 165)",
+                            "{when}"
+                        }
+                        label {
+                            width: "70",
+                            color: "rgb(165, 165, 165)",
+                            "{source}"
+                        }
+                        label {
+                            max_lines: "1",
+                            text_overflow: "ellipsis",
+                            "{text}"
+                        }
+                    }
+                }
+            }
+        }
+    )
+}
@@ -1,3 +1,5 @@
 pub mod editor;
+pub mod history;
+pub mod keybindings;
 pub mod settings;
 pub mod welcome;
@@ -29,8 +29,8 @@ pub mod Settings {
 
     #[async_trait]
     impl FSReadTransportInterface for MemoryTransport {
-        async fn read_to_string(&self, _path: &Path) -> tokio::io::Result<String> {
-            Ok(self.0.clone())
+        async fn read(&self, _path: &Path) -> tokio::io::Result<Vec<u8>> {
+            Ok(self.0.clone().into_bytes())
         }
     }
 }
@@ -0,0 +1,100 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use futures::channel::mpsc::UnboundedSender;
+use ignore::WalkBuilder;
+
+/// Most files indexed, the rest are left out.
+pub const MAX_INDEXED_FILES: usize = 200_000;
+
+/// Files sent together while indexing.
+const BATCH_SIZE: usize = 1_000;
+
+/// A file in one of the open folders.
+#[derive(Clone, PartialEq)]
+pub struct IndexedFile {
+    pub path: PathBuf,
+    /// Folder of the files explorer that contains the file.
+    pub root_path: PathBuf,
+    /// Path relative to its folder, used for matching.
+    pub relative_path: String,
+}
+
+/// Walk the folders, leaving out the files ignored by `.gitignore`.
+/// The files are sent in batches as they are found, until the indexing gets cancelled.
+pub fn index_folders(
+    folders: Vec<PathBuf>,
+    cancelled: Arc<AtomicBool>,
+    sender: UnboundedSender<Vec<IndexedFile>>,
+) {
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut indexed_files = 0;
+
+    for root_path in folders {
+        for entry in WalkBuilder::new(&root_path).build() {
+            if cancelled.load(Ordering::Relaxed) || indexed_files >= MAX_INDEXED_FILES {
+                return;
+            }
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if !entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+            {
+                continue;
+            }
+
+            let path = entry.into_path();
+            let relative_path = path
+                .strip_prefix(&root_path)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            batch.push(IndexedFile {
+                path,
+                root_path: root_path.clone(),
+                relative_path,
+            });
+            indexed_files += 1;
+
+            if batch.len() == BATCH_SIZE {
+                let files = std::mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE));
+                if sender.unbounded_send(files).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        sender.unbounded_send(batch).ok();
+    }
+}
+
+/// Split a `file:line:col` query into the file query and the position it points to,
+/// as a line and a column starting from 0.
+pub fn parse_position(query: &str) -> (&str, Option<(usize, usize)>) {
+    let Some((rest, last)) = query.rsplit_once(':') else {
+        return (query, None);
+    };
+    if last.is_empty() {
+        return (rest, None);
+    }
+    let Ok(last) = last.parse::<usize>() else {
+        return (query, None);
+    };
+
+    match rest.rsplit_once(':') {
+        Some((file, line)) => match line.parse::<usize>() {
+            Ok(line) => (file, Some((line.saturating_sub(1), last.saturating_sub(1)))),
+            Err(_) => (rest, Some((last.saturating_sub(1), 0))),
+        },
+        None => (rest, Some((last.saturating_sub(1), 0))),
+    }
+}
@@ -0,0 +1,2 @@
+pub mod file_index;
+pub mod quick_open_ui;
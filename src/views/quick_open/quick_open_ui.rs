@@ -0,0 +1,281 @@
+use std::{
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use dioxus_radio::prelude::use_radio;
+use freya::prelude::*;
+use futures::{channel::mpsc::unbounded, StreamExt};
+
+use crate::{
+    fuzzy::{matched_pieces, FuzzyMatcher},
+    state::{Channel, RadioAppState},
+    views::panels::tabs::editor::EditorTab,
+    Overlay, TextArea,
+};
+
+use super::file_index::{index_folders, parse_position, IndexedFile, MAX_INDEXED_FILES};
+
+/// Most files shown, the best matches first.
+const MAX_SHOWN_FILES: usize = 500;
+
+/// Score added to the most recently opened file, the next ones get a bit less.
+const RECENCY_BONUS: u32 = 60;
+
+/// A file that matches the query, with the indexes of its matched chars.
+#[derive(Clone, PartialEq)]
+struct FileMatch {
+    file: usize,
+    indices: Vec<usize>,
+}
+
+/// Open a file, at the position if there is one.
+fn open_file(
+    mut radio_app_state: RadioAppState,
+    files: Signal<Vec<IndexedFile>>,
+    file: usize,
+    position: Option<(usize, usize)>,
+) {
+    let files = files.read();
+    let Some(file) = files.get(file) else {
+        return;
+    };
+
+    let transport = radio_app_state.read().default_transport.clone();
+    let mut app_state = radio_app_state.write_channel(Channel::Global);
+    EditorTab::open_at(
+        radio_app_state,
+        &mut app_state,
+        file.path.clone(),
+        file.root_path.clone(),
+        transport.as_read(),
+        position.map(|(line, col)| (line, col..col)),
+    );
+}
+
+#[component]
+pub fn QuickOpen() -> Element {
+    let radio_app_state = use_radio(Channel::Global);
+    let mut value = use_signal(String::new);
+    let mut files = use_signal(Vec::<IndexedFile>::new);
+    let mut is_indexing = use_signal(|| true);
+    let mut selected = use_signal(|| 0);
+    let mut focus = use_focus();
+
+    // Index the files of the open folders once it's shown
+    let running_index = use_hook(|| {
+        let folders = radio_app_state
+            .read()
+            .file_explorer
+            .folders
+            .iter()
+            .map(|folder| folder.path().clone())
+            .collect::<Vec<_>>();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, mut receiver) = unbounded();
+
+        thread::spawn({
+            let cancelled = cancelled.clone();
+            move || index_folders(folders, cancelled, sender)
+        });
+
+        let task = spawn(async move {
+            while let Some(batch) = receiver.next().await {
+                files.write().extend(batch);
+            }
+            is_indexing.set(false);
+        });
+        Rc::new((task, cancelled))
+    });
+
+    // Stop indexing once it's closed
+    use_drop(move || {
+        let (task, cancelled) = &*running_index;
+        cancelled.store(true, Ordering::Relaxed);
+        task.cancel();
+    });
+
+    let query = value.read().clone();
+    let (file_query, position) = parse_position(&query);
+    let recent_files = radio_app_state.read().recent_files.clone();
+    let mut matcher = FuzzyMatcher::for_paths(file_query);
+
+    let files_len = files.read().len();
+    let mut matches = files
+        .read()
+        .iter()
+        .enumerate()
+        .filter_map(|(file, indexed_file)| {
+            let score = matcher.fuzzy_score(&indexed_file.relative_path)?;
+            // Recently opened files go first
+            let recency = recent_files
+                .iter()
+                .position(|recent_file| *recent_file == indexed_file.path)
+                .map(|rank| RECENCY_BONUS.saturating_sub(rank as u32))
+                .unwrap_or_default();
+            Some((score + recency, file))
+        })
+        .collect::<Vec<(u32, usize)>>();
+    {
+        let files = files.read();
+        matches.sort_unstable_by(|(a_score, a), (b_score, b)| {
+            let a_path = &files[*a].relative_path;
+            let b_path = &files[*b].relative_path;
+            b_score
+                .cmp(a_score)
+                .then(a_path.len().cmp(&b_path.len()))
+                .then_with(|| a_path.cmp(b_path))
+        });
+    }
+    let matched_files = matches.len();
+    // Only the shown files need the matched chars
+    let matches = {
+        let files = files.read();
+        matches
+            .into_iter()
+            .take(MAX_SHOWN_FILES)
+            .map(|(_, file)| {
+                let indices = matcher
+                    .fuzzy_match(&files[file].relative_path)
+                    .map(|(_, indices)| indices)
+                    .unwrap_or_default();
+                FileMatch { file, indices }
+            })
+            .collect::<Vec<FileMatch>>()
+    };
+    let matches_len = matches.len();
+
+    let onchange = move |v: String| {
+        if *value.peek() != v {
+            selected.set(0);
+            value.set(v);
+        }
+    };
+
+    let selected_file = matches.get(selected()).map(|file_match| file_match.file);
+
+    let onsubmit = move |_: String| {
+        if let Some(file) = selected_file {
+            open_file(radio_app_state, files, file, position);
+        }
+    };
+
+    let onkeydown = move |e: KeyboardEvent| {
+        e.stop_propagation();
+        focus.prevent_navigation();
+        match e.code {
+            Code::ArrowDown if matches_len > 0 => {
+                if selected() < matches_len - 1 {
+                    *selected.write() += 1;
+                } else {
+                    selected.set(0);
+                }
+            }
+            Code::ArrowUp if matches_len > 0 => {
+                if selected() > 0 {
+                    *selected.write() -= 1;
+                } else {
+                    selected.set(matches_len - 1);
+                }
+            }
+            _ => {}
+        }
+    };
+
+    let summary = if *is_indexing.read() {
+        format!("Indexing... {files_len} files")
+    } else if files_len >= MAX_INDEXED_FILES {
+        format!("{matched_files} of the first {files_len} files")
+    } else if matcher.is_empty() {
+        format!("{files_len} files")
+    } else {
+        format!("{matched_files} of {files_len} files")
+    };
+
+    rsx!(
+        Overlay {
+            width: 650.0,
+            rect {
+                onkeydown,
+                spacing: "5",
+                TextArea {
+                    placeholder: "Open a file, or file:line:column...",
+                    value: "{value}",
+                    onchange,
+                    onsubmit,
+                }
+                label {
+                    margin: "0 6",
+                    color: "rgb(165, 165, 165)",
+                    "{summary}"
+                }
+                VirtualScrollView {
+                    height: "350",
+                    width: "100%",
+                    length: matches_len,
+                    item_size: 30.0,
+                    builder_args: (matches, files, selected, position, radio_app_state),
+                    direction: "vertical",
+                    scroll_with_arrows: false,
+                    builder: file_match_builder
+                }
+            }
+        }
+    )
+}
+
+type FileMatchBuilderOptions = (
+    Vec<FileMatch>,
+    Signal<Vec<IndexedFile>>,
+    Signal<usize>,
+    Option<(usize, usize)>,
+    RadioAppState,
+);
+
+fn file_match_builder(index: usize, values: &Option<FileMatchBuilderOptions>) -> Element {
+    let (matches, files, selected, position, radio_app_state) = values.as_ref().unwrap();
+    let (files, position, radio_app_state) = (*files, *position, *radio_app_state);
+    let file_match = &matches[index];
+    let file = file_match.file;
+
+    let indexed_files = files.read();
+    let relative_path = &indexed_files[file].relative_path;
+    let pieces = matched_pieces(relative_path, &file_match.indices);
+    let background = if index == selected() {
+        "rgb(29, 32, 33)"
+    } else {
+        "none"
+    };
+
+    let onclick = move |_| open_file(radio_app_state, files, file, position);
+
+    rsx!(
+        rect {
+            key: "{file}",
+            onclick,
+            background,
+            width: "100%",
+            height: "30",
+            padding: "0 6",
+            corner_radius: "8",
+            main_align: "center",
+            paragraph {
+                width: "100%",
+                max_lines: "1",
+                text_overflow: "ellipsis",
+                for (i, (piece, is_match)) in pieces.into_iter().enumerate() {
+                    text {
+                        key: "{i}",
+                        color: if is_match { "rgb(255, 197, 61)" } else { "rgb(215, 215, 215)" },
+                        font_weight: if is_match { "bold" } else { "normal" },
+                        "{piece}"
+                    }
+                }
+            }
+        }
+    )
+}
@@ -1 +1,3 @@
+pub mod project_replace;
+pub mod project_search;
 pub mod search_ui;
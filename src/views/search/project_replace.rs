@@ -0,0 +1,160 @@
+use std::path::PathBuf;
+
+use grep::regex::RegexMatcher;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+
+use crate::{
+    fs::FSTransport,
+    state::{Channel, RadioAppState, TabId},
+    views::panels::tabs::editor::{
+        replacements_in, AppStateEditorUtils, LineReplacements, TabEditorUtils,
+    },
+};
+
+use super::project_search::LineMatches;
+
+/// Replacements of the matches of a line, except for the excluded ones.
+pub fn line_replacements(
+    line: &LineMatches,
+    matcher: &RegexMatcher,
+    replacement: &str,
+    expand_groups: bool,
+    is_included: impl Fn(usize) -> bool,
+) -> LineReplacements {
+    let replacements = replacements_in(matcher, &line.text, replacement, expand_groups)
+        .into_iter()
+        .filter(|(range, _)| {
+            let index = line.ranges.iter().position(|found| found == range);
+            index.is_some_and(&is_included)
+        })
+        .collect();
+
+    LineReplacements {
+        line: line.line,
+        text: line.text.clone(),
+        replacements,
+    }
+}
+
+/// The lines to replace in a file, sorted by line.
+#[derive(Clone, PartialEq)]
+pub struct FileReplacements {
+    pub path: PathBuf,
+    pub lines: Vec<LineReplacements>,
+}
+
+/// What happened after replacing in the files.
+#[derive(Clone, PartialEq, Default)]
+pub struct ReplaceReport {
+    pub replaced: usize,
+    pub changed_files: usize,
+    /// Files that couldn't be changed, with the reason.
+    pub failures: Vec<String>,
+}
+
+/// Apply the replacements of the lines to the text of a file.
+/// Gives `None` if some of the lines changed since they were searched.
+pub fn replace_in_text(text: &str, lines: &[LineReplacements]) -> Option<String> {
+    let mut pending = lines.iter().peekable();
+    let mut replaced = String::with_capacity(text.len());
+
+    for (idx, line) in text.split_inclusive('\n').enumerate() {
+        let Some(line_replacements) = pending.next_if(|pending| pending.line == idx) else {
+            replaced.push_str(line);
+            continue;
+        };
+
+        let content = line.trim_end_matches(['\n', '\r']);
+        let line_ending = &line[content.len()..];
+        // The search doesn't see the BOM of the file
+        let (bom, content) = match content.strip_prefix('\u{feff}') {
+            Some(rest) if idx == 0 && rest == line_replacements.text => ("\u{feff}", rest),
+            _ => ("", content),
+        };
+        if content != line_replacements.text {
+            return None;
+        }
+
+        replaced.push_str(bom);
+        replaced.push_str(&line_replacements.replaced_text());
+        replaced.push_str(line_ending);
+    }
+
+    pending.next().is_none().then_some(replaced)
+}
+
+/// Replace in the open tabs of the files, so it can be undone, and write the rest of them.
+pub async fn replace_in_files(
+    mut radio_app_state: RadioAppState,
+    files: Vec<FileReplacements>,
+) -> ReplaceReport {
+    let transport = radio_app_state.read().default_transport.clone();
+    let mut report = ReplaceReport::default();
+
+    for file in files {
+        let open_tab = radio_app_state
+            .read()
+            .tabs
+            .iter()
+            .find_map(|(tab_id, tab)| {
+                let editor_tab = tab.as_text_editor()?;
+                (editor_tab.editor.path() == Some(&file.path)).then_some(*tab_id)
+            });
+
+        let result = if let Some(tab_id) = open_tab {
+            replace_in_tab(radio_app_state, tab_id, &file)
+        } else {
+            replace_in_file(&transport, &file).await
+        };
+
+        match result {
+            Ok(replaced) => {
+                report.replaced += replaced;
+                report.changed_files += 1;
+            }
+            Err(err) => report
+                .failures
+                .push(format!("{}: {err}", file.path.display())),
+        }
+    }
+
+    report
+}
+
+fn replace_in_tab(
+    mut radio_app_state: RadioAppState,
+    tab_id: TabId,
+    file: &FileReplacements,
+) -> Result<usize, String> {
+    let mut app_state = radio_app_state.write_channel(Channel::follow_tab(tab_id));
+    app_state
+        .editor_tab_mut(tab_id)
+        .editor
+        .replace_in_lines(&file.lines)
+        .ok_or_else(|| "the tab changed since the search".to_string())
+}
+
+async fn replace_in_file(
+    transport: &FSTransport,
+    file: &FileReplacements,
+) -> Result<usize, String> {
+    let content = transport
+        .read(&file.path)
+        .await
+        .map_err(|err| err.to_string())?;
+    let content = String::from_utf8(content).map_err(|_| "it's not valid UTF-8".to_string())?;
+    let replaced = replace_in_text(&content, &file.lines)
+        .ok_or_else(|| "the file changed since the search".to_string())?;
+
+    let mut writer = transport
+        .open(&file.path, OpenOptions::new().write(true).truncate(true))
+        .await
+        .map_err(|err| err.to_string())?;
+    writer
+        .write_all(replaced.as_bytes())
+        .await
+        .map_err(|err| err.to_string())?;
+    writer.flush().await.map_err(|err| err.to_string())?;
+
+    Ok(file.lines.iter().map(|line| line.replacements.len()).sum())
+}
@@ -0,0 +1,357 @@
+use std::{
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use futures::channel::mpsc::UnboundedSender;
+use grep::{
+    matcher::Matcher,
+    regex::{RegexMatcher, RegexMatcherBuilder},
+    searcher::{
+        sinks::{Lossy, UTF8},
+        BinaryDetection, Searcher, SearcherBuilder,
+    },
+};
+use ignore::{
+    overrides::{Override, OverrideBuilder},
+    WalkBuilder,
+};
+use tracing::info;
+
+use crate::views::panels::tabs::editor::FindOption;
+
+/// Most lines with matches sent by a search, the rest are left out.
+pub const MAX_MATCHED_LINES: usize = 5_000;
+
+/// Filters of the project search.
+#[derive(Clone, PartialEq)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+    /// Globs of the files to search, separated by commas.
+    pub include: String,
+    /// Globs of the files to leave out, separated by commas.
+    pub exclude: String,
+    /// Size in KB of the biggest files to search, no limit if empty.
+    pub max_file_size: String,
+    pub skip_binary: bool,
+    /// Search the content of the open tabs instead of the folders.
+    pub open_files_only: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            case_sensitive: false,
+            whole_word: false,
+            regex: false,
+            include: String::new(),
+            exclude: String::new(),
+            max_file_size: String::new(),
+            skip_binary: true,
+            open_files_only: false,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn is_enabled(&self, option: FindOption) -> bool {
+        match option {
+            FindOption::CaseSensitive => self.case_sensitive,
+            FindOption::WholeWord => self.whole_word,
+            FindOption::Regex => self.regex,
+            FindOption::InSelection => false,
+        }
+    }
+
+    pub fn toggle(&mut self, option: FindOption) {
+        match option {
+            FindOption::CaseSensitive => self.case_sensitive = !self.case_sensitive,
+            FindOption::WholeWord => self.whole_word = !self.whole_word,
+            FindOption::Regex => self.regex = !self.regex,
+            FindOption::InSelection => {}
+        }
+    }
+
+    /// Matcher of a query with the enabled options, or why it isn't valid.
+    pub fn matcher(&self, query: &str) -> Result<RegexMatcher, String> {
+        RegexMatcherBuilder::new()
+            .case_insensitive(!self.case_sensitive)
+            .word(self.whole_word)
+            .fixed_strings(!self.regex)
+            .line_terminator(Some(b'\n'))
+            .build(query)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Biggest size of the searched files in bytes.
+    pub fn max_file_size(&self) -> Result<Option<u64>, String> {
+        let max_file_size = self.max_file_size.trim();
+        if max_file_size.is_empty() {
+            return Ok(None);
+        }
+        max_file_size
+            .parse::<u64>()
+            .map(|kb| Some(kb * 1024))
+            .map_err(|_| format!("Invalid max file size `{max_file_size}`, it must be in KB"))
+    }
+
+    /// Filter of the included and excluded files of a folder.
+    pub fn file_filter(&self, root_path: &Path) -> Result<Override, String> {
+        let mut builder = OverrideBuilder::new(root_path);
+        let globs = |globs: &'_ str| {
+            globs
+                .split(',')
+                .map(str::trim)
+                .filter(|glob| !glob.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<String>>()
+        };
+        for glob in globs(&self.include) {
+            builder.add(&glob).map_err(|err| err.to_string())?;
+        }
+        for glob in globs(&self.exclude) {
+            builder
+                .add(&format!("!{glob}"))
+                .map_err(|err| err.to_string())?;
+        }
+        builder.build().map_err(|err| err.to_string())
+    }
+}
+
+/// What a search goes through.
+pub enum SearchTarget {
+    /// Every file in the folders, with the filter of each folder.
+    Folders(Vec<(PathBuf, Override)>),
+    /// The content of some files, already filtered.
+    OpenFiles(Vec<OpenFile>),
+}
+
+/// A file open in a tab, with its content as it is in the tab.
+pub struct OpenFile {
+    pub path: PathBuf,
+    pub root_path: PathBuf,
+    pub content: String,
+}
+
+/// A line with matches.
+#[derive(Clone, PartialEq)]
+pub struct LineMatches {
+    /// Index of the line in the file.
+    pub line: usize,
+    /// Text of the line, without the line break.
+    pub text: String,
+    /// Matches in the line, as byte ranges.
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl LineMatches {
+    /// Columns of a match as UTF-16 code units, like the cursor of the editor.
+    pub fn match_cols(&self, index: usize) -> Range<usize> {
+        let range = self.ranges.get(index).cloned().unwrap_or_default();
+        let start = self.text[..range.start].encode_utf16().count();
+        start..start + self.text[range].encode_utf16().count()
+    }
+}
+
+/// The lines with matches of a file.
+#[derive(Clone, PartialEq)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    /// Folder of the files explorer that contains the file.
+    pub root_path: PathBuf,
+    pub lines: Vec<LineMatches>,
+}
+
+/// Search the files of the target, leaving out the ones ignored by `.gitignore`.
+/// The files with matches are sent as they are found, until the search gets cancelled.
+pub fn search_files(
+    target: SearchTarget,
+    matcher: RegexMatcher,
+    options: SearchOptions,
+    cancelled: Arc<AtomicBool>,
+    sender: UnboundedSender<FileMatches>,
+) {
+    let binary_detection = if options.skip_binary {
+        BinaryDetection::quit(b'\x00')
+    } else {
+        BinaryDetection::none()
+    };
+    let mut searcher = SearcherBuilder::new()
+        .binary_detection(binary_detection)
+        .line_number(true)
+        .build();
+    let mut matched_lines = 0;
+
+    // Send the lines with matches of a file, returns whether to keep searching
+    let mut search = |path: PathBuf, root_path: &Path, content: Option<&str>| {
+        let max_lines = MAX_MATCHED_LINES - matched_lines;
+        let result = search_file(
+            &mut searcher,
+            &matcher,
+            &path,
+            content,
+            max_lines,
+            &cancelled,
+        );
+
+        let lines = match result {
+            Ok(lines) => lines,
+            // Files that aren't valid UTF-8 are skipped
+            Err(err) => {
+                info!("Skipped {path:?} in the search: {err}");
+                Vec::new()
+            }
+        };
+        if lines.is_empty() {
+            return !cancelled.load(Ordering::Relaxed);
+        }
+
+        matched_lines += lines.len();
+        let file = FileMatches {
+            path,
+            root_path: root_path.to_path_buf(),
+            lines,
+        };
+        sender.unbounded_send(file).is_ok()
+            && matched_lines < MAX_MATCHED_LINES
+            && !cancelled.load(Ordering::Relaxed)
+    };
+
+    match target {
+        SearchTarget::Folders(folders) => {
+            let max_file_size = options.max_file_size().ok().flatten();
+            for (root_path, file_filter) in folders {
+                let walker = WalkBuilder::new(&root_path)
+                    .overrides(file_filter)
+                    .max_filesize(max_file_size)
+                    .build();
+                for entry in walker {
+                    if cancelled.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let Ok(entry) = entry else {
+                        continue;
+                    };
+                    if !entry
+                        .file_type()
+                        .is_some_and(|file_type| file_type.is_file())
+                    {
+                        continue;
+                    }
+                    if !search(entry.into_path(), &root_path, None) {
+                        return;
+                    }
+                }
+            }
+        }
+        SearchTarget::OpenFiles(files) => {
+            for file in files {
+                if !search(file.path, &file.root_path, Some(&file.content)) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Lines with matches of a file, or of its content if it's given.
+fn search_file(
+    searcher: &mut Searcher,
+    matcher: &RegexMatcher,
+    path: &Path,
+    content: Option<&str>,
+    max_lines: usize,
+    cancelled: &AtomicBool,
+) -> io::Result<Vec<LineMatches>> {
+    let mut lines = Vec::new();
+    let mut on_line = |line_number: u64, text: &str| {
+        let text = text.trim_end_matches(['\n', '\r']);
+        let mut ranges = Vec::new();
+        matcher
+            .find_iter(text.as_bytes(), |found| {
+                if !found.is_empty() {
+                    ranges.push(found.start()..found.end());
+                }
+                true
+            })
+            .ok();
+        if !ranges.is_empty() {
+            lines.push(LineMatches {
+                line: line_number as usize - 1,
+                text: text.to_string(),
+                ranges,
+            });
+        }
+        Ok(lines.len() < max_lines && !cancelled.load(Ordering::Relaxed))
+    };
+
+    match content {
+        Some(content) => searcher.search_slice(matcher, content.as_bytes(), UTF8(&mut on_line)),
+        // Binary files are searched lossily, as they aren't valid UTF-8
+        None if searcher.binary_detection().quit_byte().is_none() => {
+            searcher.search_path(matcher, path, Lossy(&mut on_line))
+        }
+        None => searcher.search_path(matcher, path, UTF8(&mut on_line)),
+    }?;
+    Ok(lines)
+}
+
+/// Where the shown text of a line starts, leaving out the indentation.
+/// Long lines only keep some context before the match.
+fn context_start(text: &str, match_start: usize, max_context: usize) -> usize {
+    let indentation = text.len() - text.trim_start().len();
+    text[..match_start]
+        .char_indices()
+        .rev()
+        .nth(max_context)
+        .map(|(idx, _)| idx)
+        .unwrap_or(0)
+        .max(indentation)
+        .min(match_start)
+}
+
+/// Pieces of a line with matches and whether they are a match.
+pub fn match_pieces(line: &LineMatches, max_context: usize) -> Vec<(String, bool)> {
+    let first_match = line.ranges.first().map(|range| range.start).unwrap_or(0);
+    let start = context_start(&line.text, first_match, max_context);
+
+    let mut pieces = Vec::new();
+    let mut position = start;
+    if start > line.text.len() - line.text.trim_start().len() {
+        pieces.push(("…".to_string(), false));
+    }
+    for range in &line.ranges {
+        if range.start > position {
+            pieces.push((line.text[position..range.start].to_string(), false));
+        }
+        pieces.push((line.text[range.clone()].to_string(), true));
+        position = range.end;
+    }
+    pieces.push((line.text[position..].to_string(), false));
+    pieces
+}
+
+/// The text before and after a match of a line, to preview its replacement.
+pub fn match_context(
+    line: &LineMatches,
+    range: Range<usize>,
+    max_context: usize,
+) -> (String, String) {
+    let start = context_start(&line.text, range.start, max_context);
+    let ellipsis = if start > line.text.len() - line.text.trim_start().len() {
+        "…"
+    } else {
+        ""
+    };
+    (
+        format!("{ellipsis}{}", &line.text[start..range.start]),
+        line.text[range.end..].to_string(),
+    )
+}
@@ -1,84 +1,851 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use dioxus_radio::prelude::use_radio;
 use freya::prelude::*;
-use grep::{
-    regex::RegexMatcher,
-    searcher::{sinks::UTF8, BinaryDetection, SearcherBuilder},
+use futures::{channel::mpsc::unbounded, StreamExt};
+use grep::regex::RegexMatcher;
+
+use crate::{
+    state::{Channel, RadioAppState},
+    views::panels::tabs::editor::{EditorTab, FindBarButton, FindOption, TabEditorUtils},
+    Overlay, TextArea,
+};
+
+use super::{
+    project_replace::{line_replacements, replace_in_files, FileReplacements, ReplaceReport},
+    project_search::{
+        match_context, match_pieces, search_files, FileMatches, OpenFile, SearchOptions,
+        SearchTarget, MAX_MATCHED_LINES,
+    },
 };
 
-use crate::{Overlay, TextArea};
+/// Toggles of the search, with their text.
+const SEARCH_OPTIONS: [(FindOption, &str); 3] = [
+    (FindOption::CaseSensitive, "Aa"),
+    (FindOption::WholeWord, "ab|"),
+    (FindOption::Regex, ".*"),
+];
+
+/// Most chars shown before the first match of a line.
+const MAX_CONTEXT: usize = 30;
+
+/// A row of the results, every file is followed by its lines with matches,
+/// or by each one of its matches when replacing.
+#[derive(Clone, Copy, PartialEq)]
+enum SearchRow {
+    File(usize),
+    Line {
+        file: usize,
+        line: usize,
+        /// Position among all the selectable rows.
+        position: usize,
+    },
+    Match {
+        file: usize,
+        line: usize,
+        index: usize,
+        /// Position among all the selectable rows.
+        position: usize,
+    },
+}
+
+/// Matches left out of the replacement, as their file, line and match indexes.
+#[derive(Clone, PartialEq, Default)]
+struct Exclusions(HashSet<(usize, usize, usize)>);
+
+impl Exclusions {
+    fn is_included(&self, file: usize, line: usize, index: usize) -> bool {
+        !self.0.contains(&(file, line, index))
+    }
+
+    /// Whether any of the matches of a file is included.
+    fn is_file_included(&self, file: usize, file_matches: &FileMatches) -> bool {
+        match_ids(file, file_matches).any(|id| !self.0.contains(&id))
+    }
+
+    fn toggle(&mut self, file: usize, line: usize, index: usize) {
+        if !self.0.remove(&(file, line, index)) {
+            self.0.insert((file, line, index));
+        }
+    }
+
+    /// Exclude all the matches of a file, or include them back if all were excluded.
+    fn toggle_file(&mut self, file: usize, file_matches: &FileMatches) {
+        if self.is_file_included(file, file_matches) {
+            self.0.extend(match_ids(file, file_matches));
+        } else {
+            for id in match_ids(file, file_matches) {
+                self.0.remove(&id);
+            }
+        }
+    }
+}
+
+fn match_ids(
+    file: usize,
+    file_matches: &FileMatches,
+) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+    file_matches
+        .lines
+        .iter()
+        .enumerate()
+        .flat_map(move |(line, line_matches)| {
+            (0..line_matches.ranges.len()).map(move |index| (file, line, index))
+        })
+}
+
+/// Task and cancellation flag of the search in progress.
+type RunningSearch = Rc<RefCell<Option<(Task, Arc<AtomicBool>)>>>;
+
+fn cancel_search(running_search: &RunningSearch) {
+    if let Some((task, cancelled)) = running_search.borrow_mut().take() {
+        cancelled.store(true, Ordering::Relaxed);
+        task.cancel();
+    }
+}
+
+/// Open the file of a match with the match selected.
+fn open_match(
+    mut radio_app_state: RadioAppState,
+    results: Signal<Vec<FileMatches>>,
+    file: usize,
+    line: usize,
+    index: usize,
+) {
+    let results = results.read();
+    let Some(file) = results.get(file) else {
+        return;
+    };
+    let line = &file.lines[line];
+
+    let transport = radio_app_state.read().default_transport.clone();
+    let mut app_state = radio_app_state.write_channel(Channel::Global);
+    EditorTab::open_at(
+        radio_app_state,
+        &mut app_state,
+        file.path.clone(),
+        file.root_path.clone(),
+        transport.as_read(),
+        Some((line.line, line.match_cols(index))),
+    );
+}
+
+/// The open folders or tabs to search with the filters, or why the filters aren't valid.
+fn search_target(
+    radio_app_state: RadioAppState,
+    options: &SearchOptions,
+) -> Result<SearchTarget, String> {
+    let app_state = radio_app_state.read();
+    let max_file_size = options.max_file_size()?;
+
+    if !options.open_files_only {
+        let folders = app_state
+            .file_explorer
+            .folders
+            .iter()
+            .map(|folder| {
+                let root_path = folder.path().clone();
+                let file_filter = options.file_filter(&root_path)?;
+                Ok((root_path, file_filter))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        return Ok(SearchTarget::Folders(folders));
+    }
+
+    let mut files = Vec::new();
+    for tab in app_state.tabs.values() {
+        let Some(editor_tab) = tab.as_text_editor() else {
+            continue;
+        };
+        let Some((path, root_path)) = editor_tab.editor.editor_type.paths() else {
+            continue;
+        };
+        let content = editor_tab.editor.content();
+        let is_ignored = options
+            .file_filter(root_path)?
+            .matched(path, false)
+            .is_ignore();
+        let is_too_big = max_file_size.is_some_and(|max| content.len() as u64 > max);
+        if !is_ignored && !is_too_big {
+            files.push(OpenFile {
+                path: path.clone(),
+                root_path: root_path.clone(),
+                content,
+            });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(SearchTarget::OpenFiles(files))
+}
 
 #[component]
 pub fn Search() -> Element {
+    let radio_app_state = use_radio(Channel::Global);
     let mut value = use_signal(String::new);
+    let mut replacement = use_signal(String::new);
+    let mut show_replace = use_signal(|| false);
+    let mut show_filters = use_signal(|| false);
+    let mut options = use_signal(SearchOptions::default);
+    let mut matcher = use_signal(|| None::<RegexMatcher>);
+    let mut results = use_signal(Vec::<FileMatches>::new);
+    let mut exclusions = use_signal(Exclusions::default);
+    let mut report = use_signal(|| None::<ReplaceReport>);
+    let mut error = use_signal(|| None::<String>);
+    let mut is_searching = use_signal(|| false);
+    let mut selected = use_signal(|| 0);
     let mut focus = use_focus();
+    let running_search = use_hook(RunningSearch::default);
 
-    let onchange = move |v| {
-        if *value.read() != v {
-            value.set(v);
+    // Stop searching once the search is closed
+    use_drop({
+        to_owned![running_search];
+        move || cancel_search(&running_search)
+    });
+
+    let mut rows = Vec::new();
+    let mut matched_lines = 0;
+    let mut position = 0;
+    let is_replacing = show_replace();
+    for (file, file_matches) in results.read().iter().enumerate() {
+        rows.push(SearchRow::File(file));
+        for (line, line_matches) in file_matches.lines.iter().enumerate() {
+            matched_lines += 1;
+            if is_replacing {
+                for index in 0..line_matches.ranges.len() {
+                    rows.push(SearchRow::Match {
+                        file,
+                        line,
+                        index,
+                        position,
+                    });
+                    position += 1;
+                }
+            } else {
+                rows.push(SearchRow::Line {
+                    file,
+                    line,
+                    position,
+                });
+                position += 1;
+            }
         }
-    };
+    }
+    let selectable_rows = position;
+    let files_len = results.read().len();
 
-    let results = use_memo(move || {
-        let mut results = Vec::new();
+    let run_search = use_callback({
+        to_owned![running_search];
+        move |()| {
+            // Start again when the query or the filters change
+            cancel_search(&running_search);
+            results.write().clear();
+            exclusions.write().0.clear();
+            matcher.set(None);
+            report.set(None);
+            selected.set(0);
+            error.set(None);
+            is_searching.set(false);
+
+            let query = value.peek().clone();
+            if query.is_empty() {
+                return;
+            }
 
-        if value.read().is_empty() {
-            return results;
+            let options = options.peek().clone();
+            let target = options.matcher(&query).and_then(|query_matcher| {
+                let target = search_target(radio_app_state, &options)?;
+                Ok((query_matcher, target))
+            });
+            let (query_matcher, target) = match target {
+                Ok(target) => target,
+                Err(err) => {
+                    error.set(Some(err));
+                    return;
+                }
+            };
+            matcher.set(Some(query_matcher.clone()));
+
+            let cancelled = Arc::new(AtomicBool::new(false));
+            let (sender, mut receiver) = unbounded();
+
+            // Search without blocking the app
+            thread::spawn({
+                let cancelled = cancelled.clone();
+                move || search_files(target, query_matcher, options, cancelled, sender)
+            });
+
+            is_searching.set(true);
+            let task = spawn(async move {
+                while let Some(file) = receiver.next().await {
+                    results.write().push(file);
+                }
+                is_searching.set(false);
+            });
+            *running_search.borrow_mut() = Some((task, cancelled));
         }
+    });
 
-        let matcher = RegexMatcher::new_line_matcher(&value()).unwrap();
+    let onchange = move |query: String| {
+        if *value.peek() != query {
+            value.set(query);
+            run_search.call(());
+        }
+    };
 
-        let mut searcher = SearcherBuilder::new()
-            .binary_detection(BinaryDetection::quit(b'\x00'))
-            .line_number(true)
-            .build();
+    // Change a filter and search again
+    let mut set_option = move |update: &dyn Fn(&mut SearchOptions)| {
+        update(&mut options.write());
+        run_search.call(());
+    };
 
-        let path = "...";
+    let replace_all = use_callback({
+        to_owned![running_search];
+        move |()| {
+            let Some(matcher) = matcher.peek().clone() else {
+                return;
+            };
+            let files = {
+                let replacement = replacement.peek();
+                let expand_groups = options.peek().regex;
+                let exclusions = exclusions.peek();
+                results
+                    .peek()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(file, file_matches)| {
+                        let lines = file_matches
+                            .lines
+                            .iter()
+                            .enumerate()
+                            .map(|(line, line_matches)| {
+                                line_replacements(
+                                    line_matches,
+                                    &matcher,
+                                    &replacement,
+                                    expand_groups,
+                                    |index| exclusions.is_included(file, line, index),
+                                )
+                            })
+                            .filter(|line| !line.replacements.is_empty())
+                            .collect::<Vec<_>>();
+                        (!lines.is_empty()).then(|| FileReplacements {
+                            path: file_matches.path.clone(),
+                            lines,
+                        })
+                    })
+                    .collect::<Vec<FileReplacements>>()
+            };
+            if files.is_empty() {
+                return;
+            }
 
-        searcher
-            .search_path(
-                &matcher,
-                path,
-                UTF8(|a, b| {
-                    print!("{}:{}", a, b);
-                    results.push((a, b.to_string()));
-                    Ok(true)
-                }),
-            )
-            .unwrap();
+            // The results are outdated once replaced
+            cancel_search(&running_search);
+            results.write().clear();
+            exclusions.write().0.clear();
+            selected.set(0);
+            is_searching.set(false);
 
-        results
+            spawn(async move {
+                let replace_report = replace_in_files(radio_app_state, files).await;
+                report.set(Some(replace_report));
+            });
+        }
+    });
+
+    let selected_match = rows.iter().find_map(|row| match *row {
+        SearchRow::Line {
+            file,
+            line,
+            position,
+        } if position == selected() => Some((file, line, 0)),
+        SearchRow::Match {
+            file,
+            line,
+            index,
+            position,
+        } if position == selected() => Some((file, line, index)),
+        _ => None,
     });
 
     let onsubmit = move |_: String| {
-        println!("{value}");
+        if let Some((file, line, index)) = selected_match {
+            open_match(radio_app_state, results, file, line, index);
+        }
+    };
+
+    let onchange_replacement = move |text: String| {
+        if *replacement.peek() != text {
+            replacement.set(text);
+        }
     };
 
     let onkeydown = move |e: KeyboardEvent| {
         e.stop_propagation();
         focus.prevent_navigation();
+        match e.code {
+            Code::ArrowDown if selectable_rows > 0 => {
+                if selected() < selectable_rows - 1 {
+                    *selected.write() += 1;
+                } else {
+                    selected.set(0);
+                }
+            }
+            Code::ArrowUp if selectable_rows > 0 => {
+                if selected() > 0 {
+                    *selected.write() -= 1;
+                } else {
+                    selected.set(selectable_rows - 1);
+                }
+            }
+            _ => {}
+        }
+    };
+
+    let summary = if let Some(error) = error.read().as_ref() {
+        error.clone()
+    } else if let Some(report) = report.read().as_ref() {
+        format!(
+            "Replaced {} matches in {} files",
+            report.replaced, report.changed_files
+        )
+    } else if value.read().is_empty() {
+        "Search in the open folders".to_string()
+    } else if *is_searching.read() {
+        format!("Searching... {matched_lines} results in {files_len} files")
+    } else if matched_lines >= MAX_MATCHED_LINES {
+        format!("Showing the first {matched_lines} results in {files_len} files")
+    } else {
+        format!("{matched_lines} results in {files_len} files")
+    };
+    let summary_color = if error.read().is_some() {
+        "rgb(251, 60, 44)"
+    } else {
+        "rgb(165, 165, 165)"
+    };
+    let failures = report
+        .read()
+        .as_ref()
+        .map(|report| report.failures.clone())
+        .unwrap_or_default();
+    let search_options = {
+        let options = options.read();
+        SEARCH_OPTIONS.map(|(option, text)| (option, text, options.is_enabled(option)))
     };
 
     rsx!(
         Overlay {
+            width: 750.0,
             rect {
                 onkeydown,
-                ScrollView {
-                    height: "400",
+                spacing: "5",
+                rect {
                     width: "100%",
-                    for (line, path) in &*results.read() {
+                    direction: "horizontal",
+                    cross_align: "center",
+                    spacing: "4",
+                    rect {
+                        width: "fill",
+                        TextArea {
+                            placeholder: "Search...",
+                            value: "{value}",
+                            onchange,
+                            onsubmit,
+                        }
+                    }
+                    for (option, text, is_enabled) in search_options {
+                        FindBarButton {
+                            key: "{option:?}",
+                            is_enabled,
+                            onclick: move |_| set_option(&|options| options.toggle(option)),
+                            label {
+                                "{text}"
+                            }
+                        }
+                    }
+                    FindBarButton {
+                        is_enabled: show_filters(),
+                        onclick: move |_| show_filters.toggle(),
+                        label {
+                            "Filters"
+                        }
+                    }
+                    FindBarButton {
+                        is_enabled: show_replace(),
+                        onclick: move |_| {
+                            show_replace.toggle();
+                            selected.set(0);
+                        },
+                        label {
+                            "Replace"
+                        }
+                    }
+                }
+                if show_filters() {
+                    rect {
+                        width: "100%",
+                        direction: "horizontal",
+                        cross_align: "center",
+                        spacing: "4",
+                        rect {
+                            width: "fill",
+                            TextArea {
+                                placeholder: "Files to include, e.g src/**, *.rs",
+                                value: "{options.read().include}",
+                                onchange: move |include: String| {
+                                    if options.peek().include != include {
+                                        set_option(&|options| options.include.clone_from(&include));
+                                    }
+                                },
+                                onsubmit: move |_| {},
+                                auto_focus: false,
+                            }
+                        }
                         rect {
-                            key: "{line}{path}",
+                            width: "fill",
+                            TextArea {
+                                placeholder: "Files to exclude",
+                                value: "{options.read().exclude}",
+                                onchange: move |exclude: String| {
+                                    if options.peek().exclude != exclude {
+                                        set_option(&|options| options.exclude.clone_from(&exclude));
+                                    }
+                                },
+                                onsubmit: move |_| {},
+                                auto_focus: false,
+                            }
+                        }
+                        rect {
+                            width: "130",
+                            TextArea {
+                                placeholder: "Max size (KB)",
+                                value: "{options.read().max_file_size}",
+                                onchange: move |max_file_size: String| {
+                                    if options.peek().max_file_size != max_file_size {
+                                        set_option(&|options| {
+                                            options.max_file_size.clone_from(&max_file_size)
+                                        });
+                                    }
+                                },
+                                onsubmit: move |_| {},
+                                auto_focus: false,
+                            }
+                        }
+                        FindBarButton {
+                            is_enabled: options.read().skip_binary,
+                            onclick: move |_| {
+                                set_option(&|options| options.skip_binary = !options.skip_binary)
+                            },
                             label {
-                                "{line}:{path}"
+                                "Skip binary"
+                            }
+                        }
+                        FindBarButton {
+                            is_enabled: options.read().open_files_only,
+                            onclick: move |_| {
+                                set_option(&|options| {
+                                    options.open_files_only = !options.open_files_only
+                                })
+                            },
+                            label {
+                                "Open files"
+                            }
+                        }
+                    }
+                }
+                if show_replace() {
+                    rect {
+                        width: "100%",
+                        direction: "horizontal",
+                        cross_align: "center",
+                        spacing: "4",
+                        rect {
+                            width: "fill",
+                            TextArea {
+                                placeholder: "Replace...",
+                                value: "{replacement}",
+                                onchange: onchange_replacement,
+                                onsubmit: move |_| replace_all.call(()),
+                                auto_focus: false,
+                            }
+                        }
+                        FindBarButton {
+                            onclick: move |_| replace_all.call(()),
+                            label {
+                                "Replace All"
+                            }
+                        }
+                    }
+                }
+                label {
+                    margin: "0 6",
+                    color: summary_color,
+                    max_lines: "2",
+                    text_overflow: "ellipsis",
+                    "{summary}"
+                }
+                for (i, failure) in failures.into_iter().enumerate() {
+                    label {
+                        key: "{i}",
+                        margin: "0 6",
+                        color: "rgb(251, 60, 44)",
+                        max_lines: "1",
+                        text_overflow: "ellipsis",
+                        "{failure}"
+                    }
+                }
+                VirtualScrollView {
+                    height: "400",
+                    width: "100%",
+                    length: rows.len(),
+                    item_size: 28.0,
+                    builder_args: (
+                        rows,
+                        results,
+                        exclusions,
+                        matcher,
+                        replacement,
+                        options,
+                        selected,
+                        radio_app_state,
+                    ),
+                    direction: "vertical",
+                    scroll_with_arrows: false,
+                    builder: search_row_builder
+                }
+            }
+        }
+    )
+}
+
+type SearchBuilderOptions = (
+    Vec<SearchRow>,
+    Signal<Vec<FileMatches>>,
+    Signal<Exclusions>,
+    Signal<Option<RegexMatcher>>,
+    Signal<String>,
+    Signal<SearchOptions>,
+    Signal<usize>,
+    RadioAppState,
+);
+
+fn search_row_builder(index: usize, values: &Option<SearchBuilderOptions>) -> Element {
+    let (rows, results, exclusions, matcher, replacement, options, selected, radio_app_state) =
+        values.as_ref().unwrap();
+    let (results, mut exclusions, radio_app_state) = (*results, *exclusions, *radio_app_state);
+    let file_matches = results.read();
+    let row_background = |position: usize| {
+        if position == selected() {
+            "rgb(29, 32, 33)"
+        } else {
+            "none"
+        }
+    };
+
+    match rows[index] {
+        SearchRow::File(file) => {
+            let file_matches = &file_matches[file];
+            let path = file_matches
+                .path
+                .strip_prefix(&file_matches.root_path)
+                .unwrap_or(&file_matches.path)
+                .to_string_lossy()
+                .to_string();
+            let lines_len = file_matches.lines.len();
+            let is_replacing = rows
+                .get(index + 1)
+                .is_some_and(|row| matches!(row, SearchRow::Match { .. }));
+            let is_included = exclusions.read().is_file_included(file, file_matches);
+
+            let ontoggle = move |_| {
+                if let Some(file_matches) = results.read().get(file) {
+                    exclusions.write().toggle_file(file, file_matches);
+                }
+            };
+
+            rsx!(
+                rect {
+                    key: "{path}",
+                    width: "100%",
+                    height: "28",
+                    padding: "0 6",
+                    direction: "horizontal",
+                    cross_align: "center",
+                    spacing: "8",
+                    if is_replacing {
+                        MatchToggle {
+                            is_included,
+                            onclick: ontoggle,
+                        }
+                    }
+                    label {
+                        font_weight: "bold",
+                        max_lines: "1",
+                        text_overflow: "ellipsis",
+                        "{path}"
+                    }
+                    label {
+                        color: "rgb(135, 135, 135)",
+                        "{lines_len}"
+                    }
+                }
+            )
+        }
+        SearchRow::Line {
+            file,
+            line,
+            position,
+        } => {
+            let line_matches = &file_matches[file].lines[line];
+            let line_number = line_matches.line + 1;
+            let pieces = match_pieces(line_matches, MAX_CONTEXT);
+            let background = row_background(position);
+            let onclick = move |_| open_match(radio_app_state, results, file, line, 0);
+
+            rsx!(
+                rect {
+                    key: "{file}-{line}",
+                    onclick,
+                    background,
+                    width: "100%",
+                    height: "28",
+                    padding: "0 6 0 16",
+                    corner_radius: "8",
+                    direction: "horizontal",
+                    cross_align: "center",
+                    label {
+                        width: "50",
+                        color: "rgb(135, 135, 135)",
+                        "{line_number}"
+                    }
+                    paragraph {
+                        width: "fill",
+                        max_lines: "1",
+                        text_overflow: "ellipsis",
+                        for (i, (piece, is_match)) in pieces.into_iter().enumerate() {
+                            text {
+                                key: "{i}",
+                                color: if is_match { "rgb(255, 197, 61)" } else { "rgb(215, 215, 215)" },
+                                font_weight: if is_match { "bold" } else { "normal" },
+                                "{piece}"
                             }
                         }
                     }
                 }
-                TextArea {
-                    placeholder: "Search...",
-                    value: "{value}",
-                    onchange,
-                    onsubmit,
+            )
+        }
+        SearchRow::Match {
+            file,
+            line,
+            index,
+            position,
+        } => {
+            let line_matches = &file_matches[file].lines[line];
+            let line_number = line_matches.line + 1;
+            let range = line_matches.ranges[index].clone();
+            let (before, after) = match_context(line_matches, range.clone(), MAX_CONTEXT);
+            let matched = &line_matches.text[range];
+            let replaced = matcher
+                .read()
+                .as_ref()
+                .and_then(|matcher| {
+                    let expand_groups = options.read().regex;
+                    line_replacements(
+                        line_matches,
+                        matcher,
+                        &replacement.read(),
+                        expand_groups,
+                        |i| i == index,
+                    )
+                    .replacements
+                    .pop()
+                })
+                .map(|(_, text)| text)
+                .unwrap_or_default();
+            let is_included = exclusions.read().is_included(file, line, index);
+            let background = row_background(position);
+
+            let ontoggle = move |_| exclusions.write().toggle(file, line, index);
+            let onclick = move |_| open_match(radio_app_state, results, file, line, index);
+
+            rsx!(
+                rect {
+                    key: "{file}-{line}-{index}",
+                    background,
+                    width: "100%",
+                    height: "28",
+                    padding: "0 6 0 16",
+                    corner_radius: "8",
+                    direction: "horizontal",
+                    cross_align: "center",
+                    MatchToggle {
+                        is_included,
+                        onclick: ontoggle,
+                    }
+                    rect {
+                        onclick,
+                        width: "fill",
+                        direction: "horizontal",
+                        cross_align: "center",
+                        label {
+                            width: "50",
+                            color: "rgb(135, 135, 135)",
+                            "{line_number}"
+                        }
+                        paragraph {
+                            width: "fill",
+                            max_lines: "1",
+                            text_overflow: "ellipsis",
+                            text {
+                                color: "rgb(215, 215, 215)",
+                                "{before}"
+                            }
+                            text {
+                                color: "rgb(251, 60, 44)",
+                                decoration: "line-through",
+                                "{matched}"
+                            }
+                            if is_included {
+                                text {
+                                    color: "rgb(90, 200, 110)",
+                                    font_weight: "bold",
+                                    "{replaced}"
+                                }
+                            }
+                            text {
+                                color: "rgb(215, 215, 215)",
+                                "{after}"
+                            }
+                        }
+                    }
                 }
+            )
+        }
+    }
+}
+
+/// Checkbox to include or exclude matches from the replacement.
+#[allow(non_snake_case)]
+#[component]
+fn MatchToggle(is_included: bool, onclick: EventHandler<()>) -> Element {
+    let mark = if is_included { "☑" } else { "☐" };
+
+    rsx!(
+        rect {
+            onclick: move |_| onclick.call(()),
+            width: "24",
+            main_align: "center",
+            label {
+                "{mark}"
             }
         }
     )